@@ -6,10 +6,44 @@ pub struct Config {
     pub host: String,
     pub port: u16,
     pub database_url: String,
-    pub jwt_secret: String,
-    pub jwt_expiration_hours: i64,
+    pub jwt_algorithm: String, // EdDSA or ES256, used for newly generated signing keys
+    pub access_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i64,
+    pub oauth_access_token_ttl_minutes: i64, // Access tokens issued to partner portals
+    pub keystore_master_key: String,
+    pub document_store: String, // filesystem, ipfs or s3
+    pub document_store_dir: String,
     pub ipfs_url: String,
+    pub s3_endpoint: Option<String>, // Any S3-compatible service, e.g. MinIO
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
     pub blockchain_node_url: String,
+    pub public_base_url: String,
+    pub frontend_url: String, // Base of links in account emails
+    pub mail_transport: String, // log, file or smtp
+    pub mail_outbox_dir: String,
+    pub mail_from: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub email_verification_ttl_hours: i64,
+    pub password_reset_ttl_minutes: i64,
+    pub password_min_length: usize,
+    pub password_blocklist_file: Option<String>, // Extra common or breached passwords, one per line
+    pub password_hash_algorithm: String, // bcrypt or argon2id, used for new and upgraded hashes
+    pub bcrypt_cost: u32,
+    pub totp_required_roles: Vec<String>, // Roles that must enroll in two-factor authentication
+    pub login_max_failed_attempts: u32, // Per account, before a temporary lockout
+    pub login_max_failed_attempts_per_ip: u32,
+    pub login_lockout_minutes: i64,
+    pub wallet_challenges_per_ip: u32, // Per lockout period, before further requests are refused
+    pub trust_proxy_headers: bool, // Take the client IP from X-Forwarded-For
+    pub trusted_proxy_hops: usize, // Proxies in front of the server, each appending to X-Forwarded-For
+    pub expiry_check_interval_secs: u64,
+    pub expiry_notice_days: Vec<i32>, // Lead times for expiry reminders; empty disables them
     pub environment: String,
 }
 
@@ -22,15 +56,99 @@ impl Config {
                 .parse()?,
             database_url: env::var("DATABASE_URL")
                 .expect("DATABASE_URL must be set"),
-            jwt_secret: env::var("JWT_SECRET")
-                .expect("JWT_SECRET must be set"),
-            jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
-                .unwrap_or_else(|_| "24".to_string())
+            jwt_algorithm: env::var("JWT_ALGORITHM")
+                .unwrap_or_else(|_| "EdDSA".to_string()),
+            access_token_ttl_minutes: env::var("ACCESS_TOKEN_TTL_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()?,
+            refresh_token_ttl_days: env::var("REFRESH_TOKEN_TTL_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
                 .parse()?,
+            oauth_access_token_ttl_minutes: env::var("OAUTH_ACCESS_TOKEN_TTL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            keystore_master_key: env::var("KEYSTORE_MASTER_KEY")
+                .expect("KEYSTORE_MASTER_KEY must be set"),
+            document_store: env::var("DOCUMENT_STORE")
+                .unwrap_or_else(|_| "filesystem".to_string()),
+            document_store_dir: env::var("DOCUMENT_STORE_DIR")
+                .unwrap_or_else(|_| "./documents".to_string()),
             ipfs_url: env::var("IPFS_URL")
                 .unwrap_or_else(|_| "http://127.0.0.1:5001".to_string()),
+            s3_endpoint: env::var("S3_ENDPOINT").ok().filter(|v| !v.is_empty()),
+            s3_bucket: env::var("S3_BUCKET").ok().filter(|v| !v.is_empty()),
+            s3_region: env::var("S3_REGION")
+                .unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_access_key_id: env::var("S3_ACCESS_KEY_ID").ok().filter(|v| !v.is_empty()),
+            s3_secret_access_key: env::var("S3_SECRET_ACCESS_KEY").ok().filter(|v| !v.is_empty()),
             blockchain_node_url: env::var("BLOCKCHAIN_NODE_URL")
                 .unwrap_or_else(|_| "ws://127.0.0.1:9944".to_string()),
+            public_base_url: env::var("PUBLIC_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+            frontend_url: env::var("FRONTEND_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            mail_transport: env::var("MAIL_TRANSPORT")
+                .unwrap_or_else(|_| "log".to_string()),
+            mail_outbox_dir: env::var("MAIL_OUTBOX_DIR")
+                .unwrap_or_else(|_| "./mail-outbox".to_string()),
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "Safari Skills Passport <no-reply@safariskills.local>".to_string()),
+            smtp_host: env::var("SMTP_HOST").ok().filter(|v| !v.is_empty()),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()?,
+            smtp_username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+            smtp_password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+            email_verification_ttl_hours: env::var("EMAIL_VERIFICATION_TTL_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()?,
+            password_reset_ttl_minutes: env::var("PASSWORD_RESET_TTL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()?,
+            password_blocklist_file: env::var("PASSWORD_BLOCKLIST_FILE").ok().filter(|v| !v.is_empty()),
+            password_hash_algorithm: env::var("PASSWORD_HASH_ALGORITHM")
+                .unwrap_or_else(|_| "bcrypt".to_string()),
+            bcrypt_cost: env::var("BCRYPT_COST")
+                .unwrap_or_else(|_| "12".to_string())
+                .parse()?,
+            totp_required_roles: env::var("TOTP_REQUIRED_ROLES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|role| !role.is_empty())
+                .map(str::to_string)
+                .collect(),
+            login_max_failed_attempts: env::var("LOGIN_MAX_FAILED_ATTEMPTS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            login_max_failed_attempts_per_ip: env::var("LOGIN_MAX_FAILED_ATTEMPTS_PER_IP")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()?,
+            login_lockout_minutes: env::var("LOGIN_LOCKOUT_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()?,
+            wallet_challenges_per_ip: env::var("WALLET_CHALLENGES_PER_IP")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            trust_proxy_headers: env::var("TRUST_PROXY_HEADERS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            trusted_proxy_hops: env::var("TRUSTED_PROXY_HOPS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()?,
+            expiry_check_interval_secs: env::var("EXPIRY_CHECK_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()?,
+            expiry_notice_days: env::var("EXPIRY_NOTICE_DAYS")
+                .unwrap_or_else(|_| "30,7".to_string())
+                .split(',')
+                .map(str::trim)
+                .filter(|days| !days.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
             environment: env::var("ENVIRONMENT")
                 .unwrap_or_else(|_| "development".to_string()),
         })
@@ -0,0 +1,312 @@
+use axum::{
+    extract::{State, Path, Query},
+    Json,
+};
+use std::sync::Arc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use common::{
+    AccreditationDecisionRequest, AccreditationStatus, InstitutionRegistrant, InstitutionReview,
+    InstitutionReviewListResponse, JwtSigningKey, JwtSigningKeyListResponse, FailedLoginAttempt,
+    FailedLoginListResponse, DocumentKeyRotationResponse, AppError, AppResult,
+};
+use database::{
+    AccreditationAuthorityRepository, FailedLoginDb, InstitutionDb, InstitutionRepository, JwtSigningKeyDb,
+    UserRepository,
+};
+use crate::{services::AppState, middleware::auth::AuthUser};
+use crate::services::convert::{institution_from_db, authority_from_db, accreditation_status_name};
+
+/// Build the review view of an institution, flagging reused accreditation numbers
+async fn institution_review(state: &AppState, institution: InstitutionDb) -> AppResult<InstitutionReview> {
+    let registered_by = state.user_repo
+        .get_user_by_id(institution.user_id)
+        .await?
+        .map(|user| InstitutionRegistrant {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+        });
+
+    let shares_accreditation_number_with = match &institution.accreditation_number {
+        Some(number) if !number.trim().is_empty() => state.institution_repo
+            .get_institutions_by_accreditation_number(number.trim())
+            .await?
+            .into_iter()
+            .filter(|other| other.id != institution.id)
+            .map(|other| other.institution_name)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let accreditation_authority = match institution.accreditation_authority_id {
+        Some(authority_id) => state.authority_repo
+            .get_authority_by_id(authority_id)
+            .await?
+            .map(authority_from_db),
+        None => None,
+    };
+
+    Ok(InstitutionReview {
+        accreditation_authority,
+        review_notes: institution.review_notes.clone(),
+        reviewed_by: institution.reviewed_by,
+        reviewed_at: institution.reviewed_at,
+        institution: institution_from_db(institution),
+        registered_by,
+        shares_accreditation_number_with,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct InstitutionListQuery {
+    pub status: Option<AccreditationStatus>, // Defaults to pending
+}
+
+pub async fn list_institutions(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_, _): AuthUser,
+    Query(query): Query<InstitutionListQuery>,
+) -> Result<Json<InstitutionReviewListResponse>, AppError> {
+    let status = query.status.unwrap_or(AccreditationStatus::Pending);
+    let institutions_db = state.institution_repo
+        .get_institutions_by_accreditation_status(&accreditation_status_name(&status))
+        .await?;
+
+    let mut institutions = Vec::with_capacity(institutions_db.len());
+    for institution in institutions_db {
+        institutions.push(institution_review(&state, institution).await?);
+    }
+
+    let total = institutions.len();
+    Ok(Json(InstitutionReviewListResponse { institutions, total }))
+}
+
+pub async fn get_institution(
+    State(state): State<Arc<AppState>>,
+    Path(institution_id): Path<Uuid>,
+    AuthUser(_, _): AuthUser,
+) -> Result<Json<InstitutionReview>, AppError> {
+    let institution = state.institution_repo
+        .get_institution_by_id(institution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
+
+    Ok(Json(institution_review(&state, institution).await?))
+}
+
+/// Apply an accreditation decision if the institution is in one of the `from` states
+async fn review(
+    state: &AppState,
+    institution_id: Uuid,
+    reviewer_id: Uuid,
+    from: &[AccreditationStatus],
+    to: AccreditationStatus,
+    notes: Option<String>,
+) -> AppResult<InstitutionReview> {
+    let notes = notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+    if to != AccreditationStatus::Approved && notes.is_none() {
+        return Err(AppError::Validation("Notes are required for this decision".to_string()));
+    }
+
+    let current = state.institution_repo
+        .get_institution_by_id(institution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
+
+    let from: Vec<String> = from.iter().map(accreditation_status_name).collect();
+    let from: Vec<&str> = from.iter().map(String::as_str).collect();
+    let to_name = accreditation_status_name(&to);
+
+    let updated = state.institution_repo
+        .review_accreditation(institution_id, &from, &to_name, notes.as_deref(), reviewer_id)
+        .await?
+        .ok_or_else(|| AppError::Validation(format!(
+            "Cannot mark a {} institution as {}",
+            current.accreditation_status, to_name,
+        )))?;
+
+    if let Some(owner) = state.user_repo.get_user_by_id(updated.user_id).await? {
+        let body = match &updated.review_notes {
+            Some(notes) => format!("{} is now {}. Reviewer notes: {}", updated.institution_name, to_name, notes),
+            None => format!("{} is now {}.", updated.institution_name, to_name),
+        };
+        if let Err(e) = state.notifier
+            .notify(&owner, &format!("Accreditation {}", to_name), &body)
+            .await
+        {
+            tracing::warn!("Failed to notify institution {}: {}", updated.id, e);
+        }
+    }
+
+    tracing::info!(
+        institution_id = %updated.id,
+        reviewer_id = %reviewer_id,
+        "Institution accreditation changed from {} to {}",
+        current.accreditation_status,
+        to_name,
+    );
+
+    institution_review(state, updated).await
+}
+
+pub async fn approve_institution(
+    State(state): State<Arc<AppState>>,
+    Path(institution_id): Path<Uuid>,
+    AuthUser(admin_id, _): AuthUser,
+    Json(payload): Json<AccreditationDecisionRequest>,
+) -> Result<Json<InstitutionReview>, AppError> {
+    // Suspended institutions are reinstated by approving them again
+    let review = review(
+        &state,
+        institution_id,
+        admin_id,
+        &[AccreditationStatus::Pending, AccreditationStatus::Rejected, AccreditationStatus::Suspended],
+        AccreditationStatus::Approved,
+        payload.notes,
+    ).await?;
+
+    Ok(Json(review))
+}
+
+pub async fn reject_institution(
+    State(state): State<Arc<AppState>>,
+    Path(institution_id): Path<Uuid>,
+    AuthUser(admin_id, _): AuthUser,
+    Json(payload): Json<AccreditationDecisionRequest>,
+) -> Result<Json<InstitutionReview>, AppError> {
+    let review = review(
+        &state,
+        institution_id,
+        admin_id,
+        &[AccreditationStatus::Pending],
+        AccreditationStatus::Rejected,
+        payload.notes,
+    ).await?;
+
+    Ok(Json(review))
+}
+
+pub async fn suspend_institution(
+    State(state): State<Arc<AppState>>,
+    Path(institution_id): Path<Uuid>,
+    AuthUser(admin_id, _): AuthUser,
+    Json(payload): Json<AccreditationDecisionRequest>,
+) -> Result<Json<InstitutionReview>, AppError> {
+    let review = review(
+        &state,
+        institution_id,
+        admin_id,
+        &[AccreditationStatus::Approved],
+        AccreditationStatus::Suspended,
+        payload.notes,
+    ).await?;
+
+    Ok(Json(review))
+}
+
+fn signing_key_from_db(key: JwtSigningKeyDb) -> JwtSigningKey {
+    JwtSigningKey {
+        kid: key.id.to_string(),
+        algorithm: key.algorithm,
+        current: key.rotated_out_at.is_none(),
+        created_at: key.created_at,
+        rotated_out_at: key.rotated_out_at,
+    }
+}
+
+pub async fn list_signing_keys(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_, _): AuthUser,
+) -> Result<Json<JwtSigningKeyListResponse>, AppError> {
+    let keys: Vec<JwtSigningKey> = state.auth_service
+        .verification_keys()
+        .await?
+        .into_iter()
+        .map(signing_key_from_db)
+        .collect();
+
+    let total = keys.len();
+    Ok(Json(JwtSigningKeyListResponse { keys, total }))
+}
+
+/// Sign new access tokens with a fresh key; tokens signed by the old key stay valid until they expire
+pub async fn rotate_signing_key(
+    State(state): State<Arc<AppState>>,
+    AuthUser(admin_id, _): AuthUser,
+) -> Result<Json<JwtSigningKey>, AppError> {
+    let key = state.auth_service.rotate_signing_key().await?;
+    tracing::info!(admin_id = %admin_id, kid = %key.id, "Admin rotated the JWT signing key");
+
+    Ok(Json(signing_key_from_db(key)))
+}
+
+/// Replace every document wrapping key, rewrapping the credential document keys under the new ones
+pub async fn rotate_document_keys(
+    State(state): State<Arc<AppState>>,
+    AuthUser(admin_id, _): AuthUser,
+) -> Result<Json<DocumentKeyRotationResponse>, AppError> {
+    let (rotated_keys, rewrapped_document_keys) = state.document_key_service.rotate_wrapping_keys().await?;
+    tracing::info!(admin_id = %admin_id, rotated_keys, rewrapped_document_keys, "Admin rotated document wrapping keys");
+
+    Ok(Json(DocumentKeyRotationResponse { rotated_keys, rewrapped_document_keys }))
+}
+
+#[derive(Deserialize)]
+pub struct FailedLoginQuery {
+    pub email: Option<String>,
+    pub limit: Option<i64>, // Defaults to 100, at most 1000
+}
+
+fn failed_login_from_db(attempt: FailedLoginDb) -> FailedLoginAttempt {
+    FailedLoginAttempt {
+        id: attempt.id,
+        email: attempt.email,
+        user_id: attempt.user_id,
+        ip_address: attempt.ip_address,
+        reason: attempt.reason,
+        created_at: attempt.created_at,
+    }
+}
+
+/// Audit trail of rejected password logins, most recent first
+pub async fn list_failed_logins(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_, _): AuthUser,
+    Query(query): Query<FailedLoginQuery>,
+) -> Result<Json<FailedLoginListResponse>, AppError> {
+    let email = query.email.map(|email| email.trim().to_lowercase());
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let attempts: Vec<FailedLoginAttempt> = state.login_guard
+        .failed_logins(email.as_deref(), limit)
+        .await?
+        .into_iter()
+        .map(failed_login_from_db)
+        .collect();
+
+    let total = attempts.len();
+    Ok(Json(FailedLoginListResponse { attempts, total }))
+}
+
+/// Lift a login lockout so the user can try their password again straight away
+pub async fn unlock_user(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<Uuid>,
+    AuthUser(admin_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    state.login_guard.unlock_account(&user.email.to_lowercase()).await;
+    tracing::info!(admin_id = %admin_id, user_id = %user_id, "Admin unlocked login");
+
+    Ok(Json(serde_json::json!({
+        "message": "Login unlocked",
+        "user_id": user_id
+    })))
+}
@@ -6,9 +6,18 @@ use std::sync::Arc;
 use uuid::Uuid;
 use chrono::Utc;
 
-use common::{RegisterUserRequest, LoginRequest, AuthResponse, User, UserRole, AppError};
+use common::{
+    RegisterUserRequest, LoginRequest, RefreshTokenRequest, VerifyEmailRequest, ForgotPasswordRequest,
+    ResetPasswordRequest, ChangePasswordRequest, AuthResponse, User, UserRole, AppError, LoginResponse, MfaChallengeResponse,
+    MfaLoginRequest, TotpSetupResponse, TotpCodeRequest, RecoveryCodesResponse, TwoFactorStatus,
+    WalletChallengeRequest, WalletChallengeResponse, WalletLoginRequest,
+};
 use database::{UserDb, UserRepository};
-use crate::services::AppState;
+use crate::{
+    services::{AppState, session::{LOGIN_PASSWORD, LOGIN_WALLET}},
+    middleware::{auth::{AuthSession, AuthUser}, client_ip::ClientIp},
+};
+use crate::services::convert::{parse_user_role, user_from_db, user_role_name};
 
 pub async fn register(
     State(state): State<Arc<AppState>>,
@@ -18,14 +27,20 @@ pub async fn register(
     if payload.email.is_empty() || payload.password.is_empty() || payload.name.is_empty() {
         return Err(AppError::Validation("All fields are required".to_string()));
     }
+    state.auth_service.check_password_policy(&payload.password, &payload.email, &payload.name)?;
+
+    // Admins are promoted out of band, never self-registered
+    if payload.role == UserRole::Admin {
+        return Err(AppError::Validation("Cannot register as an admin".to_string()));
+    }
 
     // Check if user already exists
-    if let Some(_) = state.user_repo.get_user_by_email(&payload.email).await? {
+    if state.user_repo.get_user_by_email(&payload.email).await?.is_some() {
         return Err(AppError::UserExists);
     }
 
-    // Generate wallet address
-    let wallet_address = crate::services::blockchain::BlockchainService::generate_wallet_address()?;
+    // Generate a custodial wallet; its mnemonic is stored encrypted along with the user
+    let (wallet_address, phrase) = crate::services::blockchain::BlockchainService::generate_wallet()?;
 
     // Hash password
     let password_hash = state.auth_service.hash_password(&payload.password)?;
@@ -40,20 +55,25 @@ pub async fn register(
         email: payload.email.clone(),
         password_hash,
         name: payload.name.clone(),
-        role: format!("{:?}", payload.role).to_lowercase(),
+        role: user_role_name(&payload.role),
         is_verified: false,
+        wallet_custody: "custodial".to_string(),
         created_at: now,
         updated_at: now,
     };
 
-    let created_user = state.user_repo.create_user(&user_db).await?;
+    let wallet_key = state.keystore.seal_custodial_wallet(user_id, &wallet_address, &phrase)?;
+    let created_user = state.user_repo.create_user_with_wallet_key(&user_db, &wallet_key).await?;
 
-    // Generate JWT token
-    let token = state.auth_service.generate_token(
-        created_user.id,
-        created_user.email.clone(),
-        payload.role.clone(),
-    )?;
+    // Registration succeeds even if the mail cannot be sent; the user can ask for another
+    if let Err(e) = state.email_token_service.send_verification_email(&created_user).await {
+        tracing::warn!("Failed to send verification email to user {}: {}", created_user.id, e);
+    }
+
+    // Start a session
+    let tokens = state.session_service
+        .start_session(created_user.id, created_user.email.clone(), payload.role.clone(), LOGIN_PASSWORD, false)
+        .await?;
 
     // Convert to User model
     let user = User {
@@ -67,39 +87,91 @@ pub async fn register(
         updated_at: created_user.updated_at,
     };
 
-    Ok(Json(AuthResponse { token, user }))
+    Ok(Json(AuthResponse {
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+        user,
+    }))
 }
 
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, AppError> {
+) -> Result<Json<LoginResponse>, AppError> {
+    // Refuse accounts and addresses that are backing off before doing any bcrypt work
+    let attempt_email = payload.email.trim().to_lowercase();
+    state.login_guard.check(&attempt_email, ip).await?;
+
     // Find user
-    let user_db = state.user_repo
-        .get_user_by_email(&payload.email)
-        .await?
-        .ok_or_else(|| AppError::Authentication("Invalid credentials".to_string()))?;
+    let Some(user_db) = state.user_repo.get_user_by_email(&payload.email).await? else {
+        // Take as long as a wrong password would, so timing does not reveal the account is unknown
+        state.auth_service.verify_dummy_password(&payload.password);
+        state.login_guard.record_failure(&attempt_email, None, ip).await?;
+        return Err(AppError::Authentication("Invalid credentials".to_string()));
+    };
 
     // Verify password
     let valid = state.auth_service.verify_password(&payload.password, &user_db.password_hash)?;
     if !valid {
+        state.login_guard.record_failure(&attempt_email, Some(user_db.id), ip).await?;
         return Err(AppError::Authentication("Invalid credentials".to_string()));
     }
 
-    // Parse role
-    let role: UserRole = match user_db.role.as_str() {
-        "professional" => UserRole::Professional,
-        "institution" => UserRole::Institution,
-        "employer" => UserRole::Employer,
-        _ => return Err(AppError::Internal("Invalid role".to_string())),
+    if state.auth_service.needs_rehash(&user_db.password_hash) {
+        upgrade_password_hash(&state, user_db.id, &payload.password).await;
+    }
+
+    // With two-factor authentication the counter is only reset once the
+    // second step succeeds, so codes cannot be guessed between password logins
+    let response = start_login(&state, user_db, LOGIN_PASSWORD).await?;
+    if let LoginResponse::Authenticated(_) = &response {
+        state.login_guard.record_success(&attempt_email).await;
+    }
+
+    Ok(Json(response))
+}
+
+/// The key failed attempts on an account are counted under
+pub fn attempt_email(user: &UserDb) -> String {
+    user.email.trim().to_lowercase()
+}
+
+/// Re-hash a password that was stored with an older cost or algorithm. A
+/// failure only delays the upgrade to a later login.
+async fn upgrade_password_hash(state: &AppState, user_id: Uuid, password: &str) {
+    let result = match state.auth_service.hash_password(password) {
+        Ok(password_hash) => state.user_repo.update_password(user_id, &password_hash).await,
+        Err(e) => Err(e),
     };
 
-    // Generate JWT token
-    let token = state.auth_service.generate_token(
-        user_db.id,
-        user_db.email.clone(),
-        role.clone(),
-    )?;
+    match result {
+        Ok(()) => tracing::info!(user_id = %user_id, "Upgraded password hash"),
+        Err(e) => tracing::warn!("Failed to upgrade password hash for user {}: {}", user_id, e),
+    }
+}
+
+/// Finish a login whose first factor was accepted: start a session, or hand out
+/// a challenge if the account has two-factor authentication
+async fn start_login(state: &AppState, user_db: UserDb, login_method: &str) -> Result<LoginResponse, AppError> {
+    // Parse role
+    let role = parse_user_role(&user_db.role)?;
+
+    // Accounts with two-factor authentication finish logging in at /api/auth/login/mfa
+    if state.mfa_service.is_enabled(user_db.id).await? {
+        let (challenge_token, expires_in) = state.mfa_service.create_challenge(user_db.id, login_method).await?;
+        return Ok(LoginResponse::MfaRequired(MfaChallengeResponse {
+            mfa_required: true,
+            challenge_token,
+            expires_in,
+        }));
+    }
+
+    // Start a session
+    let tokens = state.session_service
+        .start_session(user_db.id, user_db.email.clone(), role.clone(), login_method, false)
+        .await?;
 
     // Convert to User model
     let user = User {
@@ -113,5 +185,317 @@ pub async fn login(
         updated_at: user_db.updated_at,
     };
 
-    Ok(Json(AuthResponse { token, user }))
+    Ok(LoginResponse::Authenticated(AuthResponse {
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+        user,
+    }))
+}
+
+/// Issue a sign-in message for a wallet to sign. Unknown addresses get a
+/// challenge too, so the endpoint does not reveal which wallets have accounts.
+pub async fn wallet_challenge(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<WalletChallengeRequest>,
+) -> Result<Json<WalletChallengeResponse>, AppError> {
+    state.login_guard.throttle_wallet_challenge(ip).await?;
+
+    let challenge = state.auth_service
+        .create_wallet_challenge(payload.wallet_address.trim())
+        .await?;
+
+    Ok(Json(WalletChallengeResponse {
+        message: challenge.message,
+        nonce: challenge.nonce,
+        expires_at: challenge.expires_at,
+    }))
+}
+
+/// Log in with an sr25519 signature over a wallet challenge instead of a password
+pub async fn wallet_login(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<WalletLoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let wallet_address = state.auth_service
+        .verify_wallet_login(&payload.nonce, &payload.signature)
+        .await?;
+
+    let user_db = state.user_repo
+        .get_user_by_wallet(&wallet_address)
+        .await?
+        .ok_or_else(|| AppError::Authentication("No account uses this wallet".to_string()))?;
+
+    start_login(&state, user_db, LOGIN_WALLET).await.map(Json)
+}
+
+/// Second login step: exchange the login challenge and an authentication code for tokens
+pub async fn login_mfa(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<MfaLoginRequest>,
+) -> Result<Json<AuthResponse>, AppError> {
+    let challenge = state.mfa_service.get_challenge(&payload.challenge_token).await?;
+
+    let user_db = state.user_repo
+        .get_user_by_id(challenge.user_id)
+        .await?
+        .ok_or_else(|| AppError::Authentication("Invalid credentials".to_string()))?;
+
+    // Wrong codes count against the account like wrong passwords
+    let attempt_email = attempt_email(&user_db);
+    state.login_guard
+        .attempt(
+            &attempt_email,
+            user_db.id,
+            ip,
+            "invalid_code",
+            state.mfa_service.complete_challenge(&challenge, payload.code.trim()),
+        )
+        .await?;
+    state.login_guard.record_success(&attempt_email).await;
+    let user = user_from_db(user_db);
+
+    let tokens = state.session_service
+        .start_session(user.id, user.email.clone(), user.role.clone(), &challenge.login_method, true)
+        .await?;
+
+    Ok(Json(AuthResponse {
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+        user,
+    }))
+}
+
+/// Exchange a refresh token for a new access token and refresh token
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RefreshTokenRequest>,
+) -> Result<Json<AuthResponse>, AppError> {
+    let (tokens, user_db) = state.session_service.refresh(&payload.refresh_token).await?;
+
+    Ok(Json(AuthResponse {
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+        user: user_from_db(user_db),
+    }))
+}
+
+/// Public keys for verifying access tokens, served at /.well-known/jwks.json
+pub async fn jwks(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    Ok(Json(state.auth_service.jwks().await?))
+}
+
+/// End the current session
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    AuthSession(session_id, _): AuthSession,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.session_service.end_session(session_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Logged out successfully"
+    })))
+}
+
+/// End every session of the current user, on all devices
+pub async fn logout_all(
+    State(state): State<Arc<AppState>>,
+    AuthSession(_, user_id): AuthSession,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let sessions = state.session_service.end_all_sessions(user_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Logged out from all devices",
+        "sessions_revoked": sessions
+    })))
+}
+
+/// Send a new email verification link to the current user
+pub async fn send_verification_email(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    state.email_token_service.send_verification_email(&user).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Verification email sent"
+    })))
+}
+
+pub async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.email_token_service.verify_email(&payload.token).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Email address verified"
+    })))
+}
+
+/// Mail a password reset link. Responds the same whether or not the account exists.
+pub async fn forgot_password(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if let Err(e) = state.email_token_service.send_password_reset(payload.email.trim()).await {
+        tracing::warn!("Failed to send password reset email: {}", e);
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "If an account exists for that address, a reset link has been sent"
+    })))
+}
+
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.email_token_service
+        .reset_password(&payload.token, &payload.new_password)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Password has been reset. Please log in again."
+    })))
+}
+
+/// Change the password of the current user, who must confirm the current one.
+/// Every other session is signed out.
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    AuthSession(session_id, user_id): AuthSession,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    state.login_guard
+        .attempt(&attempt_email(&user), user_id, ip, "invalid_password", async {
+            if !state.auth_service.verify_password(&payload.current_password, &user.password_hash)? {
+                return Err(AppError::Authentication("Current password is incorrect".to_string()));
+            }
+            Ok(())
+        })
+        .await?;
+    if payload.new_password == payload.current_password {
+        return Err(AppError::Validation("New password must differ from the current one".to_string()));
+    }
+    state.auth_service.check_password_policy(&payload.new_password, &user.email, &user.name)?;
+
+    let password_hash = state.auth_service.hash_password(&payload.new_password)?;
+    state.user_repo.update_password(user_id, &password_hash).await?;
+
+    let sessions = state.session_service
+        .revoke_other_sessions(user_id, session_id, "password_change")
+        .await?;
+    tracing::info!(user_id = %user_id, sessions_revoked = sessions, "Password changed");
+
+    Ok(Json(serde_json::json!({
+        "message": "Password changed. Other devices have been signed out.",
+        "sessions_revoked": sessions
+    })))
+}
+
+pub async fn get_two_factor_status(
+    State(state): State<Arc<AppState>>,
+    AuthSession(_, user_id): AuthSession,
+) -> Result<Json<TwoFactorStatus>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(state.mfa_service.status(user_id, &parse_user_role(&user.role)?).await?))
+}
+
+/// Generate a TOTP secret and QR code to scan into an authenticator app
+pub async fn setup_two_factor(
+    State(state): State<Arc<AppState>>,
+    AuthSession(_, user_id): AuthSession,
+) -> Result<Json<TotpSetupResponse>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(state.mfa_service.begin_setup(&user).await?))
+}
+
+/// Confirm setup with a code from the authenticator app. The current session
+/// counts as two-factor verified from then on.
+pub async fn enable_two_factor(
+    State(state): State<Arc<AppState>>,
+    AuthSession(session_id, user_id): AuthSession,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let recovery_codes = state.mfa_service.enable(user_id, payload.code.trim()).await?;
+    state.session_service.mark_mfa_verified(session_id).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+pub async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, role): AuthUser,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    state.login_guard
+        .attempt(
+            &attempt_email(&user),
+            user_id,
+            ip,
+            "invalid_code",
+            state.mfa_service.disable(user_id, &role, payload.code.trim()),
+        )
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+/// Replace the recovery codes, invalidating any that are left
+pub async fn regenerate_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let recovery_codes = state.login_guard
+        .attempt(
+            &attempt_email(&user),
+            user_id,
+            ip,
+            "invalid_code",
+            state.mfa_service.regenerate_recovery_codes(user_id, payload.code.trim()),
+        )
+        .await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
 }
@@ -1,40 +1,57 @@
 use axum::{
-    extract::{State, Path},
+    extract::{State, Path, Query},
     Json,
     response::Response,
     body::Body,
 };
 use std::sync::Arc;
 use serde::Deserialize;
+use chrono::{DateTime, Utc};
 use base64::{Engine as _, engine::general_purpose};
 
 use common::{
     IssueCredentialRequest, IssueCredentialResponse, Credential, CredentialListResponse,
-    VerificationResponse, Institution, User, UserRole, CredentialType, CredentialStatus, AppError,
+    VerificationResponse, VcVerificationResponse, CredentialHolder, CredentialStatus, AppError, AppResult,
+    SdJwtPresentationRequest, SdJwtPresentationResponse, SdJwtVerifyRequest, SdJwtVerificationResponse,
+    ImportBadgeRequest, ImportBadgeResponse, SuspendCredentialRequest, ReinstateCredentialRequest,
+    RevokeCredentialRequest, RejectClaimRequest, CredentialHistoryResponse, InstitutionPermission, ApiKeyScope,
+};
+use database::{
+    CredentialDb, UserRepository, InstitutionRepository, AccreditationAuthorityRepository,
+    CredentialRepository,
+};
+use crate::{
+    services::{AppState, StatusChange},
+    middleware::{auth::{AuthUser, VerifiedUser}, api_key::{OptionalApiKey, UserOrApiKey}, oauth::UserOrOAuth},
+    utils::{content_type, vc, open_badges, status_list, sd_jwt::{self, Disclosure}},
+};
+use super::policy::{
+    credential_for_issuer, credential_issued_by, document_recipient, ensure_can_view, ensure_holder,
+    institution_for_caller, institution_for_staff,
+};
+use crate::services::convert::{
+    credential_from_db, public_credential_from_db, institution_from_db, authority_from_db, user_from_db, parse_credential_status,
+    credential_status_name, reason_code,
 };
-use database::{UserRepository, InstitutionRepository, CredentialRepository};
-use crate::{services::AppState, middleware::auth::AuthUser};
 
 pub async fn issue_credential(
     State(state): State<Arc<AppState>>,
-    AuthUser(issuer_id, role): AuthUser,
+    caller: UserOrApiKey<VerifiedUser>,
     Json(payload): Json<IssueCredentialRequest>,
 ) -> Result<Json<IssueCredentialResponse>, AppError> {
-    // Verify issuer is an accredited institution
-    if role != "institution" {
-        return Err(AppError::Authorization("Only institutions can issue credentials".to_string()));
+    // Verify the issuer is staff of an institution, or one of its API keys
+    let (institution, issuer_id) = institution_for_caller(
+        &state,
+        &caller,
+        InstitutionPermission::IssueCredentials,
+        "issue credentials",
+    ).await?;
+
+    // Pending, rejected and suspended institutions cannot issue
+    if institution.accreditation_status != "approved" {
+        return Err(AppError::InstitutionNotAccredited);
     }
 
-    // Temporarily bypass accreditation check for testing
-    // let institution = state.institution_repo
-    //     .get_institution_by_user_id(issuer_id)
-    //     .await?
-    //     .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
-    // 
-    // if !institution.is_accredited {
-    //     return Err(AppError::InstitutionNotAccredited);
-    // }
-
     // Find holder by email
     let holder = state.user_repo
         .get_user_by_email(&payload.holder_email)
@@ -43,16 +60,45 @@ pub async fn issue_credential(
 
     // Issue credential
     let response = state.credential_service
-        .issue_credential(payload, issuer_id, holder.id)
+        .issue_credential(payload, &institution, issuer_id, holder.id)
         .await?;
 
     Ok(Json(response))
 }
 
+#[derive(Deserialize)]
+pub struct VerifyCredentialQuery {
+    pub at: Option<DateTime<Utc>>, // Check validity at a past point in time
+}
+
+/// Describe the outcome of a verification, in the past tense for historical checks
+fn verification_message(status: &CredentialStatus, valid: bool, historical: bool) -> String {
+    let message = match (status, historical) {
+        (CredentialStatus::Issued, false) if valid => "Credential is valid and verified",
+        (CredentialStatus::Issued, true) if valid => "Credential was valid at the requested time",
+        (CredentialStatus::Issued, _) => "Issuer signature could not be verified",
+        (CredentialStatus::Revoked, false) => "Credential has been revoked",
+        (CredentialStatus::Revoked, true) => "Credential had been revoked at the requested time",
+        (CredentialStatus::Suspended, false) => "Credential has been suspended",
+        (CredentialStatus::Suspended, true) => "Credential was suspended at the requested time",
+        (CredentialStatus::Expired, false) => "Credential has expired",
+        (CredentialStatus::Expired, true) => "Credential had expired at the requested time",
+        (CredentialStatus::Pending, true) => "Credential had not been issued at the requested time",
+        (CredentialStatus::Pending, false) => "Credential verification failed",
+    };
+
+    message.to_string()
+}
+
 pub async fn verify_credential(
     State(state): State<Arc<AppState>>,
     Path(credential_id): Path<String>,
+    Query(query): Query<VerifyCredentialQuery>,
+    api_key: OptionalApiKey,
 ) -> Result<Json<VerificationResponse>, AppError> {
+    api_key.require(ApiKeyScope::Verify)?;
+    let checked_at = query.at.unwrap_or_else(Utc::now);
+
     // Get credential from database
     let credential_db = state.credential_repo
         .get_credential_by_credential_id(&credential_id)
@@ -60,108 +106,55 @@ pub async fn verify_credential(
 
     if let Some(cred_db) = credential_db {
         // Verify on blockchain
-        let valid = state.credential_service.verify_credential(&credential_id).await?;
+        let (valid, status) = state.credential_service
+            .verify_credential(&credential_id, Some(checked_at))
+            .await?;
 
         // Get issuer info
-        let issuer_db = state.user_repo.get_user_by_id(cred_db.issuer_id).await?;
-        let institution = if let Some(issuer) = issuer_db {
-            state.institution_repo.get_institution_by_user_id(issuer.id).await?
-        } else {
-            None
-        };
+        let institution = state.institution_repo.get_institution_by_id(cred_db.institution_id).await?;
 
-        // Get holder info
+        // Get holder info, limited to what anonymous verifiers may see
         let holder_db = state.user_repo.get_user_by_id(cred_db.holder_id).await?;
 
-        // Parse credential type
-        let credential_type: CredentialType = match cred_db.credential_type.as_str() {
-            "certificate" => CredentialType::Certificate,
-            "license" => CredentialType::License,
-            "degree" => CredentialType::Degree,
-            "workexperience" => CredentialType::WorkExperience,
-            "skill" => CredentialType::Skill,
-            _ => return Err(AppError::Internal("Invalid credential type".to_string())),
-        };
-
-        // Parse status
-        let status: CredentialStatus = match cred_db.status.as_str() {
-            "pending" => CredentialStatus::Pending,
-            "issued" => CredentialStatus::Issued,
-            "revoked" => CredentialStatus::Revoked,
-            "expired" => CredentialStatus::Expired,
-            _ => return Err(AppError::Internal("Invalid status".to_string())),
-        };
-
-        let credential = Credential {
-            id: cred_db.id,
-            credential_id: cred_db.credential_id,
-            holder_id: cred_db.holder_id,
-            issuer_id: cred_db.issuer_id,
-            credential_type,
-            title: cred_db.title,
-            description: cred_db.description,
-            ipfs_hash: cred_db.ipfs_hash,
-            chain_hash: cred_db.chain_hash,
-            issue_date: cred_db.issue_date,
-            expiry_date: cred_db.expiry_date,
-            status,
-            metadata: cred_db.metadata,
-            created_at: cred_db.created_at,
+        // Name the authority only while the platform recognises the accreditation
+        let accredited_by = match &institution {
+            Some(institution) if institution.accreditation_status == "approved" => {
+                match institution.accreditation_authority_id {
+                    Some(authority_id) => state.authority_repo
+                        .get_authority_by_id(authority_id)
+                        .await?
+                        .map(authority_from_db),
+                    None => None,
+                }
+            }
+            _ => None,
         };
 
-        let issuer_institution = institution.map(|inst| Institution {
-            id: inst.id,
-            user_id: inst.user_id,
-            institution_name: inst.institution_name,
-            institution_type: inst.institution_type,
-            country: inst.country,
-            accreditation_number: inst.accreditation_number,
-            is_accredited: inst.is_accredited,
-            created_at: inst.created_at,
-        });
-
-        let holder_user = holder_db.map(|h| {
-            let role: UserRole = match h.role.as_str() {
-                "professional" => UserRole::Professional,
-                "institution" => UserRole::Institution,
-                "employer" => UserRole::Employer,
-                _ => UserRole::Professional,
-            };
-            
-            User {
-                id: h.id,
-                wallet_address: h.wallet_address,
-                email: h.email,
-                name: h.name,
-                role,
-                is_verified: h.is_verified,
-                created_at: h.created_at,
-                updated_at: h.updated_at,
-            }
+        let credential = public_credential_from_db(cred_db)?;
+        let issuer_institution = institution.map(institution_from_db);
+        let holder_user = holder_db.map(|holder| CredentialHolder {
+            name: holder.name,
+            wallet_address: holder.wallet_address,
         });
 
-        let message = if valid && credential.status == CredentialStatus::Issued {
-            "Credential is valid and verified".to_string()
-        } else if credential.status == CredentialStatus::Revoked {
-            "Credential has been revoked".to_string()
-        } else {
-            "Credential verification failed".to_string()
-        };
-
         Ok(Json(VerificationResponse {
-            valid: valid && credential.status == CredentialStatus::Issued,
+            valid,
             credential: Some(credential),
             issuer: issuer_institution,
+            accredited_by,
             holder: holder_user,
-            message,
+            message: verification_message(&status, valid, query.at.is_some()),
+            checked_at,
         }))
     } else {
         Ok(Json(VerificationResponse {
             valid: false,
             credential: None,
             issuer: None,
+            accredited_by: None,
             holder: None,
             message: "Credential not found".to_string(),
+            checked_at,
         }))
     }
 }
@@ -169,14 +162,21 @@ pub async fn verify_credential(
 #[derive(Deserialize)]
 pub struct VerifyQrCodeRequest {
     pub qr_data: String,
+    pub at: Option<DateTime<Utc>>,
 }
 
 pub async fn verify_qr_code(
     State(state): State<Arc<AppState>>,
+    api_key: OptionalApiKey,
     Json(payload): Json<VerifyQrCodeRequest>,
 ) -> Result<Json<VerificationResponse>, AppError> {
     // QR code contains the credential ID
-    verify_credential(State(state), Path(payload.qr_data)).await
+    verify_credential(
+        State(state),
+        Path(payload.qr_data),
+        Query(VerifyCredentialQuery { at: payload.at }),
+        api_key,
+    ).await
 }
 
 pub async fn get_my_credentials(
@@ -187,41 +187,7 @@ pub async fn get_my_credentials(
     
     let credentials: Vec<Credential> = credentials_db
         .into_iter()
-        .filter_map(|cred_db| {
-            let credential_type = match cred_db.credential_type.as_str() {
-                "certificate" => CredentialType::Certificate,
-                "license" => CredentialType::License,
-                "degree" => CredentialType::Degree,
-                "workexperience" => CredentialType::WorkExperience,
-                "skill" => CredentialType::Skill,
-                _ => return None,
-            };
-
-            let status = match cred_db.status.as_str() {
-                "pending" => CredentialStatus::Pending,
-                "issued" => CredentialStatus::Issued,
-                "revoked" => CredentialStatus::Revoked,
-                "expired" => CredentialStatus::Expired,
-                _ => return None,
-            };
-
-            Some(Credential {
-                id: cred_db.id,
-                credential_id: cred_db.credential_id,
-                holder_id: cred_db.holder_id,
-                issuer_id: cred_db.issuer_id,
-                credential_type,
-                title: cred_db.title,
-                description: cred_db.description,
-                ipfs_hash: cred_db.ipfs_hash,
-                chain_hash: cred_db.chain_hash,
-                issue_date: cred_db.issue_date,
-                expiry_date: cred_db.expiry_date,
-                status,
-                metadata: cred_db.metadata,
-                created_at: cred_db.created_at,
-            })
-        })
+        .filter_map(|cred_db| credential_from_db(cred_db).ok())
         .collect();
 
     let total = credentials.len();
@@ -231,51 +197,20 @@ pub async fn get_my_credentials(
 
 pub async fn get_issued_credentials(
     State(state): State<Arc<AppState>>,
-    AuthUser(user_id, role): AuthUser,
+    caller: UserOrApiKey<AuthUser>,
 ) -> Result<Json<CredentialListResponse>, AppError> {
-    if role != "institution" {
-        return Err(AppError::Authorization("Only institutions can view issued credentials".to_string()));
-    }
-
-    let credentials_db = state.credential_repo.get_credentials_by_issuer(user_id).await?;
+    let (institution, _) = institution_for_caller(
+        &state,
+        &caller,
+        InstitutionPermission::ViewCredentials,
+        "view issued credentials",
+    ).await?;
+
+    let credentials_db = state.credential_repo.get_credentials_by_institution(institution.id).await?;
     
     let credentials: Vec<Credential> = credentials_db
         .into_iter()
-        .filter_map(|cred_db| {
-            let credential_type = match cred_db.credential_type.as_str() {
-                "certificate" => CredentialType::Certificate,
-                "license" => CredentialType::License,
-                "degree" => CredentialType::Degree,
-                "workexperience" => CredentialType::WorkExperience,
-                "skill" => CredentialType::Skill,
-                _ => return None,
-            };
-
-            let status = match cred_db.status.as_str() {
-                "pending" => CredentialStatus::Pending,
-                "issued" => CredentialStatus::Issued,
-                "revoked" => CredentialStatus::Revoked,
-                "expired" => CredentialStatus::Expired,
-                _ => return None,
-            };
-
-            Some(Credential {
-                id: cred_db.id,
-                credential_id: cred_db.credential_id,
-                holder_id: cred_db.holder_id,
-                issuer_id: cred_db.issuer_id,
-                credential_type,
-                title: cred_db.title,
-                description: cred_db.description,
-                ipfs_hash: cred_db.ipfs_hash,
-                chain_hash: cred_db.chain_hash,
-                issue_date: cred_db.issue_date,
-                expiry_date: cred_db.expiry_date,
-                status,
-                metadata: cred_db.metadata,
-                created_at: cred_db.created_at,
-            })
-        })
+        .filter_map(|cred_db| credential_from_db(cred_db).ok())
         .collect();
 
     let total = credentials.len();
@@ -293,44 +228,9 @@ pub async fn get_credential(
         .await?
         .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
 
-    // Check authorization - user must be holder or issuer
-    if credential_db.holder_id != user_id && credential_db.issuer_id != user_id {
-        return Err(AppError::Authorization("Not authorized to view this credential".to_string()));
-    }
-
-    let credential_type = match credential_db.credential_type.as_str() {
-        "certificate" => CredentialType::Certificate,
-        "license" => CredentialType::License,
-        "degree" => CredentialType::Degree,
-        "workexperience" => CredentialType::WorkExperience,
-        "skill" => CredentialType::Skill,
-        _ => return Err(AppError::Internal("Invalid credential type".to_string())),
-    };
+    ensure_can_view(&state, &credential_db, user_id).await?;
 
-    let status = match credential_db.status.as_str() {
-        "pending" => CredentialStatus::Pending,
-        "issued" => CredentialStatus::Issued,
-        "revoked" => CredentialStatus::Revoked,
-        "expired" => CredentialStatus::Expired,
-        _ => return Err(AppError::Internal("Invalid status".to_string())),
-    };
-
-    let credential = Credential {
-        id: credential_db.id,
-        credential_id: credential_db.credential_id,
-        holder_id: credential_db.holder_id,
-        issuer_id: credential_db.issuer_id,
-        credential_type,
-        title: credential_db.title,
-        description: credential_db.description,
-        ipfs_hash: credential_db.ipfs_hash,
-        chain_hash: credential_db.chain_hash,
-        issue_date: credential_db.issue_date,
-        expiry_date: credential_db.expiry_date,
-        status,
-        metadata: credential_db.metadata,
-        created_at: credential_db.created_at,
-    };
+    let credential = credential_from_db(credential_db)?;
 
     Ok(Json(credential))
 }
@@ -338,31 +238,121 @@ pub async fn get_credential(
 pub async fn revoke_credential(
     State(state): State<Arc<AppState>>,
     Path(credential_id): Path<String>,
-    AuthUser(user_id, role): AuthUser,
+    caller: UserOrApiKey<VerifiedUser>,
+    Json(payload): Json<RevokeCredentialRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let (institution, user_id) = institution_for_caller(
+        &state,
+        &caller,
+        InstitutionPermission::RevokeCredentials,
+        "revoke credentials",
+    ).await?;
+    let credential_db = credential_issued_by(&state, &credential_id, &institution, "revoke").await?;
+    let reason = reason_code(&payload.reason)?;
+
+    state.credential_service
+        .transition_status(&credential_db, CredentialStatus::Revoked, StatusChange {
+            actor_id: Some(user_id),
+            reason: Some(reason.clone()),
+            note: payload.note,
+            effective_at: payload.effective_at,
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Credential revoked successfully",
+        "credential_id": credential_id,
+        "reason": reason
+    })))
+}
+
+pub async fn suspend_credential(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    VerifiedUser(user_id, role): VerifiedUser,
+    Json(payload): Json<SuspendCredentialRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let credential_db = credential_for_issuer(
+        &state,
+        &credential_id,
+        user_id,
+        &role,
+        InstitutionPermission::SuspendCredentials,
+        "suspend",
+    ).await?;
+    let reason = reason_code(&payload.reason)?;
+
+    state.credential_service
+        .transition_status(&credential_db, CredentialStatus::Suspended, StatusChange {
+            actor_id: Some(user_id),
+            reason: Some(reason.clone()),
+            note: payload.note,
+            effective_at: None,
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Credential suspended successfully",
+        "credential_id": credential_id,
+        "reason": reason
+    })))
+}
+
+pub async fn reinstate_credential(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    VerifiedUser(user_id, role): VerifiedUser,
+    Json(payload): Json<ReinstateCredentialRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    if role != "institution" {
-        return Err(AppError::Authorization("Only institutions can revoke credentials".to_string()));
+    let credential_db = credential_for_issuer(
+        &state,
+        &credential_id,
+        user_id,
+        &role,
+        InstitutionPermission::SuspendCredentials,
+        "reinstate",
+    ).await?;
+    let reason = reason_code(&payload.reason)?;
+
+    if credential_db.status != credential_status_name(&CredentialStatus::Suspended) {
+        return Err(AppError::Validation("Only suspended credentials can be reinstated".to_string()));
     }
 
+    state.credential_service
+        .transition_status(&credential_db, CredentialStatus::Issued, StatusChange {
+            actor_id: Some(user_id),
+            reason: Some(reason.clone()),
+            note: payload.note,
+            effective_at: None,
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Credential reinstated successfully",
+        "credential_id": credential_id,
+        "reason": reason
+    })))
+}
+
+pub async fn get_credential_history(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<CredentialHistoryResponse>, AppError> {
     let credential_db = state.credential_repo
         .get_credential_by_credential_id(&credential_id)
         .await?
         .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
 
-    // Check if user is the issuer
-    if credential_db.issuer_id != user_id {
-        return Err(AppError::Authorization("Not authorized to revoke this credential".to_string()));
-    }
+    ensure_can_view(&state, &credential_db, user_id).await?;
 
-    // Update status to revoked
-    state.credential_repo
-        .update_credential_status(credential_db.id, "revoked")
-        .await?;
+    let events = state.credential_service.status_history(&credential_db).await?;
 
-    Ok(Json(serde_json::json!({
-        "message": "Credential revoked successfully",
-        "credential_id": credential_id
-    })))
+    Ok(Json(CredentialHistoryResponse {
+        credential_id,
+        status: parse_credential_status(&credential_db.status)?,
+        events,
+    }))
 }
 
 pub async fn get_credential_qr(
@@ -376,10 +366,7 @@ pub async fn get_credential_qr(
         .await?
         .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
 
-    // Check authorization - user must be holder or issuer
-    if credential_db.holder_id != user_id && credential_db.issuer_id != user_id {
-        return Err(AppError::Authorization("Not authorized to view this credential".to_string()));
-    }
+    ensure_can_view(&state, &credential_db, user_id).await?;
 
     // Decode base64 QR code
     let qr_data = general_purpose::STANDARD.decode(&credential_db.qr_code)
@@ -395,3 +382,360 @@ pub async fn get_credential_qr(
 
     Ok(response)
 }
+
+/// Download a credential's document, decrypted for the holder, staff of the
+/// issuing institution, or a partner portal the holder shared it with
+pub async fn download_document(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    caller: UserOrOAuth,
+) -> Result<Response, AppError> {
+    let credential_db = state.credential_repo
+        .get_credential_by_credential_id(&credential_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+
+    let recipient = document_recipient(&state, &credential_db, &caller).await?;
+    let document = state.credential_service.read_document(&credential_db, recipient).await?;
+    let document_type = content_type::sniff(&document);
+
+    let response = Response::builder()
+        .status(200)
+        .header("Content-Type", document_type.media_type)
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"credential-{}.{}\"", credential_id, document_type.extension),
+        )
+        .header("X-Content-Type-Options", "nosniff")
+        .header("Cache-Control", "private, no-store")
+        .body(Body::from(document))
+        .map_err(|e| AppError::Internal(format!("Failed to create response: {}", e)))?;
+
+    Ok(response)
+}
+
+/// Build the W3C VC document for a credential from our records
+async fn build_verifiable_credential(
+    state: &AppState,
+    credential_db: CredentialDb,
+) -> AppResult<serde_json::Value> {
+    let institution = state.institution_repo
+        .get_institution_by_id(credential_db.institution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Issuing institution not found".to_string()))?;
+
+    let holder = state.user_repo
+        .get_user_by_id(credential_db.holder_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Holder not found".to_string()))?;
+
+    let status_list_entry = credential_db.status_list_id.zip(credential_db.status_list_index);
+    let credential = credential_from_db(credential_db)?;
+
+    let mut verifiable_credential = vc::to_verifiable_credential(
+        &credential,
+        &institution_from_db(institution),
+        &user_from_db(holder),
+        &state.config.public_base_url,
+    );
+
+    // Point verifiers at the cacheable status lists where the credential has an entry
+    if let Some((list_id, index)) = status_list_entry {
+        verifiable_credential["credentialStatus"] =
+            status_list::status_entries(&state.config.public_base_url, list_id, index);
+    }
+
+    Ok(verifiable_credential)
+}
+
+pub async fn export_verifiable_credential(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let credential_db = state.credential_repo
+        .get_credential_by_credential_id(&credential_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+
+    ensure_can_view(&state, &credential_db, user_id).await?;
+
+    if credential_db.issuer_signature.is_empty() {
+        return Err(AppError::Validation("Credential has no issuer signature and cannot be exported".to_string()));
+    }
+
+    let verifiable_credential = build_verifiable_credential(&state, credential_db).await?;
+
+    Ok(Json(verifiable_credential))
+}
+
+pub async fn verify_verifiable_credential(
+    State(state): State<Arc<AppState>>,
+    api_key: OptionalApiKey,
+    Json(presented): Json<serde_json::Value>,
+) -> Result<Json<VcVerificationResponse>, AppError> {
+    api_key.require(ApiKeyScope::Verify)?;
+
+    let not_verified = |credential_id: Option<String>, message: &str| VcVerificationResponse {
+        valid: false,
+        credential_id,
+        proof_valid: false,
+        claims_match: false,
+        status: None,
+        message: message.to_string(),
+    };
+
+    let Some(credential_id) = vc::credential_id_from_vc(&presented) else {
+        return Ok(Json(not_verified(None, "Not a Safari Skills Passport credential")));
+    };
+
+    let Some(credential_db) = state.credential_repo
+        .get_credential_by_credential_id(&credential_id)
+        .await?
+    else {
+        return Ok(Json(not_verified(Some(credential_id), "Credential not found")));
+    };
+
+    let status = state.credential_service.status_at(&credential_db, &Utc::now()).await?;
+    let proof_valid = state.credential_service.verify_issuer_signature(&credential_db).await?;
+    let expected = build_verifiable_credential(&state, credential_db).await?;
+    let claims_match = vc::claims_match(&presented, &expected);
+
+    let message = if !claims_match {
+        "Credential claims do not match the issuer's records"
+    } else if !proof_valid {
+        "Issuer signature could not be verified"
+    } else if status == CredentialStatus::Revoked {
+        "Credential has been revoked"
+    } else if status == CredentialStatus::Suspended {
+        "Credential has been suspended"
+    } else if status == CredentialStatus::Expired {
+        "Credential has expired"
+    } else if status != CredentialStatus::Issued {
+        "Credential is not currently valid"
+    } else {
+        "Credential is valid and verified"
+    };
+
+    Ok(Json(VcVerificationResponse {
+        valid: claims_match && proof_valid && status == CredentialStatus::Issued,
+        credential_id: Some(credential_id),
+        proof_valid,
+        claims_match,
+        status: Some(status),
+        message: message.to_string(),
+    }))
+}
+
+pub async fn create_sd_jwt_presentation(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<SdJwtPresentationRequest>,
+) -> Result<Json<SdJwtPresentationResponse>, AppError> {
+    let credential_db = state.credential_repo
+        .get_credential_by_credential_id(&credential_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+
+    // Only the holder decides what to disclose
+    ensure_holder(&credential_db, user_id)?;
+
+    let holder = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Holder not found".to_string()))?;
+
+    let (jwt, disclosures) = state.credential_service
+        .mint_sd_jwt(&credential_db, &holder, &state.config.public_base_url)
+        .await?;
+
+    let selected: Vec<&Disclosure> = match &payload.claims {
+        Some(claims) => {
+            if let Some(unknown) = claims.iter().find(|claim| !disclosures.iter().any(|d| &d.path == *claim)) {
+                return Err(AppError::Validation(format!("Unknown claim: {}", unknown)));
+            }
+            disclosures.iter().filter(|d| claims.contains(&d.path)).collect()
+        }
+        None => disclosures.iter().collect(),
+    };
+
+    Ok(Json(SdJwtPresentationResponse {
+        presentation: sd_jwt::compose_presentation(&jwt, selected.iter().copied()),
+        disclosed_claims: selected.iter().map(|d| d.path.clone()).collect(),
+    }))
+}
+
+pub async fn verify_sd_jwt_presentation(
+    State(state): State<Arc<AppState>>,
+    api_key: OptionalApiKey,
+    Json(payload): Json<SdJwtVerifyRequest>,
+) -> Result<Json<SdJwtVerificationResponse>, AppError> {
+    api_key.require(ApiKeyScope::Verify)?;
+
+    let result = state.credential_service.verify_sd_jwt(&payload.presentation).await?;
+
+    Ok(Json(result))
+}
+
+pub async fn export_open_badge(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let credential_db = state.credential_repo
+        .get_credential_by_credential_id(&credential_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+
+    ensure_can_view(&state, &credential_db, user_id).await?;
+
+    if credential_db.issuer_signature.is_empty() {
+        return Err(AppError::Validation("Credential has no issuer signature and cannot be exported".to_string()));
+    }
+
+    let institution = state.institution_repo
+        .get_institution_by_id(credential_db.institution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Issuing institution not found".to_string()))?;
+
+    let holder = state.user_repo
+        .get_user_by_id(credential_db.holder_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Holder not found".to_string()))?;
+
+    // Prefer issuer-supplied artwork, otherwise use the credential's QR code
+    let image = match credential_db.metadata.get("badge_image").and_then(|image| image.as_str()) {
+        Some(image) => image.to_string(),
+        None => format!("data:image/png;base64,{}", credential_db.qr_code),
+    };
+
+    let credential = credential_from_db(credential_db)?;
+    if !open_badges::is_badge_type(&credential.credential_type) {
+        return Err(AppError::Validation("Only skill and certificate credentials can be exported as badges".to_string()));
+    }
+
+    Ok(Json(open_badges::to_open_badge(
+        &credential,
+        &institution_from_db(institution),
+        &user_from_db(holder),
+        &image,
+        &state.config.public_base_url,
+    )))
+}
+
+pub async fn import_open_badge(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<ImportBadgeRequest>,
+) -> Result<Json<ImportBadgeResponse>, AppError> {
+    let imported = open_badges::parse_open_badge(&payload.badge)?;
+
+    // The claim is raised against an institution registered with us
+    let institution = match (imported.issuer_id, &imported.issuer_name) {
+        (Some(id), _) => state.institution_repo.get_institution_by_id(id).await?,
+        (None, Some(name)) => state.institution_repo.get_institution_by_name(name).await?,
+        (None, None) => None,
+    }
+    .ok_or_else(|| AppError::NotFound("Issuing institution is not registered".to_string()))?;
+
+    let claim = state.credential_service
+        .create_badge_claim(payload.badge, imported, user_id, &institution)
+        .await?;
+
+    Ok(Json(ImportBadgeResponse {
+        credential_id: claim.credential_id,
+        status: CredentialStatus::Pending,
+        institution_name: institution.institution_name,
+    }))
+}
+
+/// Badge claims raised against the caller's institution that await review
+pub async fn list_badge_claims(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, role): AuthUser,
+) -> Result<Json<CredentialListResponse>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::IssueCredentials,
+        "review badge claims",
+    ).await?;
+
+    let pending = credential_status_name(&CredentialStatus::Pending);
+    let credentials: Vec<Credential> = state.credential_repo
+        .get_credentials_by_institution(institution.id)
+        .await?
+        .into_iter()
+        .filter(|cred_db| cred_db.status == pending)
+        .filter_map(|cred_db| credential_from_db(cred_db).ok())
+        .collect();
+
+    let total = credentials.len();
+
+    Ok(Json(CredentialListResponse { credentials, total }))
+}
+
+pub async fn approve_badge_claim(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    VerifiedUser(user_id, role): VerifiedUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::IssueCredentials,
+        "review badge claims",
+    ).await?;
+
+    // Approving signs the credential, which only accredited institutions may do
+    if institution.accreditation_status != "approved" {
+        return Err(AppError::InstitutionNotAccredited);
+    }
+
+    let claim = credential_issued_by(&state, &credential_id, &institution, "approve").await?;
+    state.credential_service
+        .approve_badge_claim(&claim, &institution, user_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Claim approved and credential issued",
+        "credential_id": credential_id
+    })))
+}
+
+pub async fn reject_badge_claim(
+    State(state): State<Arc<AppState>>,
+    Path(credential_id): Path<String>,
+    VerifiedUser(user_id, role): VerifiedUser,
+    Json(payload): Json<RejectClaimRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::IssueCredentials,
+        "review badge claims",
+    ).await?;
+    let claim = credential_issued_by(&state, &credential_id, &institution, "reject").await?;
+
+    if parse_credential_status(&claim.status)? != CredentialStatus::Pending {
+        return Err(AppError::Validation("Only pending claims can be rejected".to_string()));
+    }
+
+    state.credential_service
+        .transition_status(&claim, CredentialStatus::Revoked, StatusChange {
+            actor_id: Some(user_id),
+            reason: Some("claim_rejected".to_string()),
+            note: payload.note,
+            effective_at: None,
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Claim rejected",
+        "credential_id": credential_id
+    })))
+}
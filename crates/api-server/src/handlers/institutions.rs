@@ -1,30 +1,109 @@
 use axum::{
-    extract::State,
+    extract::{State, Path, Query},
     Json,
 };
 use std::sync::Arc;
+use serde::Deserialize;
 use uuid::Uuid;
 use chrono::Utc;
 
-use common::{RegisterInstitutionRequest, Institution, AppError};
-use database::{InstitutionDb, InstitutionRepository};
-use crate::{services::AppState, middleware::auth::AuthUser};
+use common::{
+    RegisterInstitutionRequest, Institution, AccreditationAuthorityListResponse, InstitutionMember,
+    InstitutionMemberListResponse, AddInstitutionMemberRequest, UpdateInstitutionMemberRequest, InstitutionPermission,
+    ApiKeyListResponse, CreateApiKeyRequest, CreatedApiKeyResponse, UserRole, AppError, AppResult,
+};
+use database::{
+    AccreditationAuthorityRepository, InstitutionDb, InstitutionMemberDb, InstitutionMemberRepository,
+    InstitutionRepository, UserRepository,
+};
+use crate::{
+    services::AppState,
+    middleware::auth::{AuthUser, VerifiedUser},
+};
+use crate::services::convert::{
+    institution_from_db, authority_from_db, parse_institution_role, parse_user_role, institution_role_name,
+    api_key_from_db,
+};
+use super::policy::institution_for_staff;
+
+/// Validate an accreditation number against the authorities of the institution's
+/// country, returning the normalised number and the authority that issued it.
+/// Countries without a registered authority are accepted unlinked for admin review.
+async fn resolve_accreditation(
+    state: &AppState,
+    payload: &RegisterInstitutionRequest,
+) -> AppResult<(Option<String>, Option<Uuid>)> {
+    let number = payload.accreditation_number
+        .as_deref()
+        .map(|number| number.trim().to_uppercase())
+        .filter(|number| !number.is_empty());
+
+    let authorities = match &payload.accreditation_authority {
+        Some(code) => {
+            let authority = state.authority_repo
+                .get_authority_by_code(code)
+                .await?
+                .ok_or_else(|| AppError::Validation(format!("Unknown accreditation authority {}", code)))?;
+
+            let country = payload.country.trim();
+            if !authority.country.eq_ignore_ascii_case(country)
+                && !authority.country_code.eq_ignore_ascii_case(country)
+            {
+                return Err(AppError::Validation(format!(
+                    "{} does not accredit institutions in {}",
+                    authority.code, payload.country,
+                )));
+            }
+            vec![authority]
+        }
+        None => state.authority_repo.list_authorities(Some(&payload.country)).await?,
+    };
+
+    if authorities.is_empty() {
+        return Ok((number, None));
+    }
+
+    let examples = authorities
+        .iter()
+        .map(|authority| format!("{} (e.g. {})", authority.code, authority.number_example))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let number = number.ok_or_else(|| AppError::Validation(format!(
+        "An accreditation number from {} is required",
+        examples,
+    )))?;
+
+    let mut matching = Vec::new();
+    for authority in authorities {
+        if state.authority_repo.matches_number_format(authority.id, &number).await? {
+            matching.push(authority);
+        }
+    }
+
+    match matching.as_slice() {
+        [authority] => Ok((Some(number), Some(authority.id))),
+        [] => Err(AppError::Validation(format!(
+            "Accreditation number {} does not match the format of {}",
+            number, examples,
+        ))),
+        _ => Err(AppError::Validation(
+            "Accreditation number matches several authorities; specify accreditation_authority".to_string(),
+        )),
+    }
+}
 
 pub async fn register_institution(
     State(state): State<Arc<AppState>>,
-    AuthUser(user_id, role): AuthUser,
+    AuthUser(user_id, _): AuthUser,
     Json(payload): Json<RegisterInstitutionRequest>,
 ) -> Result<Json<Institution>, AppError> {
-    // Verify user is an institution
-    if role != "institution" {
-        return Err(AppError::Authorization("Only institutions can register".to_string()));
-    }
-
-    // Check if institution already registered for this user
-    if let Some(_) = state.institution_repo.get_institution_by_user_id(user_id).await? {
+    // Check if this account already registered or joined an institution
+    if state.member_repo.get_membership(user_id).await?.is_some() {
         return Err(AppError::Validation("Institution already registered".to_string()));
     }
 
+    let (accreditation_number, accreditation_authority_id) = resolve_accreditation(&state, &payload).await?;
+
     // Create institution
     let institution_db = InstitutionDb {
         id: Uuid::new_v4(),
@@ -32,46 +111,271 @@ pub async fn register_institution(
         institution_name: payload.institution_name.clone(),
         institution_type: payload.institution_type.clone(),
         country: payload.country.clone(),
-        accreditation_number: payload.accreditation_number.clone(),
+        accreditation_number,
         is_accredited: false, // Pending accreditation
         created_at: Utc::now(),
+        accreditation_status: "pending".to_string(),
+        review_notes: None,
+        reviewed_by: None,
+        reviewed_at: None,
+        accreditation_authority_id,
     };
 
     let created = state.institution_repo.create_institution(&institution_db).await?;
 
-    let institution = Institution {
-        id: created.id,
-        user_id: created.user_id,
-        institution_name: created.institution_name,
-        institution_type: created.institution_type,
-        country: created.country,
-        accreditation_number: created.accreditation_number,
-        is_accredited: created.is_accredited,
-        created_at: created.created_at,
-    };
+    // Provision the institution's credential signing key
+    state.credential_service.ensure_issuer_key(&created).await?;
 
-    Ok(Json(institution))
+    Ok(Json(institution_from_db(created)))
 }
 
 pub async fn get_my_institution(
     State(state): State<Arc<AppState>>,
-    AuthUser(user_id, _): AuthUser,
+    AuthUser(user_id, role): AuthUser,
 ) -> Result<Json<Institution>, AppError> {
-    let institution_db = state.institution_repo
-        .get_institution_by_user_id(user_id)
+    let (institution_db, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ViewInstitution,
+        "view institution details",
+    ).await?;
+
+    Ok(Json(institution_from_db(institution_db)))
+}
+
+async fn member_from_db(state: &AppState, member: InstitutionMemberDb) -> AppResult<InstitutionMember> {
+    let user = state.user_repo
+        .get_user_by_id(member.user_id)
         .await?
-        .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
-
-    let institution = Institution {
-        id: institution_db.id,
-        user_id: institution_db.user_id,
-        institution_name: institution_db.institution_name,
-        institution_type: institution_db.institution_type,
-        country: institution_db.country,
-        accreditation_number: institution_db.accreditation_number,
-        is_accredited: institution_db.is_accredited,
-        created_at: institution_db.created_at,
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    Ok(InstitutionMember {
+        user_id: member.user_id,
+        name: user.name,
+        email: user.email,
+        role: parse_institution_role(&member.role)?,
+        added_by: member.added_by,
+        created_at: member.created_at,
+    })
+}
+
+pub async fn list_members(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, role): AuthUser,
+) -> Result<Json<InstitutionMemberListResponse>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ViewInstitution,
+        "view staff",
+    ).await?;
+
+    let mut members = Vec::new();
+    for member in state.member_repo.get_members(institution.id).await? {
+        members.push(member_from_db(&state, member).await?);
+    }
+
+    let total = members.len();
+    Ok(Json(InstitutionMemberListResponse { members, total }))
+}
+
+pub async fn add_member(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, role): AuthUser,
+    Json(payload): Json<AddInstitutionMemberRequest>,
+) -> Result<Json<InstitutionMember>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageMembers,
+        "manage staff",
+    ).await?;
+
+    let user = state.user_repo
+        .get_user_by_email(payload.email.trim())
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Staff sign in with institution accounts so their tokens carry the institution role
+    if parse_user_role(&user.role)? != UserRole::Institution {
+        return Err(AppError::Validation("Only institution accounts can join an institution".to_string()));
+    }
+
+    let member = InstitutionMemberDb {
+        institution_id: institution.id,
+        user_id: user.id,
+        role: institution_role_name(&payload.role),
+        added_by: Some(user_id),
+        created_at: Utc::now(),
     };
 
-    Ok(Json(institution))
+    let added = state.member_repo
+        .add_member(&member)
+        .await?
+        .ok_or_else(|| AppError::Validation("User already belongs to an institution".to_string()))?;
+
+    Ok(Json(member_from_db(&state, added).await?))
+}
+
+pub async fn update_member(
+    State(state): State<Arc<AppState>>,
+    Path(member_id): Path<Uuid>,
+    AuthUser(user_id, role): AuthUser,
+    Json(payload): Json<UpdateInstitutionMemberRequest>,
+) -> Result<Json<InstitutionMember>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageMembers,
+        "manage staff",
+    ).await?;
+
+    let updated = state.member_repo
+        .update_member_role(institution.id, member_id, &institution_role_name(&payload.role))
+        .await?
+        .ok_or_else(|| AppError::Validation(
+            "Staff member not found, or they are the institution's last owner".to_string(),
+        ))?;
+
+    Ok(Json(member_from_db(&state, updated).await?))
+}
+
+pub async fn remove_member(
+    State(state): State<Arc<AppState>>,
+    Path(member_id): Path<Uuid>,
+    AuthUser(user_id, role): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageMembers,
+        "manage staff",
+    ).await?;
+
+    let removed = state.member_repo.remove_member(institution.id, member_id).await?;
+    if !removed {
+        return Err(AppError::Validation(
+            "Staff member not found, or they are the institution's last owner".to_string(),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Staff member removed",
+        "user_id": member_id
+    })))
+}
+
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, role): AuthUser,
+) -> Result<Json<ApiKeyListResponse>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageApiKeys,
+        "manage API keys",
+    ).await?;
+
+    let api_keys = state.api_key_service
+        .list(institution.id)
+        .await?
+        .into_iter()
+        .map(api_key_from_db)
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let total = api_keys.len();
+    Ok(Json(ApiKeyListResponse { api_keys, total }))
+}
+
+/// Create an API key for the institution's own systems. The secret is only returned here.
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    VerifiedUser(user_id, role): VerifiedUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreatedApiKeyResponse>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageApiKeys,
+        "manage API keys",
+    ).await?;
+
+    let (api_key, key) = state.api_key_service
+        .create(institution.id, user_id, &payload.name, &payload.scopes, payload.expires_in_days)
+        .await?;
+
+    Ok(Json(CreatedApiKeyResponse {
+        key,
+        api_key: api_key_from_db(api_key)?,
+    }))
+}
+
+/// Replace a key's secret; the old secret stops working at once
+pub async fn rotate_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(key_id): Path<Uuid>,
+    VerifiedUser(user_id, role): VerifiedUser,
+) -> Result<Json<CreatedApiKeyResponse>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageApiKeys,
+        "manage API keys",
+    ).await?;
+
+    let (api_key, key) = state.api_key_service.rotate(institution.id, key_id, user_id).await?;
+
+    Ok(Json(CreatedApiKeyResponse {
+        key,
+        api_key: api_key_from_db(api_key)?,
+    }))
+}
+
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(key_id): Path<Uuid>,
+    AuthUser(user_id, role): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let (institution, _) = institution_for_staff(
+        &state,
+        user_id,
+        &role,
+        InstitutionPermission::ManageApiKeys,
+        "manage API keys",
+    ).await?;
+
+    state.api_key_service.revoke(institution.id, key_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "API key revoked",
+        "id": key_id
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct AuthorityListQuery {
+    pub country: Option<String>, // Country name or ISO code
+}
+
+pub async fn list_accreditation_authorities(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuthorityListQuery>,
+) -> Result<Json<AccreditationAuthorityListResponse>, AppError> {
+    let authorities: Vec<_> = state.authority_repo
+        .list_authorities(query.country.as_deref())
+        .await?
+        .into_iter()
+        .map(authority_from_db)
+        .collect();
+
+    let total = authorities.len();
+    Ok(Json(AccreditationAuthorityListResponse { authorities, total }))
 }
@@ -1,6 +1,11 @@
+pub mod admin;
 pub mod auth;
 pub mod institutions;
 pub mod credentials;
+pub mod oauth;
+pub mod policy;
+pub mod status_lists;
+pub mod wallet;
 
 use axum::{Json, response::IntoResponse, http::StatusCode};
 use serde_json::json;
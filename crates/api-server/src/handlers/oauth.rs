@@ -0,0 +1,301 @@
+use axum::{
+    extract::{Path, Query, RawQuery, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use axum_extra::{
+    headers::{authorization::Basic, Authorization},
+    TypedHeader,
+};
+use std::sync::Arc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use common::{
+    AuthorizationDecisionRequest, AuthorizationRedirectResponse, AuthorizationRequest, ConsentScope,
+    CredentialListResponse, OAuthClientListResponse, OAuthConsentResponse, OAuthGrantListResponse, OAuthScope,
+    RegisterOAuthClientRequest, RegisteredOAuthClientResponse, UserInfoResponse, AppError, AppResult,
+};
+use crate::{
+    services::{AppState, oidc::{scope_description, TokenError}},
+    middleware::{auth::AuthUser, oauth::OAuthAccess},
+};
+use crate::services::convert::{credential_from_db, oauth_client_from_db, oauth_grant_from_db, oauth_scope_name};
+
+const SUPPORTED_SCOPES: [OAuthScope; 4] = [
+    OAuthScope::OpenId,
+    OAuthScope::Profile,
+    OAuthScope::Email,
+    OAuthScope::CredentialsRead,
+];
+
+/// OpenID Connect discovery metadata
+pub async fn openid_configuration(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let issuer = state.oidc_service.issuer();
+    let scopes: Vec<&str> = SUPPORTED_SCOPES.into_iter().map(oauth_scope_name).collect();
+
+    Ok(Json(serde_json::json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/oauth/authorize", issuer),
+        "token_endpoint": format!("{}/oauth/token", issuer),
+        "userinfo_endpoint": format!("{}/oauth/userinfo", issuer),
+        "jwks_uri": format!("{}/.well-known/jwks.json", issuer),
+        "scopes_supported": scopes,
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": [state.auth_service.signing_algorithm().await?],
+        "token_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post", "none"],
+        "code_challenge_methods_supported": ["S256"],
+        "claims_supported": ["sub", "iss", "aud", "exp", "iat", "nonce", "name", "email", "email_verified"],
+        "authorization_response_iss_parameter_supported": true,
+    })))
+}
+
+/// Authorization endpoint. The browser is sent on to the consent screen in
+/// the web app, which signs the user in if needed and calls the endpoints below.
+pub async fn authorize(
+    State(state): State<Arc<AppState>>,
+    RawQuery(query): RawQuery,
+) -> Redirect {
+    let frontend_url = state.config.frontend_url.trim_end_matches('/');
+    let query = query.map(|query| format!("&{}", query)).unwrap_or_default();
+
+    Redirect::to(&format!("{}/?oauth_authorize=1{}", frontend_url, query))
+}
+
+/// What the consent screen shows for an authentication request
+pub async fn get_consent(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Query(request): Query<AuthorizationRequest>,
+) -> Result<Json<OAuthConsentResponse>, AppError> {
+    let valid = state.oidc_service.validate_authorization(&request).await?;
+
+    let credentials = if valid.scopes.contains(&OAuthScope::CredentialsRead) {
+        state.oidc_service
+            .shareable_credentials(user_id)
+            .await?
+            .into_iter()
+            .map(credential_from_db)
+            .collect::<AppResult<Vec<_>>>()?
+    } else {
+        Vec::new()
+    };
+
+    let scopes = valid.scopes
+        .iter()
+        .map(|scope| ConsentScope {
+            scope: *scope,
+            description: scope_description(*scope).to_string(),
+        })
+        .collect();
+
+    Ok(Json(OAuthConsentResponse {
+        client_id: valid.client.client_id,
+        client_name: valid.client.name,
+        redirect_uri: request.redirect_uri,
+        scopes,
+        credentials,
+    }))
+}
+
+/// Approve or deny an authentication request. The web app sends the browser
+/// to `redirect_to`, which hands the client a code or an `access_denied` error.
+pub async fn decide(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<AuthorizationDecisionRequest>,
+) -> Result<Json<AuthorizationRedirectResponse>, AppError> {
+    let redirect_to = if payload.approve {
+        state.oidc_service.approve(user_id, &payload.request, &payload.credential_ids).await?
+    } else {
+        state.oidc_service.deny(&payload.request).await?
+    };
+
+    Ok(Json(AuthorizationRedirectResponse { redirect_to }))
+}
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub client_id: Option<String>, // Unless sent with HTTP Basic authentication
+    pub client_secret: Option<String>,
+}
+
+/// Token endpoint errors, in the shape RFC 6749 section 5.2 requires
+impl IntoResponse for TokenError {
+    fn into_response(self) -> Response {
+        let (status, error, description) = match self {
+            TokenError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, "invalid_request", msg),
+            TokenError::InvalidClient => (
+                StatusCode::UNAUTHORIZED,
+                "invalid_client",
+                "Client authentication failed".to_string(),
+            ),
+            TokenError::InvalidGrant(msg) => (StatusCode::BAD_REQUEST, "invalid_grant", msg),
+            TokenError::UnsupportedGrantType => (
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                "Only the authorization_code grant is supported".to_string(),
+            ),
+            TokenError::Server(e) => return e.into_response(),
+        };
+
+        let body = Json(serde_json::json!({
+            "error": error,
+            "error_description": description,
+        }));
+
+        let mut response = (status, body).into_response();
+        response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        if status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Basic"));
+        }
+        response
+    }
+}
+
+/// Token endpoint: redeem an authorization code, form-encoded as RFC 6749 requires
+pub async fn token(
+    State(state): State<Arc<AppState>>,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(payload): Form<TokenRequest>,
+) -> Result<Response, TokenError> {
+    let missing = |name: &str| TokenError::InvalidRequest(format!("Missing {}", name));
+
+    if payload.grant_type.as_deref().ok_or_else(|| missing("grant_type"))? != "authorization_code" {
+        return Err(TokenError::UnsupportedGrantType);
+    }
+
+    // Client ids and secrets are URL-safe, so Basic credentials need no form-decoding
+    let (client_id, client_secret) = match &basic {
+        Some(TypedHeader(Authorization(basic))) => {
+            if payload.client_secret.is_some() {
+                return Err(TokenError::InvalidRequest("Use only one client authentication method".to_string()));
+            }
+            (basic.username(), Some(basic.password()))
+        }
+        None => (
+            payload.client_id.as_deref().ok_or_else(|| missing("client_id"))?,
+            payload.client_secret.as_deref(),
+        ),
+    };
+
+    let tokens = state.oidc_service
+        .exchange_code(
+            client_id,
+            client_secret,
+            payload.code.as_deref().ok_or_else(|| missing("code"))?,
+            payload.redirect_uri.as_deref().ok_or_else(|| missing("redirect_uri"))?,
+            payload.code_verifier.as_deref().ok_or_else(|| missing("code_verifier"))?,
+        )
+        .await?;
+
+    let mut response = Json(tokens).into_response();
+    response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok(response)
+}
+
+/// Claims about the user behind an OAuth access token
+pub async fn userinfo(
+    State(state): State<Arc<AppState>>,
+    OAuthAccess(grant): OAuthAccess,
+) -> Result<Json<UserInfoResponse>, AppError> {
+    Ok(Json(state.oidc_service.user_info(&grant).await?))
+}
+
+/// The credentials the user chose to share with the client, with their current status
+pub async fn shared_credentials(
+    State(state): State<Arc<AppState>>,
+    OAuthAccess(grant): OAuthAccess,
+) -> Result<Json<CredentialListResponse>, AppError> {
+    let credentials = state.oidc_service
+        .shared_credentials(&grant)
+        .await?
+        .into_iter()
+        .map(credential_from_db)
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let total = credentials.len();
+    Ok(Json(CredentialListResponse { credentials, total }))
+}
+
+pub async fn list_clients(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<OAuthClientListResponse>, AppError> {
+    let clients: Vec<_> = state.oidc_service
+        .list_clients(user_id)
+        .await?
+        .into_iter()
+        .map(oauth_client_from_db)
+        .collect();
+
+    let total = clients.len();
+    Ok(Json(OAuthClientListResponse { clients, total }))
+}
+
+/// Register an employer portal as an OAuth client. A confidential client's secret is only returned here.
+pub async fn register_client(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<RegisterOAuthClientRequest>,
+) -> Result<Json<RegisteredOAuthClientResponse>, AppError> {
+    let (client, client_secret) = state.oidc_service
+        .register_client(user_id, &payload.name, &payload.redirect_uris, payload.confidential.unwrap_or(true))
+        .await?;
+
+    Ok(Json(RegisteredOAuthClientResponse {
+        client_secret,
+        client: oauth_client_from_db(client),
+    }))
+}
+
+pub async fn revoke_client(
+    State(state): State<Arc<AppState>>,
+    Path(client_id): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.oidc_service.revoke_client(user_id, &client_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "OAuth client revoked",
+        "client_id": client_id
+    })))
+}
+
+/// Partner portals the user has signed in to
+pub async fn list_grants(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<OAuthGrantListResponse>, AppError> {
+    let grants: Vec<_> = state.oidc_service
+        .list_grants(user_id)
+        .await?
+        .into_iter()
+        .map(|(grant, client)| oauth_grant_from_db(grant, client))
+        .collect();
+
+    let total = grants.len();
+    Ok(Json(OAuthGrantListResponse { grants, total }))
+}
+
+pub async fn revoke_grant(
+    State(state): State<Arc<AppState>>,
+    Path(grant_id): Path<Uuid>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.oidc_service.revoke_grant(user_id, grant_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Access revoked",
+        "id": grant_id
+    })))
+}
@@ -0,0 +1,182 @@
+// Authorization policy: what a role may do, and who owns institutions and credentials
+
+use uuid::Uuid;
+
+use common::{ApiKeyScope, AppError, AppResult, InstitutionPermission, InstitutionRole, Permission, UserRole};
+use database::{
+    CredentialDb, CredentialRepository, InstitutionDb, InstitutionMemberRepository, InstitutionRepository,
+};
+use crate::{
+    services::{AppState, document_keys::DocumentRecipient},
+    middleware::{api_key::{UserIdentity, UserOrApiKey}, oauth::UserOrOAuth},
+};
+use crate::services::convert::{parse_institution_role, permission_name, user_role_name};
+
+/// Check the caller's role grants `permission`
+pub fn authorize(role: &UserRole, permission: Permission) -> AppResult<()> {
+    if !role.allows(permission) {
+        return Err(AppError::Authorization(format!(
+            "The {} role lacks the {} permission",
+            user_role_name(role),
+            permission_name(permission),
+        )));
+    }
+    Ok(())
+}
+
+/// The scope an institution API key needs to act with `permission`, if keys may at all
+pub fn api_key_scope_for(permission: Permission) -> Option<ApiKeyScope> {
+    match permission {
+        Permission::CredentialIssue => Some(ApiKeyScope::Issue),
+        Permission::CredentialRevoke => Some(ApiKeyScope::Revoke),
+        Permission::CredentialReadIssued => Some(ApiKeyScope::ReadIssued),
+        _ => None,
+    }
+}
+
+/// Resolve the institution a staff account acts for, checking its account role and
+/// staff role both allow `permission`. `action` completes error messages.
+pub async fn institution_for_staff(
+    state: &AppState,
+    user_id: Uuid,
+    role: &UserRole,
+    permission: InstitutionPermission,
+    action: &str,
+) -> AppResult<(InstitutionDb, InstitutionRole)> {
+    authorize(role, permission.platform_permission())?;
+
+    let membership = state.member_repo
+        .get_membership(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
+
+    let staff_role = parse_institution_role(&membership.role)?;
+    if !staff_role.allows(permission) {
+        return Err(AppError::Authorization(format!(
+            "The {} role is not allowed to {}",
+            membership.role, action,
+        )));
+    }
+
+    let institution = state.institution_repo
+        .get_institution_by_id(membership.institution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
+
+    Ok((institution, staff_role))
+}
+
+/// Resolve the institution a staff account or one of the institution's API keys
+/// acts for. Staff roles must allow `permission` and keys must carry its scope.
+/// Also returns the user accountable for the action: the staff member, or the
+/// user who created the key.
+pub async fn institution_for_caller<U: UserIdentity>(
+    state: &AppState,
+    caller: &UserOrApiKey<U>,
+    permission: InstitutionPermission,
+    action: &str,
+) -> AppResult<(InstitutionDb, Uuid)> {
+    match caller {
+        UserOrApiKey::User(user) => {
+            let (institution, _) = institution_for_staff(state, user.user_id(), user.role(), permission, action).await?;
+            Ok((institution, user.user_id()))
+        }
+        UserOrApiKey::ApiKey(api_key) => {
+            let scope = api_key_scope_for(permission.platform_permission())
+                .ok_or_else(|| AppError::Authorization(format!("API keys cannot {}", action)))?;
+            api_key.require(scope)?;
+            tracing::info!(key_id = %api_key.key_id, institution_id = %api_key.institution_id, "API key used to {}", action);
+            let institution = state.institution_repo
+                .get_institution_by_id(api_key.institution_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Institution not found".to_string()))?;
+            Ok((institution, api_key.created_by))
+        }
+    }
+}
+
+/// Holders and any staff of the issuing institution may view a credential
+pub async fn ensure_can_view(state: &AppState, credential: &CredentialDb, user_id: Uuid) -> AppResult<()> {
+    if credential.holder_id == user_id {
+        return Ok(());
+    }
+
+    let membership = state.member_repo.get_membership(user_id).await?;
+    if membership.is_some_and(|member| member.institution_id == credential.institution_id) {
+        return Ok(());
+    }
+
+    Err(AppError::Authorization("Not authorized to view this credential".to_string()))
+}
+
+/// Whose copy of the document key a caller may open a credential's document
+/// with: the holder's, also for partner portals the holder shared the
+/// credential with, or the issuer's for staff of the issuing institution
+pub async fn document_recipient(
+    state: &AppState,
+    credential: &CredentialDb,
+    caller: &UserOrOAuth,
+) -> AppResult<DocumentRecipient> {
+    match caller {
+        UserOrOAuth::User(user) if credential.holder_id == user.user_id() => Ok(DocumentRecipient::Holder),
+        UserOrOAuth::User(user) => {
+            ensure_can_view(state, credential, user.user_id()).await?;
+            Ok(DocumentRecipient::Issuer)
+        }
+        UserOrOAuth::OAuth(grant) if state.oidc_service.shares_credential(grant, credential) => {
+            Ok(DocumentRecipient::Holder)
+        }
+        UserOrOAuth::OAuth(_) => Err(AppError::Authorization(
+            "The holder has not shared this credential with your application".to_string(),
+        )),
+    }
+}
+
+/// Only the holder may present a credential
+pub fn ensure_holder(credential: &CredentialDb, user_id: Uuid) -> AppResult<()> {
+    if credential.holder_id != user_id {
+        return Err(AppError::Authorization("Only the holder can present this credential".to_string()));
+    }
+    Ok(())
+}
+
+/// Load a credential for a status change, checking the caller's institution issued it
+/// and their staff role allows the change
+pub async fn credential_for_issuer(
+    state: &AppState,
+    credential_id: &str,
+    user_id: Uuid,
+    role: &UserRole,
+    permission: InstitutionPermission,
+    action: &str,
+) -> AppResult<CredentialDb> {
+    let (institution, _) = institution_for_staff(
+        state,
+        user_id,
+        role,
+        permission,
+        &format!("{} credentials", action),
+    ).await?;
+
+    credential_issued_by(state, credential_id, &institution, action).await
+}
+
+/// Load a credential, checking the given institution issued it
+pub async fn credential_issued_by(
+    state: &AppState,
+    credential_id: &str,
+    institution: &InstitutionDb,
+    action: &str,
+) -> AppResult<CredentialDb> {
+    let credential_db = state.credential_repo
+        .get_credential_by_credential_id(credential_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
+
+    // Check the caller's institution issued it
+    if credential_db.institution_id != institution.id {
+        return Err(AppError::Authorization(format!("Not authorized to {} this credential", action)));
+    }
+
+    Ok(credential_db)
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{State, Path},
+    response::Response,
+    body::Body,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use common::{AppError, StatusPurpose};
+use crate::{services::AppState, utils::status_list::STATUS_LIST_TTL_SECS};
+
+/// Serve an issuer's signed status list so verifiers can cache it and check
+/// credential status without calling the verification API
+pub async fn get_status_list(
+    State(state): State<Arc<AppState>>,
+    Path((list_id, purpose)): Path<(Uuid, StatusPurpose)>,
+) -> Result<Response, AppError> {
+    let credential = state.credential_service
+        .status_list_credential(list_id, purpose, &state.config.public_base_url)
+        .await?;
+
+    let body = serde_json::to_vec(&credential)
+        .map_err(|e| AppError::Internal(format!("Failed to encode status list: {}", e)))?;
+
+    let response = Response::builder()
+        .status(200)
+        .header("Content-Type", "application/vc+ld+json")
+        .header("Cache-Control", format!("public, max-age={}", STATUS_LIST_TTL_SECS))
+        .body(Body::from(body))
+        .map_err(|e| AppError::Internal(format!("Failed to create response: {}", e)))?;
+
+    Ok(response)
+}
@@ -0,0 +1,177 @@
+use axum::{
+    extract::{State, Path},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use common::{
+    WalletResponse, WalletCustody, ExportMnemonicRequest, ExportMnemonicResponse,
+    LinkWalletRequest, SignMessageRequest, SignMessageResponse, AppError,
+};
+use database::UserRepository;
+use crate::{
+    services::{AppState, BlockchainService},
+    middleware::{auth::AuthUser, client_ip::ClientIp},
+};
+use super::auth::attempt_email;
+
+/// Message a user must sign with their own key to link an external wallet
+pub fn wallet_link_message(user_id: Uuid, wallet_address: &str) -> String {
+    format!(
+        "Safari Skills Passport wallet link\nUser: {}\nAddress: {}",
+        user_id, wallet_address
+    )
+}
+
+/// What the custodial wallet actually signs for a user's message. The prefix
+/// keeps these signatures from ever answering a wallet login challenge or
+/// wallet link message.
+pub fn wallet_signed_message(message: &str) -> String {
+    format!("Safari Skills Passport signed message\n{}", message)
+}
+
+fn parse_custody(custody: &str) -> WalletCustody {
+    match custody {
+        "custodial" => WalletCustody::Custodial,
+        "external" => WalletCustody::External,
+        _ => WalletCustody::Unmanaged,
+    }
+}
+
+pub async fn get_wallet(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<WalletResponse>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let wallet_key = state.keystore.get_wallet_key(user_id).await?;
+
+    Ok(Json(WalletResponse {
+        wallet_address: user.wallet_address,
+        custody: parse_custody(&user.wallet_custody),
+        mnemonic_exported: wallet_key.is_some_and(|key| key.mnemonic_exported_at.is_some()),
+    }))
+}
+
+pub async fn export_mnemonic(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<ExportMnemonicRequest>,
+) -> Result<Json<ExportMnemonicResponse>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Require the password again before revealing key material
+    state.login_guard
+        .attempt(&attempt_email(&user), user_id, ip, "invalid_password", async {
+            if !state.auth_service.verify_password(&payload.password, &user.password_hash)? {
+                return Err(AppError::Authentication("Invalid credentials".to_string()));
+            }
+            Ok(())
+        })
+        .await?;
+
+    let mnemonic = state.keystore.export_mnemonic(user_id).await?;
+
+    Ok(Json(ExportMnemonicResponse {
+        wallet_address: user.wallet_address,
+        mnemonic,
+    }))
+}
+
+pub async fn get_link_message(
+    Path(wallet_address): Path<String>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    Ok(Json(serde_json::json!({
+        "wallet_address": wallet_address,
+        "message": wallet_link_message(user_id, &wallet_address),
+    })))
+}
+
+pub async fn link_wallet(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<LinkWalletRequest>,
+) -> Result<Json<WalletResponse>, AppError> {
+    // The user must prove control of the address they are linking
+    let message = wallet_link_message(user_id, &payload.wallet_address);
+    if !BlockchainService::verify_wallet_signature(&payload.wallet_address, &message, &payload.signature) {
+        return Err(AppError::Validation("Invalid wallet address or signature".to_string()));
+    }
+
+    if let Some(existing) = state.user_repo.get_user_by_wallet(&payload.wallet_address).await? {
+        if existing.id != user_id {
+            return Err(AppError::Validation("Wallet address is already linked to another account".to_string()));
+        }
+    }
+
+    state.user_repo
+        .update_wallet(user_id, &payload.wallet_address, "external")
+        .await?;
+
+    // The platform no longer holds a key for this user
+    state.keystore.delete_wallet_key(user_id).await?;
+
+    Ok(Json(WalletResponse {
+        wallet_address: payload.wallet_address,
+        custody: WalletCustody::External,
+        mnemonic_exported: false,
+    }))
+}
+
+pub async fn provision_custodial_wallet(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+) -> Result<Json<WalletResponse>, AppError> {
+    let user = state.user_repo
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Only legacy accounts whose seed was never stored need a new wallet
+    if parse_custody(&user.wallet_custody) != WalletCustody::Unmanaged {
+        return Err(AppError::Validation("Account already has a managed wallet".to_string()));
+    }
+
+    let (wallet_address, phrase) = BlockchainService::generate_wallet()?;
+    state.keystore
+        .store_custodial_wallet(user_id, &wallet_address, &phrase)
+        .await?;
+
+    Ok(Json(WalletResponse {
+        wallet_address,
+        custody: WalletCustody::Custodial,
+        mnemonic_exported: false,
+    }))
+}
+
+/// Sign a message with the caller's custodial wallet, so they can prove
+/// control of its address to third parties without exporting the mnemonic
+pub async fn sign_message(
+    State(state): State<Arc<AppState>>,
+    AuthUser(user_id, _): AuthUser,
+    Json(payload): Json<SignMessageRequest>,
+) -> Result<Json<SignMessageResponse>, AppError> {
+    if payload.message.is_empty() {
+        return Err(AppError::Validation("Message is required".to_string()));
+    }
+
+    let signed_message = wallet_signed_message(&payload.message);
+    let (wallet_address, signature) = state.keystore
+        .sign_for_user(user_id, signed_message.as_bytes())
+        .await?;
+
+    Ok(Json(SignMessageResponse {
+        wallet_address,
+        signed_message,
+        signature,
+    }))
+}
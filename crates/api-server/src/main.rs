@@ -4,17 +4,13 @@ mod handlers;
 mod middleware;
 mod utils;
 
-use axum::{
-    Router,
-    routing::{get, post},
-    http::header,
-};
-use tower_http::cors::{CorsLayer, Any};
-use tower_http::trace::TraceLayer;
+use axum::routing::{delete, get, post, put};
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use common::Permission;
 use config::Config;
+use middleware::authorize::{Access, GuardedRouter};
 use services::*;
 
 #[tokio::main]
@@ -48,46 +44,241 @@ async fn main() -> anyhow::Result<()> {
     let app_state = Arc::new(AppState::new(config.clone(), db_pool).await?);
     tracing::info!("Services initialized");
 
-    // Build application routes
-    let app = Router::new()
+    // Expire credentials and send expiry reminders in the background
+    ExpiryWorker::new(app_state.clone()).spawn();
+
+    // Build application routes, each declaring who may call it
+    let app = GuardedRouter::new(app_state.clone())
         // Health check
-        .route("/health", get(handlers::health_check))
+        .route("/health", Access::Public, get(handlers::health_check))
+        .route("/.well-known/jwks.json", Access::Public, get(handlers::auth::jwks))
+        .route("/.well-known/openid-configuration", Access::Public, get(handlers::oauth::openid_configuration))
         
         // Auth routes
-        .route("/api/auth/register", post(handlers::auth::register))
-        .route("/api/auth/login", post(handlers::auth::login))
+        .route("/api/auth/register", Access::Public, post(handlers::auth::register))
+        .route("/api/auth/login", Access::Public, post(handlers::auth::login))
+        .route("/api/auth/login/mfa", Access::Public, post(handlers::auth::login_mfa))
+        .route("/api/auth/wallet/challenge", Access::Public, post(handlers::auth::wallet_challenge))
+        .route("/api/auth/wallet/login", Access::Public, post(handlers::auth::wallet_login))
+        .route("/api/auth/refresh", Access::Public, post(handlers::auth::refresh))
+        .route("/api/auth/logout", Access::Session, post(handlers::auth::logout))
+        .route("/api/auth/logout-all", Access::Session, post(handlers::auth::logout_all))
+        .route("/api/auth/verify-email", Access::Public, post(handlers::auth::verify_email))
+        .route("/api/auth/verify-email/send", Access::User, post(handlers::auth::send_verification_email))
+        .route("/api/auth/forgot-password", Access::Public, post(handlers::auth::forgot_password))
+        .route("/api/auth/reset-password", Access::Public, post(handlers::auth::reset_password))
+        .route("/api/auth/change-password", Access::Session, post(handlers::auth::change_password))
+        .route("/api/auth/2fa", Access::Session, get(handlers::auth::get_two_factor_status))
+        .route("/api/auth/2fa/setup", Access::Session, post(handlers::auth::setup_two_factor))
+        .route("/api/auth/2fa/enable", Access::Session, post(handlers::auth::enable_two_factor))
+        .route("/api/auth/2fa/disable", Access::User, post(handlers::auth::disable_two_factor))
+        .route("/api/auth/2fa/recovery-codes", Access::User, post(handlers::auth::regenerate_recovery_codes))
+
+        // OpenID Connect provider routes
+        .route("/oauth/authorize", Access::Public, get(handlers::oauth::authorize))
+        .route("/oauth/token", Access::Public, post(handlers::oauth::token))
+        .route("/oauth/userinfo", Access::OAuthToken, get(handlers::oauth::userinfo).post(handlers::oauth::userinfo))
+        .route("/api/oauth/authorize", Access::User, get(handlers::oauth::get_consent).post(handlers::oauth::decide))
+        .route("/api/oauth/credentials", Access::OAuthToken, get(handlers::oauth::shared_credentials))
+        .route(
+            "/api/oauth/clients",
+            Access::Permission(Permission::OAuthClientManage),
+            get(handlers::oauth::list_clients).post(handlers::oauth::register_client),
+        )
+        .route(
+            "/api/oauth/clients/:client_id",
+            Access::Permission(Permission::OAuthClientManage),
+            delete(handlers::oauth::revoke_client),
+        )
+        .route("/api/oauth/grants", Access::User, get(handlers::oauth::list_grants))
+        .route("/api/oauth/grants/:grant_id", Access::User, delete(handlers::oauth::revoke_grant))
         
         // Institution routes
-        .route("/api/institutions/register", post(handlers::institutions::register_institution))
-        .route("/api/institutions/me", get(handlers::institutions::get_my_institution))
+        .route(
+            "/api/institutions/register",
+            Access::Permission(Permission::InstitutionRegister),
+            post(handlers::institutions::register_institution),
+        )
+        .route(
+            "/api/institutions/me",
+            Access::Permission(Permission::InstitutionRead),
+            get(handlers::institutions::get_my_institution),
+        )
+        .route(
+            "/api/institutions/me/members",
+            Access::Permission(Permission::InstitutionRead),
+            get(handlers::institutions::list_members),
+        )
+        .route(
+            "/api/institutions/me/members",
+            Access::Permission(Permission::InstitutionManage),
+            post(handlers::institutions::add_member),
+        )
+        .route(
+            "/api/institutions/me/members/:user_id",
+            Access::Permission(Permission::InstitutionManage),
+            put(handlers::institutions::update_member).delete(handlers::institutions::remove_member),
+        )
+        .route(
+            "/api/institutions/me/api-keys",
+            Access::Permission(Permission::InstitutionManage),
+            get(handlers::institutions::list_api_keys).post(handlers::institutions::create_api_key),
+        )
+        .route(
+            "/api/institutions/me/api-keys/:key_id",
+            Access::Permission(Permission::InstitutionManage),
+            delete(handlers::institutions::revoke_api_key),
+        )
+        .route(
+            "/api/institutions/me/api-keys/:key_id/rotate",
+            Access::Permission(Permission::InstitutionManage),
+            post(handlers::institutions::rotate_api_key),
+        )
+        .route(
+            "/api/accreditation-authorities",
+            Access::Public,
+            get(handlers::institutions::list_accreditation_authorities),
+        )
         
-        // Credential routes
-        .route("/api/credentials/issue", post(handlers::credentials::issue_credential))
-        .route("/api/credentials/verify/:credential_id", get(handlers::credentials::verify_credential))
-        .route("/api/credentials/verify-qr", post(handlers::credentials::verify_qr_code))
-        .route("/api/credentials/my", get(handlers::credentials::get_my_credentials))
-        .route("/api/credentials/issued", get(handlers::credentials::get_issued_credentials))
-        .route("/api/credentials/:credential_id", get(handlers::credentials::get_credential))
-        .route("/api/credentials/:credential_id/revoke", post(handlers::credentials::revoke_credential))
-        .route("/api/credentials/:credential_id/qr", get(handlers::credentials::get_credential_qr))
+        // Wallet routes
+        .route("/api/wallet", Access::User, get(handlers::wallet::get_wallet))
+        .route("/api/wallet/export-mnemonic", Access::User, post(handlers::wallet::export_mnemonic))
+        .route("/api/wallet/link-message/:wallet_address", Access::User, get(handlers::wallet::get_link_message))
+        .route("/api/wallet/link", Access::User, post(handlers::wallet::link_wallet))
+        .route("/api/wallet/custodial", Access::User, post(handlers::wallet::provision_custodial_wallet))
+        .route("/api/wallet/sign", Access::User, post(handlers::wallet::sign_message))
         
-        // CORS
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(vec![
-                    header::AUTHORIZATION,
-                    header::CONTENT_TYPE,
-                    header::ACCEPT,
-                ]),
+        // Credential routes
+        .route(
+            "/api/credentials/issue",
+            Access::PermissionOrApiKey(Permission::CredentialIssue),
+            post(handlers::credentials::issue_credential),
         )
+        .route("/api/credentials/verify/:credential_id", Access::Public, get(handlers::credentials::verify_credential))
+        .route("/api/credentials/verify-qr", Access::Public, post(handlers::credentials::verify_qr_code))
+        .route("/api/credentials/vc/verify", Access::Public, post(handlers::credentials::verify_verifiable_credential))
+        .route(
+            "/api/credentials/sd-jwt/verify",
+            Access::Public,
+            post(handlers::credentials::verify_sd_jwt_presentation),
+        )
+        .route("/api/credentials/badges/import", Access::User, post(handlers::credentials::import_open_badge))
+        .route(
+            "/api/credentials/claims",
+            Access::Permission(Permission::CredentialIssue),
+            get(handlers::credentials::list_badge_claims),
+        )
+        .route(
+            "/api/credentials/claims/:credential_id/approve",
+            Access::Permission(Permission::CredentialIssue),
+            post(handlers::credentials::approve_badge_claim),
+        )
+        .route(
+            "/api/credentials/claims/:credential_id/reject",
+            Access::Permission(Permission::CredentialIssue),
+            post(handlers::credentials::reject_badge_claim),
+        )
+        .route("/api/credentials/my", Access::User, get(handlers::credentials::get_my_credentials))
+        .route(
+            "/api/credentials/issued",
+            Access::PermissionOrApiKey(Permission::CredentialReadIssued),
+            get(handlers::credentials::get_issued_credentials),
+        )
+        .route("/api/credentials/:credential_id", Access::User, get(handlers::credentials::get_credential))
+        .route(
+            "/api/credentials/:credential_id/revoke",
+            Access::PermissionOrApiKey(Permission::CredentialRevoke),
+            post(handlers::credentials::revoke_credential),
+        )
+        .route(
+            "/api/credentials/:credential_id/suspend",
+            Access::Permission(Permission::CredentialSuspend),
+            post(handlers::credentials::suspend_credential),
+        )
+        .route(
+            "/api/credentials/:credential_id/reinstate",
+            Access::Permission(Permission::CredentialSuspend),
+            post(handlers::credentials::reinstate_credential),
+        )
+        .route(
+            "/api/credentials/:credential_id/history",
+            Access::User,
+            get(handlers::credentials::get_credential_history),
+        )
+        .route("/api/credentials/:credential_id/qr", Access::User, get(handlers::credentials::get_credential_qr))
+        .route(
+            "/api/credentials/:credential_id/document",
+            Access::UserOrOAuthToken,
+            get(handlers::credentials::download_document),
+        )
+        .route(
+            "/api/credentials/:credential_id/vc",
+            Access::User,
+            get(handlers::credentials::export_verifiable_credential),
+        )
+        .route(
+            "/api/credentials/:credential_id/sd-jwt",
+            Access::User,
+            post(handlers::credentials::create_sd_jwt_presentation),
+        )
+        .route("/api/credentials/:credential_id/badge", Access::User, get(handlers::credentials::export_open_badge))
+
+        // Admin routes
+        .route(
+            "/api/admin/institutions",
+            Access::Permission(Permission::InstitutionAccredit),
+            get(handlers::admin::list_institutions),
+        )
+        .route(
+            "/api/admin/institutions/:institution_id",
+            Access::Permission(Permission::InstitutionAccredit),
+            get(handlers::admin::get_institution),
+        )
+        .route(
+            "/api/admin/institutions/:institution_id/approve",
+            Access::Permission(Permission::InstitutionAccredit),
+            post(handlers::admin::approve_institution),
+        )
+        .route(
+            "/api/admin/institutions/:institution_id/reject",
+            Access::Permission(Permission::InstitutionAccredit),
+            post(handlers::admin::reject_institution),
+        )
+        .route(
+            "/api/admin/institutions/:institution_id/suspend",
+            Access::Permission(Permission::InstitutionAccredit),
+            post(handlers::admin::suspend_institution),
+        )
+        .route(
+            "/api/admin/signing-keys",
+            Access::Permission(Permission::SecurityManage),
+            get(handlers::admin::list_signing_keys),
+        )
+        .route(
+            "/api/admin/signing-keys/rotate",
+            Access::Permission(Permission::SecurityManage),
+            post(handlers::admin::rotate_signing_key),
+        )
+        .route(
+            "/api/admin/document-keys/rotate",
+            Access::Permission(Permission::SecurityManage),
+            post(handlers::admin::rotate_document_keys),
+        )
+        .route(
+            "/api/admin/failed-logins",
+            Access::Permission(Permission::SecurityManage),
+            get(handlers::admin::list_failed_logins),
+        )
+        .route(
+            "/api/admin/users/:user_id/unlock",
+            Access::Permission(Permission::SecurityManage),
+            post(handlers::admin::unlock_user),
+        )
+
+        // Status list routes
+        .route("/api/status-lists/:list_id/:purpose", Access::Public, get(handlers::status_lists::get_status_list))
         
-        // Tracing
-        .layer(TraceLayer::new_for_http())
-        
-        // State
-        .with_state(app_state);
+        .finish();
 
     // Start server
     let addr = format!("{}:{}", config.host, config.port);
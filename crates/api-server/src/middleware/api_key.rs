@@ -0,0 +1,149 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use uuid::Uuid;
+use std::sync::Arc;
+
+use common::{ApiKeyScope, AppError, AppResult, UserRole};
+use crate::services::{
+    AppState,
+    convert::{api_key_scope_name, parse_api_key_scope},
+};
+use super::auth::{AuthUser, VerifiedUser};
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// An institution API key presented in the `X-API-Key` header
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    pub key_id: Uuid,
+    pub institution_id: Uuid,
+    pub created_by: Uuid, // Accountable for what the key does
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+impl ApiKeyAuth {
+    pub fn require(&self, scope: ApiKeyScope) -> AppResult<()> {
+        if !self.scopes.contains(&scope) {
+            return Err(AppError::Authorization(format!(
+                "API key lacks the {} scope",
+                api_key_scope_name(scope),
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Either a user, authenticated by the extractor `U`, or an institution API
+/// key. Requests carrying an `X-API-Key` header are always treated as the key.
+pub enum UserOrApiKey<U> {
+    User(U),
+    ApiKey(ApiKeyAuth),
+}
+
+/// An API key if the request carries one, for endpoints that are also public
+pub struct OptionalApiKey(pub Option<ApiKeyAuth>);
+
+impl OptionalApiKey {
+    /// Anonymous callers pass; a key that was presented must carry the scope
+    pub fn require(&self, scope: ApiKeyScope) -> AppResult<()> {
+        self.0.as_ref().map_or(Ok(()), |api_key| api_key.require(scope))
+    }
+}
+
+/// User extractors that identify the caller and their role
+pub trait UserIdentity {
+    fn user_id(&self) -> Uuid;
+    fn role(&self) -> &UserRole;
+}
+
+impl UserIdentity for AuthUser {
+    fn user_id(&self) -> Uuid {
+        self.0
+    }
+
+    fn role(&self) -> &UserRole {
+        &self.1
+    }
+}
+
+impl UserIdentity for VerifiedUser {
+    fn user_id(&self) -> Uuid {
+        self.0
+    }
+
+    fn role(&self) -> &UserRole {
+        &self.1
+    }
+}
+
+/// Check the `X-API-Key` header, if present. A present but unusable key is
+/// rejected rather than ignored. The key is checked once per request.
+async fn authenticate_api_key(parts: &mut Parts, state: &AppState) -> Result<Option<ApiKeyAuth>, (StatusCode, String)> {
+    if let Some(api_key) = parts.extensions.get::<ApiKeyAuth>() {
+        return Ok(Some(api_key.clone()));
+    }
+    let Some(value) = parts.headers.get(API_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid API key".to_string()))?;
+
+    let row = state.api_key_service
+        .authenticate(key.trim())
+        .await
+        .map_err(|e| match e {
+            AppError::Authentication(message) => (StatusCode::UNAUTHORIZED, message),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+
+    let scopes = row.scopes
+        .iter()
+        .map(|scope| parse_api_key_scope(scope))
+        .collect::<AppResult<_>>()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let api_key = ApiKeyAuth {
+        key_id: row.id,
+        institution_id: row.institution_id,
+        created_by: row.created_by,
+        scopes,
+    };
+    parts.extensions.insert(api_key.clone());
+
+    Ok(Some(api_key))
+}
+
+#[async_trait]
+impl<U> FromRequestParts<Arc<AppState>> for UserOrApiKey<U>
+where
+    U: FromRequestParts<Arc<AppState>, Rejection = (StatusCode, String)> + Send,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(api_key) = authenticate_api_key(parts, state).await? {
+            return Ok(UserOrApiKey::ApiKey(api_key));
+        }
+
+        Ok(UserOrApiKey::User(U::from_request_parts(parts, state).await?))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for OptionalApiKey {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(OptionalApiKey(authenticate_api_key(parts, state).await?))
+    }
+}
@@ -10,10 +10,98 @@ use axum_extra::{
 };
 use uuid::Uuid;
 use std::sync::Arc;
+use common::UserRole;
+use database::UserRepository;
 
 use crate::services::AppState;
 
-pub struct AuthUser(pub Uuid, pub String); // (user_id, role)
+pub struct AuthUser(pub Uuid, pub UserRole); // (user_id, role)
+
+/// An authenticated user who has verified their email address, required for
+/// sensitive actions such as issuing credentials
+pub struct VerifiedUser(pub Uuid, pub UserRole); // (user_id, role)
+
+/// The authenticated user's login session, for endpoints that act on it.
+/// Unlike the other extractors it does not demand two-factor authentication,
+/// so users whose role requires it can still enroll and log out.
+pub struct AuthSession(pub Uuid, pub Uuid); // (session_id, user_id)
+
+/// A verified bearer token, kept in the request extensions so the route
+/// guard and the handler's extractors check it only once
+#[derive(Clone)]
+struct Authenticated {
+    user_id: Uuid,
+    session_id: Uuid,
+    role: UserRole,
+    mfa_verified: bool,
+}
+
+/// Verify the bearer token and check its session has not been revoked.
+/// With `enforce_mfa`, sessions of roles that require two-factor
+/// authentication must have completed it. Returns (user_id, session_id, role).
+async fn authenticate(
+    parts: &mut Parts,
+    state: &Arc<AppState>,
+    enforce_mfa: bool,
+) -> Result<(Uuid, Uuid, UserRole), (StatusCode, String)> {
+    let auth = match parts.extensions.get::<Authenticated>() {
+        Some(auth) => auth.clone(),
+        None => {
+            let auth = verify_bearer(parts, state).await?;
+            parts.extensions.insert(auth.clone());
+            auth
+        }
+    };
+
+    if enforce_mfa && !auth.mfa_verified && state.mfa_service.is_required_for(&auth.role) {
+        return Err((StatusCode::FORBIDDEN, "Two-factor authentication required".to_string()));
+    }
+
+    Ok((auth.user_id, auth.session_id, auth.role))
+}
+
+async fn verify_bearer(parts: &mut Parts, state: &Arc<AppState>) -> Result<Authenticated, (StatusCode, String)> {
+    // Extract the token from the Authorization header
+    let TypedHeader(Authorization(bearer)) = parts
+        .extract::<TypedHeader<Authorization<Bearer>>>()
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid authorization header".to_string(),
+            )
+        })?;
+
+    // Verify the token
+    let claims = state
+        .auth_service
+        .verify_token(bearer.token())
+        .await
+        .map_err(|e| (StatusCode::UNAUTHORIZED, format!("Invalid token: {}", e)))?;
+
+    // Parse user and session IDs
+    let (Ok(user_id), Ok(session_id)) = (Uuid::parse_str(&claims.sub), Uuid::parse_str(&claims.sid)) else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid user ID in token".to_string(),
+        ));
+    };
+
+    // Tokens die with their session on logout
+    let session = state
+        .session_service
+        .get_active_session(session_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Session has been revoked".to_string()))?;
+
+    Ok(Authenticated {
+        user_id,
+        session_id,
+        role: claims.role,
+        mfa_verified: session.mfa_verified_at.is_some(),
+    })
+}
 
 #[async_trait]
 impl FromRequestParts<Arc<AppState>> for AuthUser {
@@ -23,31 +111,45 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
-        // Extract the token from the Authorization header
-        let TypedHeader(Authorization(bearer)) = parts
-            .extract::<TypedHeader<Authorization<Bearer>>>()
+        let (user_id, _, role) = authenticate(parts, state, true).await?;
+
+        Ok(AuthUser(user_id, role))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AuthSession {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let (user_id, session_id, _) = authenticate(parts, state, false).await?;
+
+        Ok(AuthSession(session_id, user_id))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for VerifiedUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let (user_id, _, role) = authenticate(parts, state, true).await?;
+
+        let verified = state.user_repo
+            .get_user_by_id(user_id)
             .await
-            .map_err(|_| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    "Missing or invalid authorization header".to_string(),
-                )
-            })?;
-
-        // Verify the token
-        let claims = state
-            .auth_service
-            .verify_token(bearer.token())
-            .map_err(|e| (StatusCode::UNAUTHORIZED, format!("Invalid token: {}", e)))?;
-
-        // Parse user ID
-        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Invalid user ID in token".to_string(),
-            )
-        })?;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .is_some_and(|user| user.is_verified);
+        if !verified {
+            return Err((StatusCode::FORBIDDEN, "Email address not verified".to_string()));
+        }
 
-        Ok(AuthUser(user_id, claims.role))
+        Ok(VerifiedUser(user_id, role))
     }
 }
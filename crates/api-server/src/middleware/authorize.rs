@@ -0,0 +1,138 @@
+use axum::{
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, FromRequestParts, Request, State},
+    http::{header, request::Parts},
+    middleware::{from_fn_with_state, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+    Router,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower_http::{cors::{Any, CorsLayer}, trace::TraceLayer};
+
+use common::{AppError, Permission};
+use crate::{
+    handlers::policy::{api_key_scope_for, authorize},
+    services::AppState,
+};
+use super::{
+    api_key::UserOrApiKey,
+    auth::{AuthSession, AuthUser},
+    oauth::{OAuthAccess, UserOrOAuth},
+};
+
+/// Who may call a route. Ownership of the resources a route touches is
+/// checked by its handler through `handlers::policy`.
+#[derive(Debug, Clone, Copy)]
+pub enum Access {
+    Public, // Routes that also take API keys check their scope themselves
+    Session, // Signed in, even before completing two-factor authentication
+    User, // Any signed-in user
+    Permission(Permission),
+    PermissionOrApiKey(Permission), // Also institution API keys with the matching scope
+    OAuthToken, // Partner portals holding an OpenID Connect access token
+    UserOrOAuthToken, // Any signed-in user, or a partner portal's access token
+}
+
+/// A router whose routes each declare their [`Access`], so none is left
+/// unguarded by omission. A path may be added again for methods that need
+/// different access. The inner router is never handed out, so routes cannot
+/// be added around the guard.
+pub struct GuardedRouter {
+    router: Router<Arc<AppState>>,
+    state: Arc<AppState>,
+}
+
+impl GuardedRouter {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self {
+            router: Router::new(),
+            state,
+        }
+    }
+
+    pub fn route(self, path: &str, access: Access, method_router: MethodRouter<Arc<AppState>>) -> Self {
+        let method_router = method_router.route_layer(from_fn_with_state((self.state.clone(), access), guard));
+
+        Self {
+            router: self.router.route(path, method_router),
+            state: self.state,
+        }
+    }
+
+    /// Apply CORS and request tracing, and build the service to serve
+    pub fn finish(self) -> IntoMakeServiceWithConnectInfo<Router, SocketAddr> {
+        self.router
+            // CORS
+            .layer(
+                CorsLayer::new()
+                    .allow_origin(Any)
+                    .allow_methods(Any)
+                    .allow_headers(vec![
+                        header::AUTHORIZATION,
+                        header::CONTENT_TYPE,
+                        header::ACCEPT,
+                    ]),
+            )
+
+            // Tracing
+            .layer(TraceLayer::new_for_http())
+
+            // State
+            .with_state(self.state)
+            .into_make_service_with_connect_info::<SocketAddr>()
+    }
+}
+
+async fn guard(
+    State((state, access)): State<(Arc<AppState>, Access)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    if let Err(rejection) = check(&mut parts, &state, access).await {
+        return rejection;
+    }
+
+    // The handler's extractors reuse what was authenticated here
+    next.run(Request::from_parts(parts, body)).await
+}
+
+async fn check(parts: &mut Parts, state: &Arc<AppState>, access: Access) -> Result<(), Response> {
+    match access {
+        Access::Public => {}
+        Access::Session => {
+            AuthSession::from_request_parts(parts, state).await.map_err(IntoResponse::into_response)?;
+        }
+        Access::User => {
+            AuthUser::from_request_parts(parts, state).await.map_err(IntoResponse::into_response)?;
+        }
+        Access::Permission(permission) => {
+            let AuthUser(_, role) = AuthUser::from_request_parts(parts, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            authorize(&role, permission).map_err(IntoResponse::into_response)?;
+        }
+        Access::PermissionOrApiKey(permission) => {
+            let caller = UserOrApiKey::<AuthUser>::from_request_parts(parts, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            let allowed = match caller {
+                UserOrApiKey::User(AuthUser(_, role)) => authorize(&role, permission),
+                UserOrApiKey::ApiKey(api_key) => match api_key_scope_for(permission) {
+                    Some(scope) => api_key.require(scope),
+                    None => Err(AppError::Authorization("API keys cannot be used here".to_string())),
+                },
+            };
+            allowed.map_err(IntoResponse::into_response)?;
+        }
+        Access::OAuthToken => {
+            OAuthAccess::from_request_parts(parts, state).await.map_err(IntoResponse::into_response)?;
+        }
+        Access::UserOrOAuthToken => {
+            UserOrOAuth::from_request_parts(parts, state).await.map_err(IntoResponse::into_response)?;
+        }
+    }
+
+    Ok(())
+}
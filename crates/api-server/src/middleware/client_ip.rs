@@ -0,0 +1,90 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap, StatusCode},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::services::AppState;
+
+/// Address of the client making the request. Behind reverse proxies, set
+/// TRUST_PROXY_HEADERS and TRUSTED_PROXY_HOPS so the address the outermost
+/// trusted proxy saw is used instead of the nearest proxy's own address.
+pub struct ClientIp(pub IpAddr);
+
+/// The client address recorded by the outermost of `hops` trusted proxies.
+/// Each proxy appends the address it received the request from, so only the
+/// rightmost `hops` entries are trustworthy; anything further left was sent
+/// by the client. None if the header is missing, malformed or too short.
+fn forwarded_client(headers: &HeaderMap, hops: usize) -> Option<IpAddr> {
+    if hops == 0 {
+        return None;
+    }
+
+    let entries: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .map(|value| value.to_str().ok())
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    entries
+        .len()
+        .checked_sub(hops)
+        .and_then(|index| entries[index].trim().parse().ok())
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ClientIp {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if state.config.trust_proxy_headers {
+            if let Some(ip) = forwarded_client(&parts.headers, state.config.trusted_proxy_hops) {
+                return Ok(ClientIp(ip));
+            }
+        }
+
+        parts.extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| ClientIp(addr.ip()))
+            .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "Client address unavailable".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    fn headers(forwarded_for: &[&str]) -> HeaderMap {
+        let mut request = Request::builder();
+        for value in forwarded_for {
+            request = request.header("X-Forwarded-For", *value);
+        }
+        request.body(()).unwrap().into_parts().0.headers
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_for_is_ignored() {
+        // The client sent "6.6.6.6"; the proxy appended the address it saw
+        let spoofed = headers(&["6.6.6.6, 203.0.113.7"]);
+        assert_eq!(forwarded_client(&spoofed, 1), Some("203.0.113.7".parse().unwrap()));
+
+        // Behind two proxies, the inner proxy's entry is the outer proxy
+        let spoofed = headers(&["6.6.6.6, 203.0.113.7", "10.0.0.2"]);
+        assert_eq!(forwarded_client(&spoofed, 2), Some("203.0.113.7".parse().unwrap()));
+
+        // Fewer entries than trusted proxies means the header cannot be trusted
+        assert_eq!(forwarded_client(&headers(&["203.0.113.7"]), 2), None);
+        assert_eq!(forwarded_client(&headers(&["6.6.6.6, not-an-ip"]), 1), None);
+        assert_eq!(forwarded_client(&headers(&[]), 1), None);
+        assert_eq!(forwarded_client(&spoofed, 0), None);
+    }
+}
@@ -1 +1,5 @@
 pub mod auth;
+pub mod api_key;
+pub mod client_ip;
+pub mod oauth;
+pub mod authorize;
@@ -0,0 +1,86 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    RequestPartsExt,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use std::sync::Arc;
+
+use common::AppError;
+use database::OAuthGrantDb;
+use crate::services::AppState;
+use super::auth::AuthUser;
+
+/// An access token issued to a partner portal through the OpenID Connect
+/// flow, carrying the grant the user consented to. Session access tokens are
+/// not accepted here, nor these tokens elsewhere.
+pub struct OAuthAccess(pub OAuthGrantDb);
+
+/// Either a signed-in user or a partner portal holding an OpenID Connect
+/// access token. Session access tokens are JWTs while OpenID Connect ones are
+/// opaque, so the bearer token's shape decides which is checked.
+pub enum UserOrOAuth {
+    User(AuthUser),
+    OAuth(OAuthGrantDb),
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for OAuthAccess {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(grant) = parts.extensions.get::<OAuthGrantDb>() {
+            return Ok(OAuthAccess(grant.clone()));
+        }
+
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "Missing or invalid authorization header".to_string(),
+                )
+            })?;
+
+        let grant = state.oidc_service
+            .authenticate_access_token(bearer.token())
+            .await
+            .map_err(|e| match e {
+                AppError::Authentication(message) => (StatusCode::UNAUTHORIZED, message),
+                e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            })?;
+
+        parts.extensions.insert(grant.clone());
+        Ok(OAuthAccess(grant))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for UserOrOAuth {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let is_jwt = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .is_ok_and(|TypedHeader(Authorization(bearer))| bearer.token().contains('.'));
+
+        if is_jwt {
+            Ok(UserOrOAuth::User(AuthUser::from_request_parts(parts, state).await?))
+        } else {
+            let OAuthAccess(grant) = OAuthAccess::from_request_parts(parts, state).await?;
+            Ok(UserOrOAuth::OAuth(grant))
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use common::{ApiKeyScope, AppError, AppResult};
+use database::{ApiKeyDb, ApiKeyRepository, ApiKeyRepositoryImpl};
+use super::convert::api_key_scope_name;
+use super::session::{hash_token, random_token};
+
+/// Every key starts with this, so leaked keys are easy to spot in code and logs
+const KEY_TAG: &str = "ssp";
+const DEFAULT_TTL_DAYS: i64 = 365;
+const MAX_TTL_DAYS: i64 = 730;
+const MAX_NAME_LEN: usize = 100;
+
+/// A new key, `ssp_<prefix>_<secret>`, and its prefix. The prefix is public
+/// and finds the row; only a hash of the whole key is stored.
+fn new_key() -> (String, String) {
+    let mut id = [0u8; 6];
+    OsRng.fill_bytes(&mut id);
+    let prefix = format!("{}_{}", KEY_TAG, hex::encode(id));
+    let key = format!("{}_{}", prefix, random_token());
+    (prefix, key)
+}
+
+/// The prefix of a well-formed key. The hex part never contains `_`, though the secret may.
+fn key_prefix(key: &str) -> Option<&str> {
+    let rest = key.strip_prefix(KEY_TAG)?.strip_prefix('_')?;
+    let (id, secret) = rest.split_once('_')?;
+    if id.is_empty() || secret.is_empty() {
+        return None;
+    }
+    Some(&key[..KEY_TAG.len() + 1 + id.len()])
+}
+
+/// API keys that let an institution's own systems, such as a student
+/// information system, act for it with a limited set of scopes
+pub struct ApiKeyService {
+    api_key_repo: Arc<ApiKeyRepositoryImpl>,
+}
+
+impl ApiKeyService {
+    pub fn new(api_key_repo: Arc<ApiKeyRepositoryImpl>) -> Self {
+        Self { api_key_repo }
+    }
+
+    fn new_row(
+        institution_id: Uuid,
+        created_by: Uuid,
+        name: String,
+        scopes: Vec<String>,
+        ttl: Duration,
+    ) -> (ApiKeyDb, String) {
+        let (prefix, key) = new_key();
+        let now = Utc::now();
+
+        let row = ApiKeyDb {
+            id: Uuid::new_v4(),
+            institution_id,
+            name,
+            prefix,
+            key_hash: hash_token(&key),
+            scopes,
+            created_by,
+            created_at: now,
+            expires_at: now + ttl,
+            last_used_at: None,
+            revoked_at: None,
+            replaced_by: None,
+        };
+        (row, key)
+    }
+
+    /// Create a key, returning it with its secret, which is not stored
+    pub async fn create(
+        &self,
+        institution_id: Uuid,
+        created_by: Uuid,
+        name: &str,
+        scopes: &[ApiKeyScope],
+        expires_in_days: Option<i64>,
+    ) -> AppResult<(ApiKeyDb, String)> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(AppError::Validation(format!("Name must be 1 to {} characters", MAX_NAME_LEN)));
+        }
+        if scopes.is_empty() {
+            return Err(AppError::Validation("At least one scope is required".to_string()));
+        }
+        let days = expires_in_days.unwrap_or(DEFAULT_TTL_DAYS);
+        if !(1..=MAX_TTL_DAYS).contains(&days) {
+            return Err(AppError::Validation(format!("expires_in_days must be between 1 and {}", MAX_TTL_DAYS)));
+        }
+
+        let mut scope_names: Vec<String> = Vec::new();
+        for scope in scopes {
+            let scope = api_key_scope_name(*scope).to_string();
+            if !scope_names.contains(&scope) {
+                scope_names.push(scope);
+            }
+        }
+
+        let (row, key) = Self::new_row(institution_id, created_by, name.to_string(), scope_names, Duration::days(days));
+        let created = self.api_key_repo.create_api_key(&row).await?;
+
+        tracing::info!(institution_id = %institution_id, key_id = %created.id, "API key created");
+        Ok((created, key))
+    }
+
+    /// Look up an active key from the secret a client presented, recording its use
+    pub async fn authenticate(&self, key: &str) -> AppResult<ApiKeyDb> {
+        let invalid = || AppError::Authentication("Invalid API key".to_string());
+
+        let prefix = key_prefix(key).ok_or_else(invalid)?;
+        let row = self.api_key_repo
+            .get_api_key_by_prefix(prefix)
+            .await?
+            .filter(|row| row.key_hash == hash_token(key))
+            .ok_or_else(invalid)?;
+
+        if row.revoked_at.is_some() {
+            return Err(AppError::Authentication("API key has been revoked".to_string()));
+        }
+        if row.expires_at <= Utc::now() {
+            return Err(AppError::Authentication("API key has expired".to_string()));
+        }
+
+        self.api_key_repo.touch_api_key(row.id).await?;
+        Ok(row)
+    }
+
+    /// All keys of an institution, newest first, including revoked ones
+    pub async fn list(&self, institution_id: Uuid) -> AppResult<Vec<ApiKeyDb>> {
+        self.api_key_repo.get_api_keys(institution_id).await
+    }
+
+    pub async fn revoke(&self, institution_id: Uuid, key_id: Uuid) -> AppResult<()> {
+        if !self.api_key_repo.revoke_api_key(institution_id, key_id).await? {
+            return Err(AppError::NotFound("Active API key not found".to_string()));
+        }
+
+        tracing::info!(institution_id = %institution_id, key_id = %key_id, "API key revoked");
+        Ok(())
+    }
+
+    /// Replace a key with a new secret carrying the same name, scopes and
+    /// lifetime. The old key stops working immediately.
+    pub async fn rotate(&self, institution_id: Uuid, key_id: Uuid, rotated_by: Uuid) -> AppResult<(ApiKeyDb, String)> {
+        let not_found = || AppError::NotFound("Active API key not found".to_string());
+
+        let old = self.api_key_repo
+            .get_api_key(institution_id, key_id)
+            .await?
+            .filter(|old| old.revoked_at.is_none())
+            .ok_or_else(not_found)?;
+
+        let ttl = old.expires_at - old.created_at;
+        let (row, key) = Self::new_row(institution_id, rotated_by, old.name, old.scopes, ttl);
+        let created = self.api_key_repo
+            .rotate_api_key(institution_id, key_id, &row)
+            .await?
+            .ok_or_else(not_found)?;
+
+        tracing::info!(institution_id = %institution_id, old_key_id = %key_id, key_id = %created.id, "API key rotated");
+        Ok((created, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_prefix_is_recovered_from_the_key() {
+        for _ in 0..20 {
+            let (prefix, key) = new_key();
+            assert_eq!(key_prefix(&key), Some(prefix.as_str()));
+        }
+
+        assert_eq!(key_prefix("ssp_abc"), None);
+        assert_eq!(key_prefix("ssp__secret"), None);
+        assert_eq!(key_prefix("other_abc_secret"), None);
+    }
+}
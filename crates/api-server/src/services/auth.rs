@@ -1,68 +1,628 @@
-use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use jsonwebtoken::{encode, decode, decode_header, Algorithm, Header, Validation, EncodingKey, DecodingKey, jwk::Jwk};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2, Params,
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, sr25519};
+use tokio::sync::RwLock;
 use uuid::Uuid;
-use chrono::{Utc, Duration};
-use common::{AppError, AppResult, UserRole};
+use chrono::{DateTime, Utc, Duration, SecondsFormat};
+use common::{AppError, AppResult, SealPurpose, UserRole};
+use database::{
+    JwtKeyRepository, JwtKeyRepositoryImpl, JwtSigningKeyDb, WalletLoginChallengeDb, WalletLoginRepository,
+    WalletLoginRepositoryImpl,
+};
+use crate::config::Config;
+use super::{BlockchainService, KeystoreService, PasswordPolicy};
+
+/// How long other instances may keep signing with a key after it is rotated out
+const KEY_REFRESH_INTERVAL: StdDuration = StdDuration::from_secs(60);
+/// Minimum gap between reloads triggered by an unknown `kid`
+const UNKNOWN_KID_RELOAD_INTERVAL: StdDuration = StdDuration::from_secs(5);
+/// Clock skew tolerated when validating `exp`, matching `Validation::default()`
+const VALIDATION_LEEWAY_SECS: i64 = 60;
+/// How long a wallet login message can be signed and submitted
+const WALLET_CHALLENGE_TTL_MINUTES: i64 = 5;
+/// Prefix of PHC-format argon2 hashes; anything else is a bcrypt hash
+const ARGON2_PREFIX: &str = "$argon2";
+
+/// How new and upgraded password hashes are computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    Bcrypt,
+    Argon2id,
+}
+
+pub fn parse_password_hash_algorithm(name: &str) -> anyhow::Result<PasswordHashAlgorithm> {
+    match name {
+        "bcrypt" => Ok(PasswordHashAlgorithm::Bcrypt),
+        "argon2id" => Ok(PasswordHashAlgorithm::Argon2id),
+        _ => Err(anyhow::anyhow!("PASSWORD_HASH_ALGORITHM must be bcrypt or argon2id, got {}", name)),
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,  // User ID
     pub email: String,
-    pub role: String,
+    pub role: UserRole,
+    pub sid: String,  // Session ID; revoking the session invalidates the token
+    #[serde(default)]
+    pub amr: Vec<String>, // Authentication methods: "pwd" or "swk" (wallet), plus "otp" after a second factor
     pub exp: i64,     // Expiration timestamp
     pub iat: i64,     // Issued at timestamp
 }
 
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: serde_json::Value,
+}
+
+/// Keys loaded from the database: the one that signs new tokens and every key
+/// whose tokens may still be live
+struct KeyRing {
+    signing: Option<SigningKey>,
+    verification: HashMap<String, VerificationKey>,
+    loaded_at: Instant,
+}
+
+/// Issues and verifies access tokens signed with rotating EdDSA or ES256 keys.
+///
+/// Tokens carry the signing key's id in the `kid` header. Rotated-out keys keep
+/// verifying until the tokens they signed have expired, so rotation does not
+/// log anyone out.
 pub struct AuthService {
-    jwt_secret: String,
-    jwt_expiration_hours: i64,
+    algorithm: Algorithm,
+    access_token_ttl_minutes: i64,
+    key_repo: Arc<JwtKeyRepositoryImpl>,
+    wallet_login_repo: Arc<WalletLoginRepositoryImpl>,
+    keystore: Arc<KeystoreService>,
+    keys: RwLock<KeyRing>,
+    public_base_url: String, // Origin named in wallet login messages
+    password_policy: PasswordPolicy,
+    password_hash_algorithm: PasswordHashAlgorithm,
+    bcrypt_cost: u32,
+    dummy_password_hash: String, // Checked for unknown accounts, so they take as long as known ones
+}
+
+/// Whether a stored hash is weaker than what is now configured: a bcrypt cost
+/// below `bcrypt_cost`, bcrypt while migrating to argon2id, or argon2 with
+/// weaker parameters. Hashes are never downgraded.
+fn hash_needs_upgrade(hash: &str, algorithm: PasswordHashAlgorithm, bcrypt_cost: u32) -> bool {
+    if hash.starts_with(ARGON2_PREFIX) {
+        if algorithm != PasswordHashAlgorithm::Argon2id {
+            return false;
+        }
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        let target = Params::default();
+        return parsed.algorithm != argon2::Algorithm::Argon2id.ident()
+            || Params::try_from(&parsed)
+                .map_or(true, |params| params.m_cost() < target.m_cost() || params.t_cost() < target.t_cost());
+    }
+
+    match algorithm {
+        PasswordHashAlgorithm::Argon2id => true,
+        PasswordHashAlgorithm::Bcrypt => hash
+            .parse::<bcrypt::HashParts>()
+            .is_ok_and(|parts| parts.get_cost() < bcrypt_cost),
+    }
+}
+
+/// Sign-in message in the Sign-In with Substrate (SIWS) format, modelled on
+/// EIP-4361. Binding the domain, nonce and expiry keeps a signature from being
+/// replayed elsewhere or later.
+fn wallet_login_message(
+    base_url: &str,
+    wallet_address: &str,
+    nonce: &str,
+    issued_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+) -> String {
+    let domain = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+
+    format!(
+        "{domain} wants you to sign in with your Substrate account:\n\
+         {wallet_address}\n\
+         \n\
+         Sign in to Safari Skills Passport.\n\
+         \n\
+         URI: {base_url}\n\
+         Version: 1\n\
+         Nonce: {nonce}\n\
+         Issued At: {}\n\
+         Expiration Time: {}",
+        issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+pub fn parse_jwt_algorithm(name: &str) -> anyhow::Result<Algorithm> {
+    match name {
+        "EdDSA" => Ok(Algorithm::EdDSA),
+        "ES256" => Ok(Algorithm::ES256),
+        _ => Err(anyhow::anyhow!("JWT_ALGORITHM must be EdDSA or ES256, got {}", name)),
+    }
+}
+
+fn algorithm_name(algorithm: Algorithm) -> &'static str {
+    match algorithm {
+        Algorithm::ES256 => "ES256",
+        _ => "EdDSA",
+    }
+}
+
+/// Generate a key pair, returning the PKCS#8 private key and the public JWK
+fn generate_key_pair(algorithm: Algorithm, kid: &str) -> AppResult<(Vec<u8>, serde_json::Value)> {
+    let rng = SystemRandom::new();
+    let failed = || AppError::Internal("Failed to generate JWT signing key".to_string());
+
+    match algorithm {
+        Algorithm::ES256 => {
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).map_err(|_| failed())?;
+            let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .map_err(|_| failed())?;
+            // Uncompressed point: 0x04 || x || y
+            let point = pair.public_key().as_ref();
+            let jwk = serde_json::json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+                "kid": kid,
+                "alg": "ES256",
+                "use": "sig",
+            });
+            Ok((pkcs8.as_ref().to_vec(), jwk))
+        }
+        _ => {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).map_err(|_| failed())?;
+            let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+                .map_err(|_| failed())?;
+            let jwk = serde_json::json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+                "kid": kid,
+                "alg": "EdDSA",
+                "use": "sig",
+            });
+            Ok((pkcs8.as_ref().to_vec(), jwk))
+        }
+    }
 }
 
 impl AuthService {
-    pub fn new(jwt_secret: String, jwt_expiration_hours: i64) -> Self {
-        Self {
-            jwt_secret,
-            jwt_expiration_hours,
+    pub fn new(
+        config: &Config,
+        key_repo: Arc<JwtKeyRepositoryImpl>,
+        wallet_login_repo: Arc<WalletLoginRepositoryImpl>,
+        keystore: Arc<KeystoreService>,
+    ) -> anyhow::Result<Self> {
+        if !(4..=31).contains(&config.bcrypt_cost) {
+            return Err(anyhow::anyhow!("BCRYPT_COST must be between 4 and 31, got {}", config.bcrypt_cost));
+        }
+
+        let mut service = Self {
+            algorithm: parse_jwt_algorithm(&config.jwt_algorithm)?,
+            access_token_ttl_minutes: config.access_token_ttl_minutes,
+            key_repo,
+            wallet_login_repo,
+            keystore,
+            keys: RwLock::new(KeyRing {
+                signing: None,
+                verification: HashMap::new(),
+                loaded_at: Instant::now(),
+            }),
+            public_base_url: config.public_base_url.trim_end_matches('/').to_string(),
+            password_policy: PasswordPolicy::new(config)?,
+            password_hash_algorithm: parse_password_hash_algorithm(&config.password_hash_algorithm)?,
+            bcrypt_cost: config.bcrypt_cost,
+            dummy_password_hash: String::new(),
+        };
+        service.dummy_password_hash = service.hash_password("unknown-account")?;
+
+        Ok(service)
+    }
+
+    /// Load the signing keys, creating the first one on a fresh database
+    pub async fn init(&self) -> AppResult<()> {
+        self.reload_keys().await?;
+        if self.keys.read().await.signing.is_some() {
+            return Ok(());
+        }
+
+        let key = self.new_signing_key()?;
+        if self.key_repo.create_first_key(&key).await?.is_some() {
+            tracing::info!(kid = %key.id, "Created JWT signing key");
+        }
+        self.reload_keys().await
+    }
+
+    /// Lifetime of access tokens, in seconds
+    pub fn access_token_ttl_secs(&self) -> i64 {
+        self.access_token_ttl_minutes * 60
+    }
+
+    /// Origin of this server, without a trailing slash
+    pub fn public_base_url(&self) -> &str {
+        &self.public_base_url
+    }
+
+    fn new_signing_key(&self) -> AppResult<JwtSigningKeyDb> {
+        let id = Uuid::new_v4();
+        let (private_key, public_jwk) = generate_key_pair(self.algorithm, &id.to_string())?;
+
+        Ok(JwtSigningKeyDb {
+            id,
+            algorithm: algorithm_name(self.algorithm).to_string(),
+            public_jwk,
+            encrypted_private_key: self.keystore.seal(SealPurpose::TokenSigningKey, id, &private_key)?,
+            created_at: Utc::now(),
+            rotated_out_at: None,
+        })
+    }
+
+    /// How long a rotated-out key can still have unexpired tokens
+    fn verification_grace(&self) -> Duration {
+        Duration::minutes(self.access_token_ttl_minutes)
+            + Duration::from_std(KEY_REFRESH_INTERVAL).unwrap_or_else(|_| Duration::zero())
+            + Duration::seconds(VALIDATION_LEEWAY_SECS)
+    }
+
+    async fn reload_keys(&self) -> AppResult<()> {
+        let keys_db = self.key_repo.get_verification_keys(Utc::now() - self.verification_grace()).await?;
+
+        let mut signing = None;
+        let mut verification = HashMap::with_capacity(keys_db.len());
+        for key_db in keys_db {
+            let kid = key_db.id.to_string();
+            let algorithm = parse_jwt_algorithm(&key_db.algorithm)
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            let jwk: Jwk = serde_json::from_value(key_db.public_jwk.clone())
+                .map_err(|e| AppError::Internal(format!("Corrupt JWT public key {}: {}", kid, e)))?;
+            let decoding_key = DecodingKey::from_jwk(&jwk)
+                .map_err(|e| AppError::Internal(format!("Corrupt JWT public key {}: {}", kid, e)))?;
+
+            if key_db.rotated_out_at.is_none() {
+                let private_key = self.keystore.open(SealPurpose::TokenSigningKey, key_db.id, &key_db.encrypted_private_key)?;
+                let encoding_key = match algorithm {
+                    Algorithm::ES256 => EncodingKey::from_ec_der(&private_key),
+                    _ => EncodingKey::from_ed_der(&private_key),
+                };
+                signing = Some(SigningKey { kid: kid.clone(), algorithm, key: encoding_key });
+            }
+
+            verification.insert(kid, VerificationKey { algorithm, key: decoding_key, jwk: key_db.public_jwk });
         }
+
+        *self.keys.write().await = KeyRing {
+            signing,
+            verification,
+            loaded_at: Instant::now(),
+        };
+        Ok(())
+    }
+
+    /// Pick up rotations made by other instances
+    async fn refresh_keys_if_stale(&self) -> AppResult<()> {
+        if self.keys.read().await.loaded_at.elapsed() > KEY_REFRESH_INTERVAL {
+            self.reload_keys().await?;
+        }
+        Ok(())
     }
 
-    pub fn generate_token(&self, user_id: Uuid, email: String, role: UserRole) -> AppResult<String> {
+    /// Issue a short-lived access token bound to a login session
+    pub async fn generate_token(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        email: String,
+        role: UserRole,
+        amr: Vec<String>,
+    ) -> AppResult<String> {
+        self.refresh_keys_if_stale().await?;
+
         let now = Utc::now();
-        let expiration = now + Duration::hours(self.jwt_expiration_hours);
+        let expiration = now + Duration::minutes(self.access_token_ttl_minutes);
 
         let claims = Claims {
             sub: user_id.to_string(),
             email,
-            role: format!("{:?}", role).to_lowercase(),
+            role,
+            sid: session_id.to_string(),
+            amr,
             exp: expiration.timestamp(),
             iat: now.timestamp(),
         };
 
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )
-        .map_err(|e| AppError::Authentication(format!("Failed to generate token: {}", e)))
+        self.sign(&claims).await
+    }
+
+    /// Sign an OpenID Connect ID token. It uses the access token lifetime, so
+    /// its key stays in the JWK Set for as long as the token is valid.
+    pub async fn generate_id_token<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        self.refresh_keys_if_stale().await?;
+        self.sign(claims).await
+    }
+
+    /// Algorithm of the key that currently signs tokens
+    pub async fn signing_algorithm(&self) -> AppResult<&'static str> {
+        self.refresh_keys_if_stale().await?;
+
+        let keys = self.keys.read().await;
+        keys.signing
+            .as_ref()
+            .map(|signing| algorithm_name(signing.algorithm))
+            .ok_or_else(|| AppError::Internal("No JWT signing key".to_string()))
+    }
+
+    async fn sign<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let keys = self.keys.read().await;
+        let signing = keys.signing
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("No JWT signing key".to_string()))?;
+
+        let mut header = Header::new(signing.algorithm);
+        header.kid = Some(signing.kid.clone());
+
+        encode(&header, claims, &signing.key)
+            .map_err(|e| AppError::Authentication(format!("Failed to generate token: {}", e)))
+    }
+
+    /// Verify a token against the key named by its `kid` header
+    pub async fn verify_token(&self, token: &str) -> AppResult<Claims> {
+        let header = decode_header(token)
+            .map_err(|e| AppError::Authentication(format!("Invalid token: {}", e)))?;
+        let kid = header.kid
+            .ok_or_else(|| AppError::Authentication("Invalid token: missing kid".to_string()))?;
+
+        self.refresh_keys_if_stale().await?;
+        let unknown_kid = {
+            let keys = self.keys.read().await;
+            !keys.verification.contains_key(&kid) && keys.loaded_at.elapsed() > UNKNOWN_KID_RELOAD_INTERVAL
+        };
+        if unknown_kid {
+            self.reload_keys().await?;
+        }
+
+        let keys = self.keys.read().await;
+        let key = keys.verification
+            .get(&kid)
+            .ok_or_else(|| AppError::Authentication("Invalid token: unknown signing key".to_string()))?;
+
+        decode::<Claims>(token, &key.key, &Validation::new(key.algorithm))
+            .map(|data| data.claims)
+            .map_err(|e| AppError::Authentication(format!("Invalid token: {}", e)))
     }
 
-    pub fn verify_token(&self, token: &str) -> AppResult<Claims> {
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &Validation::default(),
-        )
-        .map(|data| data.claims)
-        .map_err(|e| AppError::Authentication(format!("Invalid token: {}", e)))
+    /// Public keys that currently verify tokens, as a JWK Set
+    pub async fn jwks(&self) -> AppResult<serde_json::Value> {
+        self.refresh_keys_if_stale().await?;
+
+        let keys = self.keys.read().await;
+        let jwks: Vec<&serde_json::Value> = keys.verification.values().map(|key| &key.jwk).collect();
+        Ok(serde_json::json!({ "keys": jwks }))
     }
 
+    /// Start signing with a new key. The previous key keeps verifying until its tokens expire.
+    pub async fn rotate_signing_key(&self) -> AppResult<JwtSigningKeyDb> {
+        let key = self.key_repo.rotate_key(&self.new_signing_key()?).await?;
+        self.reload_keys().await?;
+
+        tracing::info!(kid = %key.id, algorithm = %key.algorithm, "Rotated JWT signing key");
+        Ok(key)
+    }
+
+    /// Keys currently published in the JWK Set, newest first
+    pub async fn verification_keys(&self) -> AppResult<Vec<JwtSigningKeyDb>> {
+        self.key_repo.get_verification_keys(Utc::now() - self.verification_grace()).await
+    }
+
+    /// Reject a new password that does not meet the password policy
+    pub fn check_password_policy(&self, password: &str, email: &str, name: &str) -> AppResult<()> {
+        self.password_policy.check(password, email, name)
+    }
+
+    /// Hash with the configured algorithm and cost
     pub fn hash_password(&self, password: &str) -> AppResult<String> {
-        bcrypt::hash(password, bcrypt::DEFAULT_COST)
-            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+        match self.password_hash_algorithm {
+            PasswordHashAlgorithm::Bcrypt => bcrypt::hash(password, self.bcrypt_cost)
+                .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e))),
+            PasswordHashAlgorithm::Argon2id => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let salt = SaltString::encode_b64(&salt)
+                    .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+                Argon2::default()
+                    .hash_password(password.as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+                    .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+            }
+        }
     }
 
+    /// Check a password against a bcrypt or argon2 hash
     pub fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
-        bcrypt::verify(password, hash)
-            .map_err(|e| AppError::Authentication(format!("Failed to verify password: {}", e)))
+        if !hash.starts_with(ARGON2_PREFIX) {
+            return bcrypt::verify(password, hash)
+                .map_err(|e| AppError::Authentication(format!("Failed to verify password: {}", e)));
+        }
+
+        let parsed = PasswordHash::new(hash)
+            .map_err(|e| AppError::Authentication(format!("Failed to verify password: {}", e)))?;
+        match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(AppError::Authentication(format!("Failed to verify password: {}", e))),
+        }
+    }
+
+    /// Do the work of a password check for a login naming an unknown account,
+    /// so response times do not reveal which accounts exist
+    pub fn verify_dummy_password(&self, password: &str) {
+        let _ = self.verify_password(password, &self.dummy_password_hash);
+    }
+
+    /// Whether a stored hash should be replaced on the next successful login
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        hash_needs_upgrade(hash, self.password_hash_algorithm, self.bcrypt_cost)
+    }
+
+    /// Issue a single-use sign-in message for the wallet to sign
+    pub async fn create_wallet_challenge(&self, wallet_address: &str) -> AppResult<WalletLoginChallengeDb> {
+        if sr25519::Public::from_ss58check(wallet_address).is_err() {
+            return Err(AppError::Validation("Invalid wallet address".to_string()));
+        }
+
+        let mut nonce = [0u8; 16];
+        OsRng.fill_bytes(&mut nonce);
+        let nonce = hex::encode(nonce);
+
+        let now = Utc::now();
+        let expires_at = now + Duration::minutes(WALLET_CHALLENGE_TTL_MINUTES);
+        let challenge = WalletLoginChallengeDb {
+            id: Uuid::new_v4(),
+            wallet_address: wallet_address.to_string(),
+            message: wallet_login_message(&self.public_base_url, wallet_address, &nonce, now, expires_at),
+            nonce,
+            created_at: now,
+            expires_at,
+            used_at: None,
+        };
+        // Challenges are worthless once used or expired, so clear them out as new ones are made
+        self.wallet_login_repo.delete_stale_wallet_challenges(now).await?;
+        self.wallet_login_repo.create_wallet_challenge(&challenge).await?;
+
+        Ok(challenge)
+    }
+
+    /// Check the sr25519 signature over a challenge message and use the challenge up.
+    /// Returns the wallet address that signed in.
+    pub async fn verify_wallet_login(&self, nonce: &str, signature: &str) -> AppResult<String> {
+        let invalid = || AppError::Authentication("Invalid or expired wallet signature".to_string());
+
+        let challenge = self.wallet_login_repo
+            .get_wallet_challenge(nonce)
+            .await?
+            .filter(|challenge| challenge.used_at.is_none() && challenge.expires_at > Utc::now())
+            .ok_or_else(invalid)?;
+
+        if !BlockchainService::verify_wallet_signature(&challenge.wallet_address, &challenge.message, signature) {
+            return Err(invalid());
+        }
+
+        // Losing a race to use the same signature twice fails here
+        if !self.wallet_login_repo.consume_wallet_challenge(challenge.id).await? {
+            return Err(invalid());
+        }
+
+        Ok(challenge.wallet_address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys_sign_and_verify_through_their_jwk() {
+        for algorithm in [Algorithm::EdDSA, Algorithm::ES256] {
+            let (private_key, public_jwk) = generate_key_pair(algorithm, "test-kid").unwrap();
+            let encoding_key = match algorithm {
+                Algorithm::ES256 => EncodingKey::from_ec_der(&private_key),
+                _ => EncodingKey::from_ed_der(&private_key),
+            };
+            let jwk: Jwk = serde_json::from_value(public_jwk).unwrap();
+            let decoding_key = DecodingKey::from_jwk(&jwk).unwrap();
+
+            let claims = Claims {
+                sub: Uuid::new_v4().to_string(),
+                email: "jwt@example.com".to_string(),
+                role: UserRole::Professional,
+                sid: Uuid::new_v4().to_string(),
+                amr: vec!["pwd".to_string()],
+                exp: (Utc::now() + Duration::minutes(5)).timestamp(),
+                iat: Utc::now().timestamp(),
+            };
+            let mut header = Header::new(algorithm);
+            header.kid = Some("test-kid".to_string());
+            let token = encode(&header, &claims, &encoding_key).unwrap();
+
+            assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("test-kid"));
+            let decoded = decode::<Claims>(&token, &decoding_key, &Validation::new(algorithm)).unwrap();
+            assert_eq!(decoded.claims.sub, claims.sub);
+            assert_eq!(decoded.claims.role, UserRole::Professional);
+
+            // A token signed by a different key must not verify
+            let (other_key, _) = generate_key_pair(algorithm, "other-kid").unwrap();
+            let other_encoding_key = match algorithm {
+                Algorithm::ES256 => EncodingKey::from_ec_der(&other_key),
+                _ => EncodingKey::from_ed_der(&other_key),
+            };
+            let forged = encode(&header, &claims, &other_encoding_key).unwrap();
+            assert!(decode::<Claims>(&forged, &decoding_key, &Validation::new(algorithm)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_wallet_login_message_binds_domain_and_nonce() {
+        use sp_core::Pair as _;
+
+        let (pair, _) = sr25519::Pair::generate();
+        let address = pair.public().to_ss58check();
+        let now = Utc::now();
+        let message = wallet_login_message("https://passport.example.com/app", &address, "abc123", now, now);
+
+        assert!(message.starts_with("passport.example.com wants you to sign in with your Substrate account:\n"));
+        assert!(message.contains(&format!("\n{}\n", address)));
+        assert!(message.contains("\nNonce: abc123\n"));
+
+        let signature = hex::encode(pair.sign(message.as_bytes()).0);
+        assert!(BlockchainService::verify_wallet_signature(&address, &message, &signature));
+
+        let other = wallet_login_message("https://passport.example.com", &address, "def456", now, now);
+        assert!(!BlockchainService::verify_wallet_signature(&address, &other, &signature));
+    }
+
+    #[test]
+    fn test_weaker_password_hashes_need_upgrade() {
+        let bcrypt_hash = bcrypt::hash("SecurePass123!", 4).unwrap();
+        assert!(hash_needs_upgrade(&bcrypt_hash, PasswordHashAlgorithm::Bcrypt, 5));
+        assert!(!hash_needs_upgrade(&bcrypt_hash, PasswordHashAlgorithm::Bcrypt, 4));
+        assert!(hash_needs_upgrade(&bcrypt_hash, PasswordHashAlgorithm::Argon2id, 4));
+
+        let salt = SaltString::encode_b64(b"0123456789abcdef").unwrap();
+        let argon2_hash = Argon2::default()
+            .hash_password(b"SecurePass123!", &salt)
+            .unwrap()
+            .to_string();
+        assert!(!hash_needs_upgrade(&argon2_hash, PasswordHashAlgorithm::Argon2id, 12));
+        // Switching back to bcrypt does not downgrade existing argon2 hashes
+        assert!(!hash_needs_upgrade(&argon2_hash, PasswordHashAlgorithm::Bcrypt, 12));
     }
 }
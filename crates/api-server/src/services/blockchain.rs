@@ -1,4 +1,4 @@
-use common::AppResult;
+use common::{AppError, AppResult};
 use sp_core::crypto::Ss58Codec;
 use sp_core::{sr25519, Pair as PairTrait};
 
@@ -16,11 +16,54 @@ impl BlockchainService {
         })
     }
 
-    /// Generate a new wallet address
-    pub fn generate_wallet_address() -> AppResult<String> {
-        let (pair, _seed) = sr25519::Pair::generate();
-        let public = pair.public();
-        Ok(public.to_ss58check())
+    /// Generate a new wallet, returning the SS58 address and its mnemonic phrase
+    pub fn generate_wallet() -> AppResult<(String, String)> {
+        let (pair, phrase, _seed) = sr25519::Pair::generate_with_phrase(None);
+        Ok((pair.public().to_ss58check(), phrase))
+    }
+
+    /// Sign a payload with the wallet key recovered from a mnemonic phrase
+    pub fn sign_with_phrase(phrase: &str, payload: &[u8]) -> AppResult<String> {
+        let (pair, _seed) = sr25519::Pair::from_phrase(phrase, None)
+            .map_err(|e| AppError::Internal(format!("Invalid wallet key: {:?}", e)))?;
+        Ok(hex::encode(pair.sign(payload)))
+    }
+
+    /// Generate a new issuer signing keypair, returning the SS58 public key and raw seed
+    pub fn generate_signing_keypair() -> AppResult<(String, Vec<u8>)> {
+        let (pair, seed) = sr25519::Pair::generate();
+        Ok((pair.public().to_ss58check(), seed.to_vec()))
+    }
+
+    /// Sign a payload with the sr25519 key derived from a raw seed
+    pub fn sign_payload(secret_seed: &[u8], payload: &[u8]) -> AppResult<String> {
+        let pair = sr25519::Pair::from_seed_slice(secret_seed)
+            .map_err(|e| AppError::Internal(format!("Invalid signing key: {:?}", e)))?;
+        Ok(hex::encode(pair.sign(payload)))
+    }
+
+    /// Verify a hex encoded sr25519 signature against an SS58 public key
+    pub fn verify_payload_signature(public_key: &str, payload: &[u8], signature: &str) -> bool {
+        let signature = signature.trim_start_matches("0x");
+        let Ok(public) = sr25519::Public::from_ss58check(public_key) else {
+            return false;
+        };
+        let Some(signature) = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| sr25519::Signature::from_slice(&bytes))
+        else {
+            return false;
+        };
+        sr25519::Pair::verify(&signature, payload, &public)
+    }
+
+    /// Verify a message signed by a wallet. Browser extensions such as
+    /// polkadot.js wrap raw messages in `<Bytes>` tags before signing, so both
+    /// the bare and the wrapped form are accepted.
+    pub fn verify_wallet_signature(wallet_address: &str, message: &str, signature: &str) -> bool {
+        let wrapped = format!("<Bytes>{}</Bytes>", message);
+        Self::verify_payload_signature(wallet_address, message.as_bytes(), signature)
+            || Self::verify_payload_signature(wallet_address, wrapped.as_bytes(), signature)
     }
 
     /// Record credential hash on blockchain
@@ -55,3 +98,20 @@ impl BlockchainService {
 
 // Add hex dependency for encoding
 use hex;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_phrase_signs_for_its_address() {
+        let (address, phrase) = BlockchainService::generate_wallet().unwrap();
+        let signature = BlockchainService::sign_with_phrase(&phrase, b"payload").unwrap();
+
+        assert!(BlockchainService::verify_payload_signature(&address, b"payload", &signature));
+        assert!(!BlockchainService::verify_payload_signature(&address, b"other payload", &signature));
+
+        let (other_address, _) = BlockchainService::generate_wallet().unwrap();
+        assert!(!BlockchainService::verify_payload_signature(&other_address, b"payload", &signature));
+    }
+}
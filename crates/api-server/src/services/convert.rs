@@ -0,0 +1,270 @@
+// Conversions from database rows to API models
+
+use common::{
+    AccreditationAuthority, AccreditationStatus, ApiKey, ApiKeyScope, AppError, AppResult, Credential,
+    CredentialStatus, CredentialStatusEvent, CredentialType, Institution, InstitutionRole, OAuthClient, OAuthGrant,
+    OAuthScope, Permission, PublicCredential, User, UserRole,
+};
+use database::{
+    AccreditationAuthorityDb, ApiKeyDb, CredentialDb, CredentialStatusEventDb, InstitutionDb, OAuthClientDb,
+    OAuthGrantDb, UserDb,
+};
+use serde::Serialize;
+
+pub fn parse_credential_type(credential_type: &str) -> AppResult<CredentialType> {
+    match credential_type {
+        "certificate" => Ok(CredentialType::Certificate),
+        "license" => Ok(CredentialType::License),
+        "degree" => Ok(CredentialType::Degree),
+        "workexperience" => Ok(CredentialType::WorkExperience),
+        "skill" => Ok(CredentialType::Skill),
+        _ => Err(AppError::Internal("Invalid credential type".to_string())),
+    }
+}
+
+/// The lowercase name stored in the database for a credential status
+pub fn credential_status_name(status: &CredentialStatus) -> String {
+    format!("{:?}", status).to_lowercase()
+}
+
+/// The snake_case code a reason enum serializes to
+pub fn reason_code<T: Serialize>(reason: &T) -> AppResult<String> {
+    serde_json::to_value(reason)
+        .ok()
+        .and_then(|code| code.as_str().map(str::to_string))
+        .ok_or_else(|| AppError::Internal("Invalid reason code".to_string()))
+}
+
+pub fn parse_credential_status(status: &str) -> AppResult<CredentialStatus> {
+    match status {
+        "pending" => Ok(CredentialStatus::Pending),
+        "issued" => Ok(CredentialStatus::Issued),
+        "suspended" => Ok(CredentialStatus::Suspended),
+        "revoked" => Ok(CredentialStatus::Revoked),
+        "expired" => Ok(CredentialStatus::Expired),
+        _ => Err(AppError::Internal("Invalid status".to_string())),
+    }
+}
+
+pub fn credential_from_db(cred_db: CredentialDb) -> AppResult<Credential> {
+    Ok(Credential {
+        id: cred_db.id,
+        credential_type: parse_credential_type(&cred_db.credential_type)?,
+        status: parse_credential_status(&cred_db.status)?,
+        credential_id: cred_db.credential_id,
+        holder_id: cred_db.holder_id,
+        issuer_id: cred_db.issuer_id,
+        institution_id: cred_db.institution_id,
+        title: cred_db.title,
+        description: cred_db.description,
+        ipfs_hash: cred_db.ipfs_hash,
+        chain_hash: cred_db.chain_hash,
+        issuer_signature: cred_db.issuer_signature,
+        issuer_public_key: cred_db.issuer_public_key,
+        issue_date: cred_db.issue_date,
+        expiry_date: cred_db.expiry_date,
+        metadata: cred_db.metadata,
+        created_at: cred_db.created_at,
+    })
+}
+
+pub fn public_credential_from_db(cred_db: CredentialDb) -> AppResult<PublicCredential> {
+    Ok(PublicCredential {
+        credential_type: parse_credential_type(&cred_db.credential_type)?,
+        status: parse_credential_status(&cred_db.status)?,
+        credential_id: cred_db.credential_id,
+        institution_id: cred_db.institution_id,
+        title: cred_db.title,
+        description: cred_db.description,
+        ipfs_hash: cred_db.ipfs_hash,
+        chain_hash: cred_db.chain_hash,
+        issuer_signature: cred_db.issuer_signature,
+        issuer_public_key: cred_db.issuer_public_key,
+        issue_date: cred_db.issue_date,
+        expiry_date: cred_db.expiry_date,
+    })
+}
+
+pub fn institution_from_db(inst: InstitutionDb) -> Institution {
+    Institution {
+        id: inst.id,
+        user_id: inst.user_id,
+        institution_name: inst.institution_name,
+        institution_type: inst.institution_type,
+        country: inst.country,
+        accreditation_number: inst.accreditation_number,
+        is_accredited: inst.is_accredited,
+        accreditation_status: parse_accreditation_status(&inst.accreditation_status),
+        accreditation_authority_id: inst.accreditation_authority_id,
+        created_at: inst.created_at,
+    }
+}
+
+pub fn parse_institution_role(role: &str) -> AppResult<InstitutionRole> {
+    match role {
+        "owner" => Ok(InstitutionRole::Owner),
+        "issuer" => Ok(InstitutionRole::Issuer),
+        "reviewer" => Ok(InstitutionRole::Reviewer),
+        "auditor" => Ok(InstitutionRole::Auditor),
+        _ => Err(AppError::Internal(format!("Unknown institution role: {}", role))),
+    }
+}
+
+pub fn institution_role_name(role: &InstitutionRole) -> String {
+    format!("{:?}", role).to_lowercase()
+}
+
+pub fn parse_api_key_scope(scope: &str) -> AppResult<ApiKeyScope> {
+    match scope {
+        "issue" => Ok(ApiKeyScope::Issue),
+        "revoke" => Ok(ApiKeyScope::Revoke),
+        "read_issued" => Ok(ApiKeyScope::ReadIssued),
+        "verify" => Ok(ApiKeyScope::Verify),
+        _ => Err(AppError::Internal(format!("Unknown API key scope: {}", scope))),
+    }
+}
+
+pub fn api_key_scope_name(scope: ApiKeyScope) -> &'static str {
+    match scope {
+        ApiKeyScope::Issue => "issue",
+        ApiKeyScope::Revoke => "revoke",
+        ApiKeyScope::ReadIssued => "read_issued",
+        ApiKeyScope::Verify => "verify",
+    }
+}
+
+pub fn api_key_from_db(key: ApiKeyDb) -> AppResult<ApiKey> {
+    Ok(ApiKey {
+        scopes: key.scopes.iter().map(|scope| parse_api_key_scope(scope)).collect::<AppResult<_>>()?,
+        id: key.id,
+        name: key.name,
+        prefix: key.prefix,
+        created_by: key.created_by,
+        created_at: key.created_at,
+        expires_at: key.expires_at,
+        last_used_at: key.last_used_at,
+        revoked_at: key.revoked_at,
+        replaced_by: key.replaced_by,
+    })
+}
+
+pub fn parse_oauth_scope(scope: &str) -> Option<OAuthScope> {
+    match scope {
+        "openid" => Some(OAuthScope::OpenId),
+        "profile" => Some(OAuthScope::Profile),
+        "email" => Some(OAuthScope::Email),
+        "credentials:read" => Some(OAuthScope::CredentialsRead),
+        _ => None,
+    }
+}
+
+pub fn oauth_scope_name(scope: OAuthScope) -> &'static str {
+    match scope {
+        OAuthScope::OpenId => "openid",
+        OAuthScope::Profile => "profile",
+        OAuthScope::Email => "email",
+        OAuthScope::CredentialsRead => "credentials:read",
+    }
+}
+
+pub fn oauth_client_from_db(client: OAuthClientDb) -> OAuthClient {
+    OAuthClient {
+        confidential: client.client_secret_hash.is_some(),
+        client_id: client.client_id,
+        name: client.name,
+        redirect_uris: client.redirect_uris,
+        created_at: client.created_at,
+        revoked_at: client.revoked_at,
+    }
+}
+
+pub fn oauth_grant_from_db(grant: OAuthGrantDb, client: OAuthClientDb) -> OAuthGrant {
+    OAuthGrant {
+        id: grant.id,
+        client_id: client.client_id,
+        client_name: client.name,
+        scopes: grant.scopes.iter().filter_map(|scope| parse_oauth_scope(scope)).collect(),
+        credential_ids: grant.credential_ids,
+        created_at: grant.created_at,
+    }
+}
+
+pub fn authority_from_db(authority: AccreditationAuthorityDb) -> AccreditationAuthority {
+    AccreditationAuthority {
+        id: authority.id,
+        code: authority.code,
+        name: authority.name,
+        country: authority.country,
+        country_code: authority.country_code,
+        number_example: authority.number_example,
+        website: authority.website,
+    }
+}
+
+pub fn parse_accreditation_status(status: &str) -> AccreditationStatus {
+    match status {
+        "approved" => AccreditationStatus::Approved,
+        "rejected" => AccreditationStatus::Rejected,
+        "suspended" => AccreditationStatus::Suspended,
+        _ => AccreditationStatus::Pending,
+    }
+}
+
+pub fn accreditation_status_name(status: &AccreditationStatus) -> String {
+    format!("{:?}", status).to_lowercase()
+}
+
+pub fn parse_user_role(role: &str) -> AppResult<UserRole> {
+    match role {
+        "professional" => Ok(UserRole::Professional),
+        "institution" => Ok(UserRole::Institution),
+        "employer" => Ok(UserRole::Employer),
+        "admin" => Ok(UserRole::Admin),
+        _ => Err(AppError::Internal(format!("Unknown user role: {}", role))),
+    }
+}
+
+/// The lowercase name stored in the database and access tokens for a user role
+pub fn user_role_name(role: &UserRole) -> String {
+    format!("{:?}", role).to_lowercase()
+}
+
+pub fn permission_name(permission: Permission) -> &'static str {
+    match permission {
+        Permission::CredentialIssue => "credential:issue",
+        Permission::CredentialRevoke => "credential:revoke",
+        Permission::CredentialSuspend => "credential:suspend",
+        Permission::CredentialReadIssued => "credential:read_issued",
+        Permission::InstitutionRead => "institution:read",
+        Permission::InstitutionRegister => "institution:register",
+        Permission::InstitutionManage => "institution:manage",
+        Permission::InstitutionAccredit => "institution:accredit",
+        Permission::OAuthClientManage => "oauth_client:manage",
+        Permission::SecurityManage => "security:manage",
+    }
+}
+
+pub fn user_from_db(user: UserDb) -> User {
+    User {
+        role: parse_user_role(&user.role).unwrap_or(UserRole::Professional),
+        id: user.id,
+        wallet_address: user.wallet_address,
+        email: user.email,
+        name: user.name,
+        is_verified: user.is_verified,
+        created_at: user.created_at,
+        updated_at: user.updated_at,
+    }
+}
+
+pub fn status_event_from_db(event: CredentialStatusEventDb) -> AppResult<CredentialStatusEvent> {
+    Ok(CredentialStatusEvent {
+        from_status: parse_credential_status(&event.from_status)?,
+        to_status: parse_credential_status(&event.to_status)?,
+        reason: event.reason,
+        note: event.note,
+        actor_id: event.actor_id,
+        effective_at: event.effective_at,
+        recorded_at: event.recorded_at,
+    })
+}
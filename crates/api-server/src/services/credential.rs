@@ -1,84 +1,589 @@
 use std::sync::Arc;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
 use base64::{Engine as _, engine::general_purpose};
-use common::{AppError, AppResult, IssueCredentialRequest, IssueCredentialResponse, CredentialStatus};
-use database::{CredentialDb, CredentialRepositoryImpl, CredentialRepository};
-use super::{IpfsService, BlockchainService};
+use serde_json::{json, Map};
+use common::{
+    AppError, AppResult, IssueCredentialRequest, IssueCredentialResponse, CredentialStatus,
+    CredentialStatusEvent, SdJwtVerificationResponse, SealPurpose, StatusPurpose, credential_status_at,
+};
+use database::{
+    CredentialDb, CredentialRepositoryImpl, CredentialRepository,
+    InstitutionDb, InstitutionRepositoryImpl, InstitutionRepository,
+    IssuerKeyDb, IssuerKeyRepositoryImpl, IssuerKeyRepository, UserDb,
+    CredentialStatusEventDb, CredentialStatusEventRepositoryImpl, CredentialStatusEventRepository,
+};
+use super::{DocumentStore, DocumentKeyService, BlockchainService, KeystoreService};
+use super::document_keys::DocumentRecipient;
+use super::convert::{
+    credential_status_name, institution_from_db, parse_credential_status, status_event_from_db,
+};
+use crate::utils::{
+    canonical::to_canonical_json,
+    open_badges::ImportedBadge,
+    sd_jwt::{self, Disclosure, SdJwtBuilder},
+    status_list,
+    vc,
+};
+
+/// Who changed a credential's status, why, and from when
+pub struct StatusChange {
+    pub actor_id: Option<Uuid>, // None for system changes
+    pub reason: Option<String>,
+    pub note: Option<String>,
+    pub effective_at: Option<DateTime<Utc>>, // Defaults to now
+}
 
 pub struct CredentialService {
-    ipfs_service: Arc<IpfsService>,
+    document_store: Arc<dyn DocumentStore>,
+    document_keys: Arc<DocumentKeyService>,
     blockchain_service: Arc<BlockchainService>,
     credential_repo: Arc<CredentialRepositoryImpl>,
+    institution_repo: Arc<InstitutionRepositoryImpl>,
+    issuer_key_repo: Arc<IssuerKeyRepositoryImpl>,
+    status_event_repo: Arc<CredentialStatusEventRepositoryImpl>,
+    keystore: Arc<KeystoreService>,
 }
 
 impl CredentialService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        ipfs_service: Arc<IpfsService>,
+        document_store: Arc<dyn DocumentStore>,
+        document_keys: Arc<DocumentKeyService>,
         blockchain_service: Arc<BlockchainService>,
         credential_repo: Arc<CredentialRepositoryImpl>,
+        institution_repo: Arc<InstitutionRepositoryImpl>,
+        issuer_key_repo: Arc<IssuerKeyRepositoryImpl>,
+        status_event_repo: Arc<CredentialStatusEventRepositoryImpl>,
+        keystore: Arc<KeystoreService>,
     ) -> Self {
         Self {
-            ipfs_service,
+            document_store,
+            document_keys,
             blockchain_service,
             credential_repo,
+            institution_repo,
+            issuer_key_repo,
+            status_event_repo,
+            keystore,
+        }
+    }
+
+    /// Get the institution's active signing key, generating one if it has none yet
+    pub async fn ensure_issuer_key(&self, institution: &InstitutionDb) -> AppResult<IssuerKeyDb> {
+        if let Some(key) = self.issuer_key_repo.get_active_issuer_key(institution.id).await? {
+            return Ok(key);
+        }
+
+        let (public_key, secret_seed) = BlockchainService::generate_signing_keypair()?;
+        let key = IssuerKeyDb {
+            id: Uuid::new_v4(),
+            institution_id: institution.id,
+            public_key,
+            encrypted_seed: self.keystore.seal(SealPurpose::IssuerSeed, institution.id, &secret_seed)?,
+            is_active: true,
+            created_at: Utc::now(),
+        };
+        if let Some(key) = self.issuer_key_repo.create_issuer_key(&key).await? {
+            return Ok(key);
         }
+
+        // Another request created it first
+        self.issuer_key_repo
+            .get_active_issuer_key(institution.id)
+            .await?
+            .ok_or_else(|| AppError::Internal("Failed to create issuer signing key".to_string()))
     }
 
     pub async fn issue_credential(
         &self,
         request: IssueCredentialRequest,
+        institution: &InstitutionDb,
         issuer_id: Uuid,
         holder_id: Uuid,
     ) -> AppResult<IssueCredentialResponse> {
+        if request.expiry_date.is_some_and(|expiry_date| expiry_date <= request.issue_date) {
+            return Err(AppError::Validation("Expiry date must be after the issue date".to_string()));
+        }
+
+        let signing_key = self.ensure_issuer_key(institution).await?;
+
         // Decode base64 document data
         let document_bytes = general_purpose::STANDARD.decode(&request.document_data)
             .map_err(|e| AppError::Validation(format!("Invalid base64 data: {}", e)))?;
 
-        // Upload to IPFS
-        let ipfs_hash = self.ipfs_service.upload(document_bytes).await?;
+        // Encrypt the document for the holder and issuer before storing it
+        let id = Uuid::new_v4();
+        let sealed = self.document_keys
+            .seal_document(id, holder_id, institution.id, &document_bytes)
+            .await?;
+        let ipfs_hash = self.document_store.put(sealed.ciphertext).await?;
 
-        // Generate unique credential ID
-        let credential_id = format!("SSP-{}", Uuid::new_v4());
+        // Anything failing from here leaves the stored document orphaned
+        let stored_at = ipfs_hash.clone();
+        let issued = async move {
+            // Generate unique credential ID
+            let credential_id = format!("SSP-{}", Uuid::new_v4());
 
-        // Record on blockchain
-        let chain_hash = self.blockchain_service
-            .record_credential_hash(&credential_id, &ipfs_hash)
+            // Record on blockchain
+            let chain_hash = self.blockchain_service
+                .record_credential_hash(&credential_id, &ipfs_hash)
+                .await?;
+
+            // Generate QR code
+            let qr_code = crate::utils::qr::generate_qr_code(&credential_id)?;
+
+            // Reserve the credential's entry in the issuer's status lists
+            let (status_list_id, status_list_index) = self.credential_repo
+                .allocate_status_list_index(institution.id)
+                .await?;
+
+            // Postgres stores timestamps with microsecond precision, so truncate
+            // before signing to keep the signed payload reproducible from the row
+            let mut credential = CredentialDb {
+                id,
+                credential_id: credential_id.clone(),
+                holder_id,
+                issuer_id,
+                credential_type: format!("{:?}", request.credential_type).to_lowercase(),
+                title: request.title,
+                description: request.description,
+                ipfs_hash: ipfs_hash.clone(),
+                chain_hash: chain_hash.clone(),
+                qr_code: qr_code.clone(), // Store QR code in database
+                issuer_signature: String::new(),
+                issuer_public_key: signing_key.public_key.clone(),
+                issue_date: request.issue_date.trunc_subsecs(6),
+                expiry_date: request.expiry_date.map(|date| date.trunc_subsecs(6)),
+                status: format!("{:?}", CredentialStatus::Issued).to_lowercase(),
+                metadata: request.metadata,
+                created_at: Utc::now(),
+                status_list_id: Some(status_list_id),
+                status_list_index: Some(status_list_index),
+                status_reason: None,
+                institution_id: institution.id,
+                document_encrypted: true,
+            };
+
+            // Sign the credential payload with the issuer's key
+            let secret_seed = self.keystore.open(SealPurpose::IssuerSeed, institution.id, &signing_key.encrypted_seed)?;
+            credential.issuer_signature = BlockchainService::sign_payload(
+                &secret_seed,
+                &credential_signing_payload(&credential),
+            )?;
+
+            self.credential_repo
+                .create_credential_with_document_keys(&credential, &sealed.keys)
+                .await?;
+
+            Ok(IssueCredentialResponse {
+                credential_id,
+                ipfs_hash,
+                chain_hash,
+                issuer_signature: credential.issuer_signature,
+                issuer_public_key: credential.issuer_public_key,
+                qr_code,
+            })
+        }
+        .await;
+
+        self.discard_document_on_error(&stored_at, issued).await
+    }
+
+    /// Raise a pending credential claim from an externally issued badge.
+    /// The badge document is stored as the credential document; the claim is
+    /// unsigned until the institution reviews it.
+    pub async fn create_badge_claim(
+        &self,
+        badge: serde_json::Value,
+        imported: ImportedBadge,
+        holder_id: Uuid,
+        institution: &InstitutionDb,
+    ) -> AppResult<CredentialDb> {
+        let document_bytes = serde_json::to_vec(&badge)
+            .map_err(|e| AppError::Internal(format!("Failed to encode badge: {}", e)))?;
+        let id = Uuid::new_v4();
+        let sealed = self.document_keys
+            .seal_document(id, holder_id, institution.id, &document_bytes)
             .await?;
+        let ipfs_hash = self.document_store.put(sealed.ciphertext).await?;
 
-        // Generate QR code
+        let credential_id = format!("SSP-{}", Uuid::new_v4());
         let qr_code = crate::utils::qr::generate_qr_code(&credential_id)?;
 
-        // Save to database
         let credential = CredentialDb {
-            id: Uuid::new_v4(),
-            credential_id: credential_id.clone(),
+            id,
+            credential_id,
             holder_id,
-            issuer_id,
-            credential_type: format!("{:?}", request.credential_type).to_lowercase(),
-            title: request.title,
-            description: request.description,
-            ipfs_hash: ipfs_hash.clone(),
-            chain_hash: chain_hash.clone(),
-            qr_code: qr_code.clone(), // Store QR code in database
-            issue_date: request.issue_date,
-            expiry_date: request.expiry_date,
-            status: format!("{:?}", CredentialStatus::Issued).to_lowercase(),
-            metadata: request.metadata,
+            issuer_id: institution.user_id, // No staff member has reviewed the claim yet
+            credential_type: format!("{:?}", imported.credential_type).to_lowercase(),
+            title: imported.title,
+            description: imported.description,
+            ipfs_hash,
+            chain_hash: String::new(),
+            qr_code,
+            issuer_signature: String::new(),
+            issuer_public_key: String::new(),
+            issue_date: imported.issue_date.trunc_subsecs(6),
+            expiry_date: imported.expiry_date.map(|date| date.trunc_subsecs(6)),
+            status: format!("{:?}", CredentialStatus::Pending).to_lowercase(),
+            metadata: json!({
+                "source": "open_badge_import",
+                "criteria": imported.criteria,
+                "badge": badge,
+            }),
             created_at: Utc::now(),
+            status_list_id: None,
+            status_list_index: None,
+            status_reason: None,
+            institution_id: institution.id,
+            document_encrypted: true,
         };
 
-        self.credential_repo.create_credential(&credential).await?;
+        let created = self.credential_repo
+            .create_credential_with_document_keys(&credential, &sealed.keys)
+            .await;
+        self.discard_document_on_error(&credential.ipfs_hash, created).await
+    }
 
-        Ok(IssueCredentialResponse {
-            credential_id,
-            ipfs_hash,
+    /// Delete a newly stored document when the credential that would refer to
+    /// it could not be created. Each sealed document is encrypted under a
+    /// fresh key, so no other credential shares its address.
+    async fn discard_document_on_error<T>(&self, address: &str, result: AppResult<T>) -> AppResult<T> {
+        if result.is_err() {
+            if let Err(e) = self.document_store.delete(address).await {
+                tracing::warn!("Could not delete orphaned document {}: {}", address, e);
+            }
+        }
+        result
+    }
+
+    /// Sign a pending badge claim on behalf of its institution and move it to
+    /// `Issued`. The reviewer becomes the credential's issuer.
+    pub async fn approve_badge_claim(
+        &self,
+        claim: &CredentialDb,
+        institution: &InstitutionDb,
+        reviewer_id: Uuid,
+    ) -> AppResult<()> {
+        if parse_credential_status(&claim.status)? != CredentialStatus::Pending {
+            return Err(AppError::Validation("Only pending claims can be approved".to_string()));
+        }
+
+        // Check the claim can be issued before anything is anchored or signed
+        let events = self.status_event_repo.get_status_events(claim.id).await?;
+        let event = status_change_event(claim, &CredentialStatus::Issued, StatusChange {
+            actor_id: Some(reviewer_id),
+            reason: Some("claim_approved".to_string()),
+            note: None,
+            effective_at: None,
+        }, &events, Utc::now())?;
+
+        let signing_key = self.ensure_issuer_key(institution).await?;
+        let chain_hash = self.blockchain_service
+            .record_credential_hash(&claim.credential_id, &claim.ipfs_hash)
+            .await?;
+        let (status_list_id, status_list_index) = self.credential_repo
+            .allocate_status_list_index(institution.id)
+            .await?;
+
+        let mut credential = CredentialDb {
+            issuer_id: reviewer_id,
             chain_hash,
-            qr_code,
+            issuer_public_key: signing_key.public_key.clone(),
+            status_list_id: Some(status_list_id),
+            status_list_index: Some(status_list_index),
+            ..claim.clone()
+        };
+        let secret_seed = self.keystore.open(SealPurpose::IssuerSeed, institution.id, &signing_key.encrypted_seed)?;
+        credential.issuer_signature = BlockchainService::sign_payload(
+            &secret_seed,
+            &credential_signing_payload(&credential),
+        )?;
+
+        if !self.credential_repo.approve_pending_credential(&credential, &event).await? {
+            return Err(AppError::Validation("Claim was reviewed concurrently, please retry".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Fetch and decrypt a credential's document. The stored bytes must still
+    /// hash to the recorded address. Callers must first check the requester
+    /// may act as the given recipient.
+    pub async fn read_document(&self, credential: &CredentialDb, recipient: DocumentRecipient) -> AppResult<Vec<u8>> {
+        let document = self.document_store.get(&credential.ipfs_hash).await?;
+
+        let address = self.document_store.address(&document).await?;
+        if address != credential.ipfs_hash {
+            tracing::error!(
+                credential_id = %credential.credential_id,
+                expected = %credential.ipfs_hash,
+                actual = %address,
+                "Credential document failed its integrity check"
+            );
+            return Err(AppError::Internal(format!(
+                "Document for credential {} does not match its recorded hash",
+                credential.credential_id,
+            )));
+        }
+
+        self.document_keys.open_document(credential, recipient, document).await
+    }
+
+    /// Move a credential to a new status, enforcing the credential lifecycle,
+    /// recording the change in its history and keeping the issuer's status
+    /// lists in step
+    pub async fn transition_status(
+        &self,
+        credential: &CredentialDb,
+        next: CredentialStatus,
+        change: StatusChange,
+    ) -> AppResult<()> {
+        let current = parse_credential_status(&credential.status)?;
+        let events = self.status_event_repo.get_status_events(credential.id).await?;
+        let event = status_change_event(credential, &next, change, &events, Utc::now())?;
+        let status_bit = match next {
+            CredentialStatus::Revoked => Some((StatusPurpose::Revocation, true)),
+            CredentialStatus::Suspended => Some((StatusPurpose::Suspension, true)),
+            CredentialStatus::Issued if current == CredentialStatus::Suspended => Some((StatusPurpose::Suspension, false)),
+            _ => None,
+        };
+
+        if !self.status_event_repo.record_status_change(&event, status_bit).await? {
+            return Err(AppError::Validation("Credential status changed concurrently, please retry".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// The credential's recorded status changes in effective order
+    pub async fn status_history(&self, credential: &CredentialDb) -> AppResult<Vec<CredentialStatusEvent>> {
+        self.status_event_repo
+            .get_status_events(credential.id)
+            .await?
+            .into_iter()
+            .map(status_event_from_db)
+            .collect()
+    }
+
+    /// The status the credential had at a point in time
+    pub async fn status_at(&self, credential: &CredentialDb, at: &DateTime<Utc>) -> AppResult<CredentialStatus> {
+        let events = self.status_history(credential).await?;
+
+        Ok(credential_status_at(
+            &parse_credential_status(&credential.status)?,
+            &credential.issue_date,
+            credential.expiry_date.as_ref(),
+            &events,
+            at,
+        ))
+    }
+
+    /// Build and sign the BitstringStatusListCredential for one of an issuer's lists
+    pub async fn status_list_credential(
+        &self,
+        list_id: Uuid,
+        purpose: StatusPurpose,
+        base_url: &str,
+    ) -> AppResult<serde_json::Value> {
+        let list = self.credential_repo
+            .get_status_list(list_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Status list not found".to_string()))?;
+        let institution = self.institution_repo
+            .get_institution_by_id(list.institution_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Issuing institution not found".to_string()))?;
+        let signing_key = self.ensure_issuer_key(&institution).await?;
+        let secret_seed = self.keystore.open(SealPurpose::IssuerSeed, institution.id, &signing_key.encrypted_seed)?;
+
+        let bits = match purpose {
+            StatusPurpose::Revocation => &list.revocation_bits,
+            StatusPurpose::Suspension => &list.suspension_bits,
+        };
+        let mut credential = status_list::to_status_list_credential(
+            base_url,
+            list.id,
+            purpose,
+            &institution_from_db(institution),
+            &status_list::encode_list(bits)?,
+            &list.updated_at,
+        );
+
+        let signature = BlockchainService::sign_payload(
+            &secret_seed,
+            to_canonical_json(&credential).as_bytes(),
+        )?;
+        credential["proof"] = json!({
+            "type": "DataIntegrityProof",
+            "cryptosuite": status_list::STATUS_LIST_CRYPTOSUITE,
+            "created": vc::format_date(&Utc::now()),
+            "verificationMethod": signing_key.public_key,
+            "proofPurpose": "assertionMethod",
+            "proofValue": format!("0x{}", signature),
+        });
+
+        Ok(credential)
+    }
+
+    /// Check that a credential carries a valid signature from a key belonging to its issuer
+    pub async fn verify_issuer_signature(&self, credential: &CredentialDb) -> AppResult<bool> {
+        if credential.issuer_signature.is_empty() {
+            return Ok(false);
+        }
+
+        let Some(key) = self.issuer_key_repo
+            .get_issuer_key_by_public_key(&credential.issuer_public_key)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        // The key must be registered to the institution that issued the credential
+        if credential.institution_id != key.institution_id {
+            return Ok(false);
+        }
+
+        Ok(BlockchainService::verify_payload_signature(
+            &key.public_key,
+            &credential_signing_payload(credential),
+            &credential.issuer_signature,
+        ))
+    }
+
+    /// Mint an SD-JWT for a credential, signed with the issuer's active key.
+    /// Every credential field and each top-level metadata key is disclosable.
+    pub async fn mint_sd_jwt(
+        &self,
+        credential: &CredentialDb,
+        holder: &UserDb,
+        base_url: &str,
+    ) -> AppResult<(String, Vec<Disclosure>)> {
+        let institution = self.institution_repo
+            .get_institution_by_id(credential.institution_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Issuing institution not found".to_string()))?;
+        let signing_key = self.ensure_issuer_key(&institution).await?;
+
+        let mut visible = Map::new();
+        visible.insert("iss".to_string(), json!(format!("urn:uuid:{}", institution.id)));
+        visible.insert("iat".to_string(), json!(Utc::now().timestamp()));
+        visible.insert("vct".to_string(), json!("SafariSkillsCredential"));
+        visible.insert("jti".to_string(), json!(vc::credential_url(base_url, &credential.credential_id)));
+
+        let mut builder = SdJwtBuilder::new(visible);
+        builder.disclosable("credential_id", json!(credential.credential_id));
+        builder.disclosable("credential_type", json!(credential.credential_type));
+        builder.disclosable("title", json!(credential.title));
+        builder.disclosable("description", json!(credential.description));
+        builder.disclosable("holder_name", json!(holder.name));
+        builder.disclosable("holder_wallet_address", json!(holder.wallet_address));
+        builder.disclosable("issue_date", json!(format_timestamp(&credential.issue_date)));
+        if let Some(expiry_date) = &credential.expiry_date {
+            builder.disclosable("expiry_date", json!(format_timestamp(expiry_date)));
+        }
+        builder.disclosable("ipfs_hash", json!(credential.ipfs_hash));
+        builder.disclosable("chain_hash", json!(credential.chain_hash));
+        match credential.metadata.as_object() {
+            Some(metadata) => builder.disclosable_object("metadata", metadata),
+            None => builder.disclosable("metadata", credential.metadata.clone()),
+        }
+        let (payload, disclosures) = builder.finish();
+
+        let header = json!({
+            "alg": sd_jwt::JWS_ALG,
+            "typ": sd_jwt::SD_JWT_TYP,
+            "kid": signing_key.public_key,
+        });
+        let secret_seed = self.keystore.open(SealPurpose::IssuerSeed, institution.id, &signing_key.encrypted_seed)?;
+        let jwt = sd_jwt::encode_jws(&header, &payload, |signing_input| {
+            let signature = BlockchainService::sign_payload(&secret_seed, signing_input)?;
+            hex::decode(signature).map_err(|e| AppError::Internal(format!("Invalid signature encoding: {}", e)))
+        })?;
+
+        Ok((jwt, disclosures))
+    }
+
+    /// Verify an SD-JWT presentation against the issuer key and our records
+    pub async fn verify_sd_jwt(&self, presentation: &str) -> AppResult<SdJwtVerificationResponse> {
+        let not_verified = |issuer: Option<String>, message: &str| SdJwtVerificationResponse {
+            valid: false,
+            issuer,
+            claims: json!({}),
+            status: None,
+            message: message.to_string(),
+        };
+
+        let (jwt, disclosures) = sd_jwt::split_presentation(presentation)?;
+        let decoded = sd_jwt::decode_jws(jwt)?;
+
+        if decoded.header.get("alg").and_then(|alg| alg.as_str()) != Some(sd_jwt::JWS_ALG) {
+            return Ok(not_verified(None, "Unsupported signature algorithm"));
+        }
+        let kid = decoded.header.get("kid").and_then(|kid| kid.as_str()).unwrap_or_default();
+
+        // The signing key must be one we issued to a registered institution
+        let Some(key) = self.issuer_key_repo.get_issuer_key_by_public_key(kid).await? else {
+            return Ok(not_verified(None, "Unknown issuer key"));
+        };
+        let Some(institution) = self.institution_repo.get_institution_by_id(key.institution_id).await? else {
+            return Ok(not_verified(None, "Unknown issuer key"));
+        };
+        let issuer = Some(institution.institution_name.clone());
+
+        let signature_valid = BlockchainService::verify_payload_signature(
+            &key.public_key,
+            decoded.signing_input.as_bytes(),
+            &hex::encode(&decoded.signature),
+        );
+        let expected_iss = format!("urn:uuid:{}", institution.id);
+        if !signature_valid || decoded.payload.get("iss").and_then(|iss| iss.as_str()) != Some(expected_iss.as_str()) {
+            return Ok(not_verified(issuer, "Issuer signature could not be verified"));
+        }
+
+        let claims = sd_jwt::resolve_disclosures(&decoded.payload, &disclosures)?;
+
+        // Check the live status of the underlying credential
+        let credential = match decoded.payload.get("jti").and_then(|jti| jti.as_str()).and_then(vc::credential_id_from_url) {
+            Some(credential_id) => self.credential_repo.get_credential_by_credential_id(&credential_id).await?,
+            None => None,
+        };
+        let Some(credential) = credential.filter(|credential| credential.institution_id == institution.id) else {
+            return Ok(not_verified(issuer, "Credential not found"));
+        };
+
+        let status = parse_credential_status(&credential.status)?;
+        let expired = credential.expiry_date.is_some_and(|expiry_date| expiry_date <= Utc::now());
+
+        let message = if status == CredentialStatus::Revoked {
+            "Credential has been revoked"
+        } else if status == CredentialStatus::Suspended {
+            "Credential has been suspended"
+        } else if expired || status == CredentialStatus::Expired {
+            "Credential has expired"
+        } else if status != CredentialStatus::Issued {
+            "Credential is not currently valid"
+        } else {
+            "Credential is valid and verified"
+        };
+
+        Ok(SdJwtVerificationResponse {
+            valid: status == CredentialStatus::Issued && !expired,
+            issuer,
+            claims,
+            status: Some(status),
+            message: message.to_string(),
         })
     }
 
-    pub async fn verify_credential(&self, credential_id: &str) -> AppResult<bool> {
+    /// Verify a credential's integrity and whether it was valid at `at`
+    /// (now by default). Returns the validity and the status at that time.
+    pub async fn verify_credential(
+        &self,
+        credential_id: &str,
+        at: Option<DateTime<Utc>>,
+    ) -> AppResult<(bool, CredentialStatus)> {
         // Check if credential exists in database
         let credential = self.credential_repo
             .get_credential_by_credential_id(credential_id)
@@ -86,8 +591,14 @@ impl CredentialService {
             .ok_or_else(|| AppError::NotFound("Credential not found".to_string()))?;
 
         // Check status
-        if credential.status != "issued" {
-            return Ok(false);
+        let status = self.status_at(&credential, &at.unwrap_or_else(Utc::now)).await?;
+        if status != CredentialStatus::Issued {
+            return Ok((false, status));
+        }
+
+        // Check the issuer signature
+        if !self.verify_issuer_signature(&credential).await? {
+            return Ok((false, status));
         }
 
         // Verify on blockchain
@@ -95,6 +606,86 @@ impl CredentialService {
             .verify_credential_on_chain(credential_id)
             .await?;
 
-        Ok(on_chain_valid)
+        Ok((on_chain_valid, status))
+    }
+}
+
+/// Check a status change fits the credential lifecycle and its recorded
+/// history, and build the event that records it
+pub fn status_change_event(
+    credential: &CredentialDb,
+    next: &CredentialStatus,
+    change: StatusChange,
+    events: &[CredentialStatusEventDb],
+    now: DateTime<Utc>,
+) -> AppResult<CredentialStatusEventDb> {
+    let current = parse_credential_status(&credential.status)?;
+    if !current.can_transition_to(next) {
+        return Err(AppError::Validation(format!(
+            "Cannot change a {} credential to {}",
+            credential_status_name(&current),
+            credential_status_name(next),
+        )));
+    }
+
+    // Once its validity has run out a credential can only expire or be
+    // revoked, even if the expiry worker has not processed it yet
+    let expired = credential.expiry_date.is_some_and(|expiry_date| expiry_date <= now);
+    if expired && *next == CredentialStatus::Issued {
+        return Err(AppError::Validation("Expired credentials cannot be reinstated".to_string()));
+    }
+    if expired && *next == CredentialStatus::Suspended {
+        return Err(AppError::Validation("Expired credentials cannot be suspended".to_string()));
+    }
+
+    // Back-dated changes must still fit into the credential's history
+    let effective_at = change.effective_at.unwrap_or(now).trunc_subsecs(6);
+    if effective_at > now {
+        return Err(AppError::Validation("Effective date cannot be in the future".to_string()));
     }
+    if effective_at < credential.issue_date {
+        return Err(AppError::Validation("Effective date cannot precede the issue date".to_string()));
+    }
+    if events.last().is_some_and(|last| effective_at < last.effective_at) {
+        return Err(AppError::Validation(
+            "Effective date cannot precede the credential's last status change".to_string(),
+        ));
+    }
+
+    Ok(CredentialStatusEventDb {
+        id: Uuid::new_v4(),
+        credential_id: credential.id,
+        from_status: credential.status.clone(),
+        to_status: credential_status_name(next),
+        reason: change.reason,
+        note: change.note,
+        actor_id: change.actor_id,
+        effective_at,
+        recorded_at: now,
+    })
+}
+
+/// Build the canonical byte payload that an issuer signs for a credential.
+///
+/// Only fields fixed at issuance are included; status changes such as
+/// revocation must not invalidate the original signature.
+pub fn credential_signing_payload(credential: &CredentialDb) -> Vec<u8> {
+    let payload = json!({
+        "credential_id": credential.credential_id,
+        "holder_id": credential.holder_id,
+        "issuer_id": credential.issuer_id,
+        "credential_type": credential.credential_type,
+        "title": credential.title,
+        "description": credential.description,
+        "ipfs_hash": credential.ipfs_hash,
+        "issue_date": format_timestamp(&credential.issue_date),
+        "expiry_date": credential.expiry_date.as_ref().map(format_timestamp),
+        "metadata": credential.metadata,
+    });
+
+    to_canonical_json(&payload).into_bytes()
+}
+
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
 }
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+use chrono::Utc;
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use common::{AppError, AppResult, SealPurpose};
+use database::{
+    CredentialDb, CredentialDocumentKeyDb, DocumentKeyRepository, DocumentKeyRepositoryImpl, DocumentWrappingKeyDb,
+};
+use super::KeystoreService;
+
+const NONCE_LEN: usize = 12;
+const HOLDER: &str = "holder";
+const ISSUER: &str = "issuer";
+
+/// Whose wrapped copy of a document key opens the document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentRecipient {
+    Holder,
+    Issuer,
+}
+
+/// A credential document encrypted under a fresh data key, with that key
+/// wrapped for the holder and the issuing institution
+pub struct SealedDocument {
+    pub ciphertext: Vec<u8>,
+    pub keys: Vec<CredentialDocumentKeyDb>,
+}
+
+/// Envelope encryption for credential documents, which may sit in public
+/// storage such as IPFS. Each document has its own AES-256-GCM data key; the
+/// data key is stored only wrapped by per-holder and per-institution wrapping
+/// keys, which are in turn sealed by the keystore.
+pub struct DocumentKeyService {
+    keystore: Arc<KeystoreService>,
+    repo: Arc<DocumentKeyRepositoryImpl>,
+}
+
+/// Encrypt under a 256-bit key, bound to the credential, returning nonce || ciphertext
+fn encrypt(key: &[u8], credential_id: Uuid, plaintext: &[u8]) -> AppResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Internal("Invalid document key".to_string()))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: credential_id.as_bytes() })
+        .map_err(|_| AppError::Internal("Failed to encrypt credential document".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn decrypt(key: &[u8], credential_id: Uuid, sealed: &[u8]) -> AppResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::Internal("Invalid document key".to_string()))?;
+    if sealed.len() <= NONCE_LEN {
+        return Err(AppError::Internal("Corrupt credential document".to_string()));
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: credential_id.as_bytes() })
+        .map_err(|_| AppError::Internal("Failed to decrypt credential document".to_string()))
+}
+
+fn new_key() -> Vec<u8> {
+    Aes256Gcm::generate_key(&mut OsRng).to_vec()
+}
+
+impl DocumentKeyService {
+    pub fn new(keystore: Arc<KeystoreService>, repo: Arc<DocumentKeyRepositoryImpl>) -> Self {
+        Self { keystore, repo }
+    }
+
+    /// Encrypt a document for a credential that is about to be created. The
+    /// returned keys are stored in the same transaction as the credential.
+    pub async fn seal_document(
+        &self,
+        credential_id: Uuid,
+        holder_id: Uuid,
+        institution_id: Uuid,
+        document: &[u8],
+    ) -> AppResult<SealedDocument> {
+        let data_key = new_key();
+        let ciphertext = encrypt(&data_key, credential_id, document)?;
+
+        let mut keys = Vec::new();
+        for (owner_type, owner_id) in [(HOLDER, holder_id), (ISSUER, institution_id)] {
+            let wrapping_key = self.current_wrapping_key(owner_type, owner_id).await?;
+            let wrapped_key = encrypt(&self.open_wrapping_key(&wrapping_key)?, credential_id, &data_key)?;
+            keys.push(CredentialDocumentKeyDb {
+                credential_id,
+                wrapping_key_id: wrapping_key.id,
+                wrapped_key: hex::encode(wrapped_key),
+                created_at: Utc::now(),
+            });
+        }
+
+        Ok(SealedDocument { ciphertext, keys })
+    }
+
+    /// Decrypt a credential's stored document with the recipient's copy of its
+    /// data key. Documents stored before encryption are returned unchanged.
+    pub async fn open_document(
+        &self,
+        credential: &CredentialDb,
+        recipient: DocumentRecipient,
+        document: Vec<u8>,
+    ) -> AppResult<Vec<u8>> {
+        if !credential.document_encrypted {
+            return Ok(document);
+        }
+
+        let keys = self.repo.get_document_keys(credential.id).await?;
+        if keys.is_empty() {
+            return Err(AppError::Internal("Missing credential document keys".to_string()));
+        }
+
+        let (owner_type, owner_id) = match recipient {
+            DocumentRecipient::Holder => (HOLDER, credential.holder_id),
+            DocumentRecipient::Issuer => (ISSUER, credential.institution_id),
+        };
+        let wrapping_key_ids: Vec<Uuid> = keys.iter().map(|key| key.wrapping_key_id).collect();
+        let wrapping_key = self.repo
+            .get_wrapping_keys(&wrapping_key_ids)
+            .await?
+            .into_iter()
+            .find(|key| key.owner_type == owner_type && key.owner_id == owner_id)
+            .ok_or_else(|| AppError::Authorization("The document is not encrypted for this recipient".to_string()))?;
+        let document_key = keys
+            .iter()
+            .find(|key| key.wrapping_key_id == wrapping_key.id)
+            .ok_or_else(|| AppError::Internal("Missing credential document key".to_string()))?;
+
+        let data_key = self.unwrap_data_key(document_key, &self.open_wrapping_key(&wrapping_key)?)?;
+        decrypt(&data_key, credential.id, &document)
+    }
+
+    /// Replace every current wrapping key, rewrapping the document keys it
+    /// protects. Returns how many wrapping keys were rotated and how many
+    /// document keys were rewrapped.
+    pub async fn rotate_wrapping_keys(&self) -> AppResult<(usize, usize)> {
+        let mut rotated = 0;
+        let mut rewrapped = 0;
+
+        for key in self.repo.get_current_wrapping_keys().await? {
+            if let Some(count) = self.rotate_wrapping_key(&key).await? {
+                rotated += 1;
+                rewrapped += count;
+            }
+        }
+
+        Ok((rotated, rewrapped))
+    }
+
+    /// Replace one owner's current wrapping key. Document keys still wrapped
+    /// by the owner's older keys are moved over too. None if the key was
+    /// rotated concurrently.
+    async fn rotate_wrapping_key(&self, current: &DocumentWrappingKeyDb) -> AppResult<Option<usize>> {
+        let document_keys = self.repo
+            .get_document_keys_for_owner(&current.owner_type, current.owner_id)
+            .await?;
+
+        let mut wrapping_key_ids: Vec<Uuid> = document_keys.iter().map(|key| key.wrapping_key_id).collect();
+        wrapping_key_ids.sort();
+        wrapping_key_ids.dedup();
+        let mut old_keys = HashMap::new();
+        for key in self.repo.get_wrapping_keys(&wrapping_key_ids).await? {
+            old_keys.insert(key.id, self.open_wrapping_key(&key)?);
+        }
+
+        let (new_key, secret) = self.new_wrapping_key(&current.owner_type, current.owner_id)?;
+        let mut rewrapped = Vec::with_capacity(document_keys.len());
+        for key in document_keys {
+            let old_secret = old_keys
+                .get(&key.wrapping_key_id)
+                .ok_or_else(|| AppError::Internal("Missing document wrapping key".to_string()))?;
+            let data_key = self.unwrap_data_key(&key, old_secret)?;
+            rewrapped.push(CredentialDocumentKeyDb {
+                wrapped_key: hex::encode(encrypt(&secret, key.credential_id, &data_key)?),
+                ..key
+            });
+        }
+
+        let count = rewrapped.len();
+        let rotated = self.repo.rotate_wrapping_key(current.id, &new_key, &rewrapped).await?;
+        if let Some(key) = &rotated {
+            tracing::info!(
+                owner_type = %key.owner_type,
+                owner_id = %key.owner_id,
+                rewrapped = count,
+                "Rotated document wrapping key"
+            );
+        }
+
+        Ok(rotated.map(|_| count))
+    }
+
+    /// The owner's current wrapping key, creating the first one on demand
+    async fn current_wrapping_key(&self, owner_type: &str, owner_id: Uuid) -> AppResult<DocumentWrappingKeyDb> {
+        if let Some(key) = self.repo.get_current_wrapping_key(owner_type, owner_id).await? {
+            return Ok(key);
+        }
+
+        let (key, _) = self.new_wrapping_key(owner_type, owner_id)?;
+        if let Some(key) = self.repo.create_wrapping_key(&key).await? {
+            return Ok(key);
+        }
+
+        // Another request created it first
+        self.repo
+            .get_current_wrapping_key(owner_type, owner_id)
+            .await?
+            .ok_or_else(|| AppError::Internal("Failed to create document wrapping key".to_string()))
+    }
+
+    fn new_wrapping_key(&self, owner_type: &str, owner_id: Uuid) -> AppResult<(DocumentWrappingKeyDb, Vec<u8>)> {
+        let id = Uuid::new_v4();
+        let secret = new_key();
+        let key = DocumentWrappingKeyDb {
+            id,
+            owner_type: owner_type.to_string(),
+            owner_id,
+            encrypted_key: self.keystore.seal(SealPurpose::DocumentWrappingKey, id, &secret)?,
+            created_at: Utc::now(),
+            rotated_out_at: None,
+        };
+
+        Ok((key, secret))
+    }
+
+    fn open_wrapping_key(&self, key: &DocumentWrappingKeyDb) -> AppResult<Vec<u8>> {
+        self.keystore.open(SealPurpose::DocumentWrappingKey, key.id, &key.encrypted_key)
+    }
+
+    fn unwrap_data_key(&self, key: &CredentialDocumentKeyDb, wrapping_secret: &[u8]) -> AppResult<Vec<u8>> {
+        let wrapped_key = hex::decode(&key.wrapped_key)
+            .map_err(|_| AppError::Internal("Corrupt credential document key".to_string()))?;
+        decrypt(wrapping_secret, key.credential_id, &wrapped_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_encryption_is_bound_to_credential() {
+        let data_key = new_key();
+        let credential_id = Uuid::new_v4();
+        let sealed = encrypt(&data_key, credential_id, b"Transcript").unwrap();
+
+        assert!(!sealed.windows(10).any(|window| window == b"Transcript"));
+        assert_eq!(decrypt(&data_key, credential_id, &sealed).unwrap(), b"Transcript");
+        assert!(decrypt(&data_key, Uuid::new_v4(), &sealed).is_err());
+        assert!(decrypt(&new_key(), credential_id, &sealed).is_err());
+    }
+}
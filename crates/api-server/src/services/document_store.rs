@@ -0,0 +1,165 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use common::{AppError, AppResult};
+use crate::config::Config;
+use super::{ipfs::IpfsStore, s3::S3Store};
+
+const ADDRESS_PREFIX: &str = "sha256-";
+
+/// Stores credential documents under a content address returned by `put`
+#[async_trait]
+pub trait DocumentStore: Send + Sync {
+    /// Store a document, returning its address. Storing the same bytes twice
+    /// returns the same address.
+    async fn put(&self, data: Vec<u8>) -> AppResult<String>;
+    /// The address `put` would give these bytes, without storing them
+    async fn address(&self, data: &[u8]) -> AppResult<String>;
+    /// Fails with `NotFound` for unknown addresses
+    async fn get(&self, address: &str) -> AppResult<Vec<u8>>;
+    /// Deleting an unknown address is not an error
+    async fn delete(&self, address: &str) -> AppResult<()>;
+}
+
+/// The address the filesystem and S3 stores give a document: its SHA-256 digest
+pub fn content_address(data: &[u8]) -> String {
+    format!("{}{}", ADDRESS_PREFIX, hex::encode(Sha256::digest(data)))
+}
+
+/// Check an address is one `content_address` produces, so it can safely name a file or object
+pub fn is_content_address(address: &str) -> bool {
+    address
+        .strip_prefix(ADDRESS_PREFIX)
+        .is_some_and(|digest| digest.len() == 64 && digest.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+}
+
+pub fn document_not_found() -> AppError {
+    AppError::NotFound("Document not found".to_string())
+}
+
+/// Stores documents as files named by their content address, fanned out into
+/// subdirectories by the first two digest characters
+pub struct FilesystemStore {
+    root: PathBuf,
+}
+
+impl FilesystemStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// None for anything that is not a content address
+    fn path(&self, address: &str) -> Option<PathBuf> {
+        if !is_content_address(address) {
+            return None;
+        }
+        let fanout = &address[ADDRESS_PREFIX.len()..ADDRESS_PREFIX.len() + 2];
+        Some(self.root.join(fanout).join(address))
+    }
+}
+
+fn storage_error(action: &str, e: std::io::Error) -> AppError {
+    AppError::Internal(format!("Failed to {} document: {}", action, e))
+}
+
+#[async_trait]
+impl DocumentStore for FilesystemStore {
+    async fn put(&self, data: Vec<u8>) -> AppResult<String> {
+        let address = content_address(&data);
+        let path = self.path(&address).ok_or_else(|| AppError::Internal("Invalid document address".to_string()))?;
+        if tokio::fs::try_exists(&path).await.map_err(|e| storage_error("store", e))? {
+            return Ok(address);
+        }
+
+        let dir = path.parent().ok_or_else(|| AppError::Internal("Invalid document path".to_string()))?;
+        tokio::fs::create_dir_all(dir).await.map_err(|e| storage_error("store", e))?;
+
+        // Write to a temporary file first so readers never see a partial document
+        let temp_path = dir.join(format!(".{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&temp_path, &data).await.map_err(|e| storage_error("store", e))?;
+        tokio::fs::rename(&temp_path, &path).await.map_err(|e| storage_error("store", e))?;
+
+        Ok(address)
+    }
+
+    async fn address(&self, data: &[u8]) -> AppResult<String> {
+        Ok(content_address(data))
+    }
+
+    async fn get(&self, address: &str) -> AppResult<Vec<u8>> {
+        let path = self.path(address).ok_or_else(document_not_found)?;
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(document_not_found()),
+            Err(e) => Err(storage_error("read", e)),
+        }
+    }
+
+    async fn delete(&self, address: &str) -> AppResult<()> {
+        let Some(path) = self.path(address) else {
+            return Ok(());
+        };
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(storage_error("delete", e)),
+        }
+    }
+}
+
+/// Build the document store selected by DOCUMENT_STORE
+pub fn document_store_from_config(config: &Config) -> anyhow::Result<Arc<dyn DocumentStore>> {
+    match config.document_store.as_str() {
+        "filesystem" => Ok(Arc::new(FilesystemStore::new(&config.document_store_dir))),
+        "ipfs" => Ok(Arc::new(IpfsStore::new(&config.ipfs_url)?)),
+        "s3" => {
+            let (Some(endpoint), Some(bucket), Some(access_key_id), Some(secret_access_key)) = (
+                config.s3_endpoint.as_deref(),
+                config.s3_bucket.as_deref(),
+                config.s3_access_key_id.as_deref(),
+                config.s3_secret_access_key.as_deref(),
+            ) else {
+                return Err(anyhow::anyhow!(
+                    "S3_ENDPOINT, S3_BUCKET, S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY must be set when DOCUMENT_STORE=s3"
+                ));
+            };
+            Ok(Arc::new(S3Store::new(endpoint, bucket, &config.s3_region, access_key_id, secret_access_key)?))
+        }
+        other => Err(anyhow::anyhow!("DOCUMENT_STORE must be filesystem, ipfs or s3, got {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_address_is_validated() {
+        let address = content_address(b"diploma");
+        assert!(is_content_address(&address));
+        assert!(!is_content_address("sha256-../../etc/passwd"));
+        assert!(!is_content_address(&address.to_uppercase()));
+        assert!(!is_content_address("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG"));
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_store_round_trip() {
+        let root = std::env::temp_dir().join(format!("ssp-documents-{}", Uuid::new_v4()));
+        let store = FilesystemStore::new(&root);
+
+        let address = store.put(b"Bachelor of Science".to_vec()).await.unwrap();
+        assert_eq!(store.put(b"Bachelor of Science".to_vec()).await.unwrap(), address);
+        assert_eq!(store.get(&address).await.unwrap(), b"Bachelor of Science");
+        assert_eq!(store.address(b"Bachelor of Science").await.unwrap(), address);
+
+        store.delete(&address).await.unwrap();
+        store.delete(&address).await.unwrap();
+        assert!(matches!(store.get(&address).await, Err(AppError::NotFound(_))));
+        assert!(matches!(store.get("mock-ipfs-abc").await, Err(AppError::NotFound(_))));
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use common::{AppError, AppResult};
+use database::{
+    EmailTokenDb, EmailTokenRepository, EmailTokenRepositoryImpl, UserDb, UserRepository, UserRepositoryImpl,
+};
+use crate::config::Config;
+use super::{
+    session::{hash_token, random_token},
+    AuthService, Mailer, SessionService,
+};
+
+const VERIFY_EMAIL: &str = "verify_email";
+const RESET_PASSWORD: &str = "reset_password";
+
+/// Email verification and password reset through single-use, expiring links
+pub struct EmailTokenService {
+    token_repo: Arc<EmailTokenRepositoryImpl>,
+    user_repo: Arc<UserRepositoryImpl>,
+    auth_service: Arc<AuthService>,
+    session_service: Arc<SessionService>,
+    mailer: Arc<dyn Mailer>,
+    frontend_url: String,
+    verification_ttl: Duration,
+    reset_ttl: Duration,
+}
+
+impl EmailTokenService {
+    pub fn new(
+        config: &Config,
+        token_repo: Arc<EmailTokenRepositoryImpl>,
+        user_repo: Arc<UserRepositoryImpl>,
+        auth_service: Arc<AuthService>,
+        session_service: Arc<SessionService>,
+        mailer: Arc<dyn Mailer>,
+    ) -> Self {
+        Self {
+            token_repo,
+            user_repo,
+            auth_service,
+            session_service,
+            mailer,
+            frontend_url: config.frontend_url.trim_end_matches('/').to_string(),
+            verification_ttl: Duration::hours(config.email_verification_ttl_hours),
+            reset_ttl: Duration::minutes(config.password_reset_ttl_minutes),
+        }
+    }
+
+    /// Store a new token for the user, replacing any unused one for the same purpose
+    async fn issue(&self, user_id: Uuid, purpose: &str, ttl: Duration) -> AppResult<String> {
+        let token = random_token();
+        let now = Utc::now();
+
+        self.token_repo
+            .create_email_token(&EmailTokenDb {
+                id: Uuid::new_v4(),
+                user_id,
+                purpose: purpose.to_string(),
+                token_hash: hash_token(&token),
+                created_at: now,
+                expires_at: now + ttl,
+                used_at: None,
+            })
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn send_verification_email(&self, user: &UserDb) -> AppResult<()> {
+        if user.is_verified {
+            return Err(AppError::Validation("Email is already verified".to_string()));
+        }
+
+        let token = self.issue(user.id, VERIFY_EMAIL, self.verification_ttl).await?;
+        let body = format!(
+            "Hello {},\n\nConfirm your email address for Safari Skills Passport by opening:\n\n{}/?verify_email_token={}\n\nThis link expires in {} hours.",
+            user.name,
+            self.frontend_url,
+            token,
+            self.verification_ttl.num_hours(),
+        );
+
+        self.mailer.send(&user.email, "Verify your email address", &body).await
+    }
+
+    pub async fn verify_email(&self, token: &str) -> AppResult<()> {
+        let token = self.token_repo
+            .consume_email_token(&hash_token(token), VERIFY_EMAIL)
+            .await?
+            .ok_or_else(|| AppError::Validation("Invalid or expired verification link".to_string()))?;
+
+        self.user_repo.update_user_verification(token.user_id, true).await?;
+        tracing::info!(user_id = %token.user_id, "Email address verified");
+        Ok(())
+    }
+
+    /// Mail a reset link if the address belongs to an account. Unknown addresses
+    /// succeed silently so the endpoint cannot be used to discover accounts.
+    pub async fn send_password_reset(&self, email: &str) -> AppResult<()> {
+        let Some(user) = self.user_repo.get_user_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let token = self.issue(user.id, RESET_PASSWORD, self.reset_ttl).await?;
+        let body = format!(
+            "Hello {},\n\nReset your Safari Skills Passport password by opening:\n\n{}/?reset_password_token={}\n\nThis link expires in {} minutes. If you did not ask for a reset, you can ignore this email.",
+            user.name,
+            self.frontend_url,
+            token,
+            self.reset_ttl.num_minutes(),
+        );
+
+        self.mailer.send(&user.email, "Reset your password", &body).await
+    }
+
+    /// Set a new password and sign the account out everywhere
+    pub async fn reset_password(&self, token: &str, new_password: &str) -> AppResult<()> {
+        if new_password.is_empty() {
+            return Err(AppError::Validation("New password is required".to_string()));
+        }
+
+        let invalid = || AppError::Validation("Invalid or expired reset link".to_string());
+        let token_hash = hash_token(token);
+
+        // Check the policy before using the link up, so a rejected password can be retried
+        let pending = self.token_repo
+            .get_email_token(&token_hash, RESET_PASSWORD)
+            .await?
+            .ok_or_else(invalid)?;
+        let user = self.user_repo
+            .get_user_by_id(pending.user_id)
+            .await?
+            .ok_or_else(invalid)?;
+        self.auth_service.check_password_policy(new_password, &user.email, &user.name)?;
+
+        let token = self.token_repo
+            .consume_email_token(&token_hash, RESET_PASSWORD)
+            .await?
+            .ok_or_else(invalid)?;
+
+        let password_hash = self.auth_service.hash_password(new_password)?;
+        self.user_repo.update_password(token.user_id, &password_hash).await?;
+
+        // Following the emailed link proves ownership of the address
+        self.user_repo.update_user_verification(token.user_id, true).await?;
+
+        let sessions = self.session_service
+            .revoke_all_sessions(token.user_id, "password_reset")
+            .await?;
+        tracing::info!(user_id = %token.user_id, sessions_revoked = sessions, "Password reset");
+        Ok(())
+    }
+}
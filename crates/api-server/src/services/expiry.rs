@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Utc};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use uuid::Uuid;
+use common::{AppResult, CredentialStatus};
+use database::{CredentialDb, CredentialRepository, InstitutionRepository, UserRepository};
+use super::{AppState, StatusChange};
+
+/// Credentials expired per batch, so one run cannot hold up the worker for long
+const EXPIRY_BATCH_SIZE: i64 = 500;
+
+/// Runs a credential may fail to expire in before the worker stops retrying it
+const MAX_EXPIRY_ATTEMPTS: u32 = 3;
+
+/// Background task that moves credentials past their expiry date to `Expired`
+/// and reminds holders and issuers ahead of expiry
+pub struct ExpiryWorker {
+    state: Arc<AppState>,
+    // Failed expiry attempts per credential, kept until the credential expires
+    failures: Mutex<HashMap<Uuid, u32>>,
+}
+
+impl ExpiryWorker {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self {
+            state,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = StdDuration::from_secs(self.state.config.expiry_check_interval_secs.max(1));
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_once().await {
+                    tracing::error!("Credential expiry processing failed: {}", e);
+                }
+            }
+        })
+    }
+
+    pub async fn run_once(&self) -> AppResult<()> {
+        let expired = self.expire_credentials().await?;
+        if expired > 0 {
+            tracing::info!("Expired {} credentials", expired);
+        }
+
+        let notified = self.send_expiry_notices().await?;
+        if notified > 0 {
+            tracing::info!("Sent expiry reminders for {} credentials", notified);
+        }
+
+        Ok(())
+    }
+
+    async fn expire_credentials(&self) -> AppResult<usize> {
+        let mut expired = 0;
+
+        // Credentials that keep failing are left for an operator, and ones that
+        // fail during this run are not retried in it, so they cannot hold up
+        // the rest of the backlog
+        let mut skipped: Vec<Uuid> = self.failures
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, &attempts)| attempts >= MAX_EXPIRY_ATTEMPTS)
+            .map(|(&id, _)| id)
+            .collect();
+
+        loop {
+            let credentials = self.state.credential_repo
+                .get_expired_credentials(Utc::now(), &skipped, EXPIRY_BATCH_SIZE)
+                .await?;
+
+            for credential in &credentials {
+                // A credential revoked or changed concurrently is simply skipped
+                match self.expire_credential(credential).await {
+                    Ok(()) => {
+                        self.failures.lock().unwrap().remove(&credential.id);
+                        expired += 1;
+                    }
+                    Err(e) => {
+                        let mut failures = self.failures.lock().unwrap();
+                        let attempts = failures.entry(credential.id).or_default();
+                        *attempts += 1;
+                        if *attempts >= MAX_EXPIRY_ATTEMPTS {
+                            tracing::error!(
+                                "Giving up on expiring credential {} after {} attempts: {}",
+                                credential.credential_id, attempts, e,
+                            );
+                        } else {
+                            tracing::warn!("Could not expire credential {}: {}", credential.credential_id, e);
+                        }
+                        skipped.push(credential.id);
+                    }
+                }
+            }
+
+            if (credentials.len() as i64) < EXPIRY_BATCH_SIZE {
+                return Ok(expired);
+            }
+        }
+    }
+
+    async fn expire_credential(&self, credential: &CredentialDb) -> AppResult<()> {
+        let history = self.state.credential_service.status_history(credential).await?;
+        let change = expiry_change(credential, history.last().map(|event| event.effective_at));
+
+        self.state.credential_service
+            .transition_status(credential, CredentialStatus::Expired, change)
+            .await
+    }
+
+    async fn send_expiry_notices(&self) -> AppResult<usize> {
+        let mut lead_days = self.state.config.expiry_notice_days.clone();
+        lead_days.sort_unstable();
+        let Some(&longest) = lead_days.last() else {
+            return Ok(0);
+        };
+
+        let now = Utc::now();
+        let credentials = self.state.credential_repo
+            .get_credentials_expiring_before(now, now + Duration::days(longest.into()))
+            .await?;
+
+        let mut notified = 0;
+        for credential in &credentials {
+            match self.send_expiry_notice(credential, &lead_days, now).await {
+                Ok(true) => notified += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Could not send expiry reminder for credential {}: {}", credential.credential_id, e),
+            }
+        }
+
+        Ok(notified)
+    }
+
+    /// Send the reminder now due for a credential, if any. It is recorded only
+    /// once it has gone out, so a failed send is retried on the next run.
+    async fn send_expiry_notice(
+        &self,
+        credential: &CredentialDb,
+        lead_days: &[i32],
+        now: DateTime<Utc>,
+    ) -> AppResult<bool> {
+        let Some(expiry_date) = credential.expiry_date else {
+            return Ok(false);
+        };
+        let sent = self.state.credential_repo.get_expiry_notices(credential.id).await?;
+        let Some(lead) = notice_due(lead_days, &sent, expiry_date, now) else {
+            return Ok(false);
+        };
+
+        self.notify_expiry(credential, lead).await?;
+        self.state.credential_repo.record_expiry_notice(credential.id, lead).await?;
+        Ok(true)
+    }
+
+    async fn notify_expiry(&self, credential: &CredentialDb, lead_days: i32) -> AppResult<()> {
+        let expiry_date = credential.expiry_date.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default();
+        let holder = self.state.user_repo.get_user_by_id(credential.holder_id).await?;
+        let issuer = self.state.user_repo.get_user_by_id(credential.issuer_id).await?;
+
+        if let Some(holder) = &holder {
+            self.state.notifier
+                .notify(
+                    holder,
+                    &format!("Your credential \"{}\" expires within {} days", credential.title, lead_days),
+                    &format!(
+                        "Your credential {} (\"{}\") expires on {}. Contact the issuer to renew it.",
+                        credential.credential_id, credential.title, expiry_date,
+                    ),
+                )
+                .await?;
+        }
+
+        if let Some(issuer) = &issuer {
+            let institution_name = self.state.institution_repo
+                .get_institution_by_id(credential.institution_id)
+                .await?
+                .map(|institution| institution.institution_name)
+                .unwrap_or_else(|| issuer.name.clone());
+            let holder_name = holder.map(|holder| holder.name).unwrap_or_default();
+
+            self.state.notifier
+                .notify(
+                    issuer,
+                    &format!("Credential {} expires within {} days", credential.credential_id, lead_days),
+                    &format!(
+                        "\"{}\" issued by {} to {} expires on {}.",
+                        credential.title, institution_name, holder_name, expiry_date,
+                    ),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The change that moves a credential to `Expired`, effective when its validity
+/// ran out. It cannot take effect before the credential was issued or before
+/// its last status change, whichever is later.
+fn expiry_change(credential: &CredentialDb, last_change: Option<DateTime<Utc>>) -> StatusChange {
+    let effective_at = credential.expiry_date
+        .into_iter()
+        .chain(Some(credential.issue_date))
+        .chain(last_change)
+        .max();
+
+    StatusChange {
+        actor_id: None,
+        reason: Some("expired".to_string()),
+        note: None,
+        effective_at,
+    }
+}
+
+/// The reminder lead time, in days, due for a credential expiring at
+/// `expiry_date`, given the lead times already sent. Only the shortest lead
+/// time that applies is sent, so a credential first seen close to expiry does
+/// not get every earlier reminder. `lead_days` must be sorted ascending.
+fn notice_due(lead_days: &[i32], sent: &[i32], expiry_date: DateTime<Utc>, now: DateTime<Utc>) -> Option<i32> {
+    lead_days
+        .iter()
+        .copied()
+        .find(|&lead| expiry_date <= now + Duration::days(lead.into()))
+        .filter(|lead| !sent.contains(lead))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::SubsecRound;
+    use uuid::Uuid;
+    use crate::services::credential::status_change_event;
+
+    fn credential(status: &str, expiry_date: DateTime<Utc>) -> CredentialDb {
+        CredentialDb {
+            id: Uuid::new_v4(),
+            credential_id: "SSP-test".to_string(),
+            holder_id: Uuid::new_v4(),
+            issuer_id: Uuid::new_v4(),
+            credential_type: "certificate".to_string(),
+            title: "First Aid".to_string(),
+            description: String::new(),
+            ipfs_hash: String::new(),
+            chain_hash: String::new(),
+            qr_code: String::new(),
+            issuer_signature: String::new(),
+            issuer_public_key: String::new(),
+            issue_date: expiry_date - Duration::days(365),
+            expiry_date: Some(expiry_date),
+            status: status.to_string(),
+            metadata: serde_json::json!({}),
+            created_at: expiry_date - Duration::days(365),
+            status_list_id: None,
+            status_list_index: None,
+            status_reason: None,
+            institution_id: Uuid::new_v4(),
+            document_encrypted: true,
+        }
+    }
+
+    #[test]
+    fn test_expiry_is_effective_at_the_expiry_date() {
+        let now = Utc::now();
+        let expiry_date = now - Duration::hours(3);
+
+        for status in ["issued", "suspended"] {
+            let credential = credential(status, expiry_date);
+            let event = status_change_event(
+                &credential,
+                &CredentialStatus::Expired,
+                expiry_change(&credential, None),
+                &[],
+                now,
+            )
+            .unwrap();
+
+            assert_eq!(event.from_status, status);
+            assert_eq!(event.to_status, "expired");
+            assert_eq!(event.reason.as_deref(), Some("expired"));
+            assert_eq!(event.actor_id, None);
+            assert_eq!(event.effective_at, expiry_date.trunc_subsecs(6));
+            assert_eq!(event.recorded_at, now);
+        }
+
+        // Revoked credentials stay revoked
+        let credential = credential("revoked", expiry_date);
+        assert!(status_change_event(
+            &credential,
+            &CredentialStatus::Expired,
+            expiry_change(&credential, None),
+            &[],
+            now,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_expiry_is_not_effective_before_the_credential_history() {
+        let now = Utc::now();
+        let expiry_date = now - Duration::days(3);
+
+        // Expiry dates recorded before the issue date
+        let mut misdated = credential("issued", expiry_date);
+        misdated.issue_date = (expiry_date + Duration::days(1)).trunc_subsecs(6);
+        let change = expiry_change(&misdated, None);
+        assert_eq!(change.effective_at, Some(misdated.issue_date));
+        assert!(status_change_event(&misdated, &CredentialStatus::Expired, change, &[], now).is_ok());
+
+        // Changes recorded after the expiry date but before the worker ran
+        let suspended = credential("suspended", expiry_date);
+        let suspended_at = expiry_date + Duration::hours(5);
+        let change = expiry_change(&suspended, Some(suspended_at));
+        assert_eq!(change.effective_at, Some(suspended_at));
+    }
+
+    #[test]
+    fn test_notice_due_is_the_shortest_lead_that_applies() {
+        let now = Utc::now();
+        let lead_days = [1, 7, 30];
+
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::days(45), now), None);
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::days(20), now), Some(30));
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::days(30), now), Some(30));
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::days(3), now), Some(7));
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::hours(2), now), Some(1));
+        assert_eq!(notice_due(&[], &[], now + Duration::hours(2), now), None);
+    }
+
+    #[test]
+    fn test_notice_due_is_sent_once() {
+        let now = Utc::now();
+        let lead_days = [1, 7, 30];
+
+        assert_eq!(notice_due(&lead_days, &[30], now + Duration::days(20), now), None);
+        assert_eq!(notice_due(&lead_days, &[30], now + Duration::days(3), now), Some(7));
+        assert_eq!(notice_due(&lead_days, &[7, 30], now + Duration::days(3), now), None);
+        // Skipped longer reminders are not sent late
+        assert_eq!(notice_due(&lead_days, &[], now + Duration::hours(2), now), Some(1));
+        assert_eq!(notice_due(&lead_days, &[1], now + Duration::hours(2), now), None);
+    }
+}
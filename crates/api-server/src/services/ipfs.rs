@@ -1,76 +1,107 @@
-use ipfs_api_backend_hyper::{IpfsApi, IpfsClient, TryFromUri};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use ipfs_api_backend_hyper::{request::Add, Error as IpfsError, IpfsApi, IpfsClient, TryFromUri};
 use common::{AppError, AppResult};
 use std::io::Cursor;
 
-pub struct IpfsService {
-    client: Option<IpfsClient>,
+use super::document_store::{document_not_found, DocumentStore};
+
+/// Stores documents on an IPFS node, addressed by their CID
+pub struct IpfsStore {
+    client: IpfsClient,
 }
 
-impl IpfsService {
+/// Whether the node refused a request because the address is not pinned
+/// there or is not a CID at all
+fn is_unknown_address(error: &IpfsError) -> bool {
+    match error {
+        IpfsError::Api(error) => {
+            let message = error.message.to_lowercase();
+            message.contains("not pinned") || message.contains("invalid")
+        }
+        _ => false,
+    }
+}
+
+impl IpfsStore {
     pub fn new(ipfs_url: &str) -> anyhow::Result<Self> {
         let client = IpfsClient::from_str(ipfs_url)?;
-        Ok(Self { client: Some(client) })
+        Ok(Self { client })
     }
 
-    /// Create a mock IPFS service for development
-    pub fn mock() -> Self {
-        Self { client: None }
+    /// Every document `put` stores is pinned, so the node's pin set is what
+    /// this store holds. Checking it never searches the wider network.
+    async fn is_pinned(&self, address: &str) -> AppResult<bool> {
+        match self.client.pin_ls(Some(address), None).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_unknown_address(&e) => Ok(false),
+            Err(e) => Err(AppError::Ipfs(format!("Failed to check IPFS pins: {}", e))),
+        }
     }
+}
 
-    /// Upload data to IPFS and return the hash
-    pub async fn upload(&self, data: Vec<u8>) -> AppResult<String> {
-        match &self.client {
-            Some(client) => {
-                let cursor = Cursor::new(data);
-                
-                let response = client
-                    .add(cursor)
-                    .await
-                    .map_err(|e| AppError::Ipfs(format!("Failed to upload to IPFS: {}", e)))?;
+#[async_trait]
+impl DocumentStore for IpfsStore {
+    async fn put(&self, data: Vec<u8>) -> AppResult<String> {
+        let response = self.client
+            .add(Cursor::new(data))
+            .await
+            .map_err(|e| AppError::Ipfs(format!("Failed to upload to IPFS: {}", e)))?;
 
-                Ok(response.hash)
-            }
-            None => {
-                // Mock implementation - generate a hash from the data
-                use sha2::{Sha256, Digest};
-                let mut hasher = Sha256::new();
-                hasher.update(&data);
-                let hash = hasher.finalize();
-                Ok(format!("mock-ipfs-{}", hex::encode(hash)))
-            }
-        }
+        Ok(response.hash)
     }
 
-    /// Download data from IPFS using hash
-    #[allow(dead_code)]
-    pub async fn download(&self, hash: &str) -> AppResult<Vec<u8>> {
-        match &self.client {
-            Some(client) => {
-                let data = client
-                    .cat(hash)
-                    .map_ok(|chunk| chunk.to_vec())
-                    .try_concat()
-                    .await
-                    .map_err(|e| AppError::Ipfs(format!("Failed to download from IPFS: {}", e)))?;
+    /// Ask the node for the CID without storing anything, since computing it
+    /// means building the same chunked DAG the node would
+    async fn address(&self, data: &[u8]) -> AppResult<String> {
+        let options = Add { only_hash: Some(true), ..Default::default() };
+        let response = self.client
+            .add_with_options(Cursor::new(data.to_vec()), options)
+            .await
+            .map_err(|e| AppError::Ipfs(format!("Failed to hash document on IPFS: {}", e)))?;
 
-                Ok(data)
-            }
-            None => {
-                // Mock implementation - return empty data
-                Err(AppError::Ipfs("IPFS not available in mock mode".to_string()))
-            }
+        Ok(response.hash)
+    }
+
+    async fn get(&self, address: &str) -> AppResult<Vec<u8>> {
+        // Reading an unknown CID would make the node search the network for it
+        if !self.is_pinned(address).await? {
+            return Err(document_not_found());
         }
+
+        self.client
+            .cat(address)
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .map_err(|e| AppError::Ipfs(format!("Failed to download from IPFS: {}", e)))
     }
 
-    /// Check if content exists on IPFS
-    #[allow(dead_code)]
-    pub async fn exists(&self, hash: &str) -> bool {
-        match &self.client {
-            Some(client) => client.object_stat(hash).await.is_ok(),
-            None => hash.starts_with("mock-ipfs-"), // Mock hashes start with this prefix
+    /// Unpin the document so the node may garbage-collect it. Copies pinned
+    /// elsewhere on the network stay available.
+    async fn delete(&self, address: &str) -> AppResult<()> {
+        match self.client.pin_rm(address, true).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_unknown_address(&e) => Ok(()),
+            Err(e) => Err(AppError::Ipfs(format!("Failed to unpin from IPFS: {}", e))),
         }
     }
 }
 
-// Import for TryStreamExt
-use futures::TryStreamExt;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipfs_api_backend_hyper::response::ApiError;
+
+    fn api_error(message: &str) -> IpfsError {
+        IpfsError::Api(ApiError { message: message.to_string(), code: 0 })
+    }
+
+    #[test]
+    fn test_unknown_addresses_are_told_apart_from_node_errors() {
+        assert!(is_unknown_address(&api_error("path 'QmUnknown' is not pinned")));
+        assert!(is_unknown_address(&api_error("not pinned or pinned indirectly")));
+        assert!(is_unknown_address(&api_error("invalid path \"not-a-cid\": invalid cid")));
+        assert!(!is_unknown_address(&api_error("context deadline exceeded")));
+    }
+}
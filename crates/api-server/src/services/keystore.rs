@@ -0,0 +1,214 @@
+use std::sync::Arc;
+use uuid::Uuid;
+use chrono::Utc;
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use common::{AppError, AppResult, SealPurpose};
+use database::{SealedSecretRepository, WalletKeyDb, WalletKeyRepositoryImpl, WalletKeyRepository};
+use super::BlockchainService;
+
+const NONCE_LEN: usize = 12;
+
+/// Marks values sealed with their purpose as well as their owner
+const LABELLED_PREFIX: &str = "v2:";
+
+const SEAL_PURPOSES: [SealPurpose; 5] = [
+    SealPurpose::WalletPhrase,
+    SealPurpose::TotpSecret,
+    SealPurpose::IssuerSeed,
+    SealPurpose::DocumentWrappingKey,
+    SealPurpose::TokenSigningKey,
+];
+
+fn purpose_label(purpose: SealPurpose) -> &'static str {
+    match purpose {
+        SealPurpose::WalletPhrase => "wallet",
+        SealPurpose::TotpSecret => "totp",
+        SealPurpose::IssuerSeed => "issuer-seed",
+        SealPurpose::DocumentWrappingKey => "doc-wrap",
+        SealPurpose::TokenSigningKey => "jwt",
+    }
+}
+
+/// `label:` followed by the owner's id
+fn associated_data(purpose: SealPurpose, owner_id: Uuid) -> Vec<u8> {
+    let mut aad = format!("{}:", purpose_label(purpose)).into_bytes();
+    aad.extend_from_slice(owner_id.as_bytes());
+    aad
+}
+
+/// Hex encoded nonce || ciphertext
+fn encrypt(cipher: &Aes256Gcm, aad: &[u8], secret: &[u8]) -> AppResult<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: secret, aad })
+        .map_err(|_| AppError::Internal("Failed to seal key material".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(hex::encode(sealed))
+}
+
+fn decrypt(cipher: &Aes256Gcm, aad: &[u8], sealed: &str) -> AppResult<Vec<u8>> {
+    let sealed = hex::decode(sealed)
+        .map_err(|e| AppError::Internal(format!("Corrupt sealed key material: {}", e)))?;
+    if sealed.len() <= NONCE_LEN {
+        return Err(AppError::Internal("Corrupt sealed key material".to_string()));
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| AppError::Internal("Failed to open sealed key material".to_string()))
+}
+
+/// Holds secret key material encrypted at rest under the server master key.
+///
+/// Every sealed value is bound to its purpose and its owner's id as associated
+/// data, so a ciphertext copied onto another row, or into another column for
+/// the same owner, will fail to open.
+pub struct KeystoreService {
+    cipher: Aes256Gcm,
+    wallet_key_repo: Arc<WalletKeyRepositoryImpl>,
+}
+
+impl KeystoreService {
+    pub fn new(master_key: &str, wallet_key_repo: Arc<WalletKeyRepositoryImpl>) -> anyhow::Result<Self> {
+        let key = hex::decode(master_key)
+            .map_err(|e| anyhow::anyhow!("KEYSTORE_MASTER_KEY must be hex encoded: {}", e))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| anyhow::anyhow!("KEYSTORE_MASTER_KEY must be 32 bytes (64 hex characters)"))?;
+
+        Ok(Self {
+            cipher,
+            wallet_key_repo,
+        })
+    }
+
+    /// Encrypt a secret for the given purpose and owner
+    pub fn seal(&self, purpose: SealPurpose, owner_id: Uuid, secret: &[u8]) -> AppResult<String> {
+        let sealed = encrypt(&self.cipher, &associated_data(purpose, owner_id), secret)?;
+        Ok(format!("{}{}", LABELLED_PREFIX, sealed))
+    }
+
+    /// Decrypt a secret previously sealed for the given purpose and owner
+    pub fn open(&self, purpose: SealPurpose, owner_id: Uuid, sealed: &str) -> AppResult<Vec<u8>> {
+        let sealed = sealed
+            .strip_prefix(LABELLED_PREFIX)
+            .ok_or_else(|| AppError::Internal("Sealed key material has no purpose label".to_string()))?;
+        decrypt(&self.cipher, &associated_data(purpose, owner_id), sealed)
+    }
+
+    /// Re-seal values stored before they were bound to their purpose, which
+    /// were bound to their owner's id alone. Must run before anything opens
+    /// sealed values. Values that cannot be opened are left as they are.
+    pub async fn relabel_legacy_secrets(&self, repo: &impl SealedSecretRepository) -> AppResult<usize> {
+        let mut relabelled = 0;
+
+        for purpose in SEAL_PURPOSES {
+            for secret in repo.get_sealed_secrets_without_prefix(purpose, LABELLED_PREFIX).await? {
+                let plaintext = match decrypt(&self.cipher, secret.owner_id.as_bytes(), &secret.sealed) {
+                    Ok(plaintext) => plaintext,
+                    Err(e) => {
+                        tracing::warn!("Could not relabel {} secret {}: {}", purpose_label(purpose), secret.id, e);
+                        continue;
+                    }
+                };
+
+                let sealed = self.seal(purpose, secret.owner_id, &plaintext)?;
+                if repo.replace_sealed_secret(purpose, &secret, &sealed).await? {
+                    relabelled += 1;
+                }
+            }
+        }
+
+        Ok(relabelled)
+    }
+
+    /// Encrypt a freshly generated wallet's mnemonic for its user, ready to
+    /// be stored along with the account
+    pub fn seal_custodial_wallet(&self, user_id: Uuid, wallet_address: &str, phrase: &str) -> AppResult<WalletKeyDb> {
+        Ok(WalletKeyDb {
+            user_id,
+            wallet_address: wallet_address.to_string(),
+            encrypted_phrase: self.seal(SealPurpose::WalletPhrase, user_id, phrase.as_bytes())?,
+            mnemonic_exported_at: None,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Give an existing account a custodial wallet, storing its key and
+    /// switching the account to its address together
+    pub async fn store_custodial_wallet(
+        &self,
+        user_id: Uuid,
+        wallet_address: &str,
+        phrase: &str,
+    ) -> AppResult<WalletKeyDb> {
+        let key = self.seal_custodial_wallet(user_id, wallet_address, phrase)?;
+        self.wallet_key_repo.set_custodial_wallet(&key).await
+    }
+
+    pub async fn get_wallet_key(&self, user_id: Uuid) -> AppResult<Option<WalletKeyDb>> {
+        self.wallet_key_repo.get_wallet_key(user_id).await
+    }
+
+    /// Reveal a custodial wallet's mnemonic. Each wallet can be exported only once.
+    pub async fn export_mnemonic(&self, user_id: Uuid) -> AppResult<String> {
+        let key = self.wallet_key_repo
+            .get_wallet_key(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("No custodial wallet for this user".to_string()))?;
+
+        let phrase = self.open_phrase(&key)?;
+
+        if !self.wallet_key_repo.mark_mnemonic_exported(user_id).await? {
+            return Err(AppError::Validation("Mnemonic has already been exported".to_string()));
+        }
+
+        Ok(phrase)
+    }
+
+    /// Sign a payload with a user's custodial wallet key for server-side
+    /// operations, returning the wallet address and the signature
+    pub async fn sign_for_user(&self, user_id: Uuid, payload: &[u8]) -> AppResult<(String, String)> {
+        let key = self.wallet_key_repo
+            .get_wallet_key(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("No custodial wallet for this user".to_string()))?;
+
+        let signature = BlockchainService::sign_with_phrase(&self.open_phrase(&key)?, payload)?;
+        Ok((key.wallet_address, signature))
+    }
+
+    /// Discard a user's custodial key, e.g. after they link their own address
+    pub async fn delete_wallet_key(&self, user_id: Uuid) -> AppResult<()> {
+        self.wallet_key_repo.delete_wallet_key(user_id).await
+    }
+
+    fn open_phrase(&self, key: &WalletKeyDb) -> AppResult<String> {
+        let phrase = self.open(SealPurpose::WalletPhrase, key.user_id, &key.encrypted_phrase)?;
+        String::from_utf8(phrase)
+            .map_err(|_| AppError::Internal("Corrupt wallet mnemonic".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_values_are_bound_to_purpose_and_owner() {
+        let cipher = Aes256Gcm::new_from_slice(&[7u8; 32]).unwrap();
+        let user_id = Uuid::new_v4();
+        let sealed = encrypt(&cipher, &associated_data(SealPurpose::TotpSecret, user_id), b"JBSWY3DP").unwrap();
+
+        assert_eq!(decrypt(&cipher, &associated_data(SealPurpose::TotpSecret, user_id), &sealed).unwrap(), b"JBSWY3DP");
+        assert!(decrypt(&cipher, &associated_data(SealPurpose::WalletPhrase, user_id), &sealed).is_err());
+        assert!(decrypt(&cipher, &associated_data(SealPurpose::TotpSecret, Uuid::new_v4()), &sealed).is_err());
+        // Nor does it open as a value sealed before purpose labels
+        assert!(decrypt(&cipher, user_id.as_bytes(), &sealed).is_err());
+    }
+}
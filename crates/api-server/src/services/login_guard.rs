@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use common::{AppError, AppResult};
+use database::{FailedLoginDb, LoginAuditRepository, LoginAuditRepositoryImpl};
+use crate::config::Config;
+
+/// Entries idle for longer than the reset window are dropped once the map grows past this
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Failed attempts recorded against one key since its counter last reset
+#[derive(Debug, Clone, Copy)]
+pub struct AttemptState {
+    pub failures: u32,
+    pub last_failure_at: DateTime<Utc>,
+    pub refusals: u32, // Attempts refused since the last failure
+}
+
+/// Keeps failed-login counters per account and per client IP
+#[async_trait]
+pub trait LoginAttemptStore: Send + Sync {
+    async fn get(&self, key: &str) -> Option<AttemptState>;
+    /// Count a failure. Counters whose last failure is older than `reset_after` start over.
+    async fn record_failure(&self, key: &str, now: DateTime<Utc>, reset_after: Duration) -> AttemptState;
+    /// Count an attempt refused while the key waits, returning the refusals
+    /// since its last failure. Zero for keys without failures.
+    async fn record_refusal(&self, key: &str) -> u32;
+    async fn clear(&self, key: &str);
+}
+
+/// Counters held in process memory, for single-node deployments and tests
+#[derive(Default)]
+pub struct InMemoryLoginAttemptStore {
+    attempts: Mutex<HashMap<String, AttemptState>>,
+}
+
+#[async_trait]
+impl LoginAttemptStore for InMemoryLoginAttemptStore {
+    async fn get(&self, key: &str) -> Option<AttemptState> {
+        self.attempts.lock().await.get(key).copied()
+    }
+
+    async fn record_failure(&self, key: &str, now: DateTime<Utc>, reset_after: Duration) -> AttemptState {
+        let mut attempts = self.attempts.lock().await;
+        if attempts.len() > PRUNE_THRESHOLD {
+            attempts.retain(|_, state| now - state.last_failure_at < reset_after);
+        }
+
+        let state = attempts.entry(key.to_string()).or_insert(AttemptState {
+            failures: 0,
+            last_failure_at: now,
+            refusals: 0,
+        });
+        if now - state.last_failure_at >= reset_after {
+            state.failures = 0;
+        }
+        state.failures += 1;
+        state.last_failure_at = now;
+        state.refusals = 0;
+        *state
+    }
+
+    async fn record_refusal(&self, key: &str) -> u32 {
+        let mut attempts = self.attempts.lock().await;
+        let Some(state) = attempts.get_mut(key) else {
+            return 0;
+        };
+        state.refusals += 1;
+        state.refusals
+    }
+
+    async fn clear(&self, key: &str) {
+        self.attempts.lock().await.remove(key);
+    }
+}
+
+/// How long a key must wait after its latest failure. Backoff doubles from one
+/// second once half of `max_failures` is reached; at `max_failures` the key is
+/// locked out for the full lockout period.
+fn backoff(failures: u32, max_failures: u32, lockout: Duration) -> Duration {
+    if failures >= max_failures {
+        return lockout;
+    }
+
+    let free = (max_failures / 2).max(1);
+    if failures < free {
+        return Duration::zero();
+    }
+    let exponent = (failures - free).min(30);
+    Duration::seconds(1i64 << exponent).min(lockout)
+}
+
+fn account_key(email: &str) -> String {
+    format!("account:{}", email)
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+fn wallet_challenge_key(ip: IpAddr) -> String {
+    format!("wallet-challenge:{}", ip)
+}
+
+/// Brute-force protection for password login, the second login step and
+/// re-entered passwords and codes: exponential backoff and temporary lockout
+/// per account and per client IP, checked before any password hashing, with
+/// an audit trail of rejected attempts. Also limits how many wallet sign-in
+/// challenges each client IP can request.
+pub struct LoginGuard {
+    store: Arc<dyn LoginAttemptStore>,
+    audit_repo: Arc<LoginAuditRepositoryImpl>,
+    max_failures_per_account: u32,
+    max_failures_per_ip: u32,
+    max_wallet_challenges_per_ip: u32,
+    lockout: Duration,
+}
+
+impl LoginGuard {
+    pub fn new(config: &Config, store: Arc<dyn LoginAttemptStore>, audit_repo: Arc<LoginAuditRepositoryImpl>) -> Self {
+        Self {
+            store,
+            audit_repo,
+            max_failures_per_account: config.login_max_failed_attempts,
+            max_failures_per_ip: config.login_max_failed_attempts_per_ip,
+            max_wallet_challenges_per_ip: config.wallet_challenges_per_ip,
+            lockout: Duration::minutes(config.login_lockout_minutes),
+        }
+    }
+
+    /// Seconds until the key may try again, if it is backing off or locked out
+    async fn wait_secs(&self, key: &str, max_failures: u32) -> Option<u64> {
+        let state = self.store.get(key).await?;
+        let wait = state.last_failure_at + backoff(state.failures, max_failures, self.lockout) - Utc::now();
+        // Round up so clients retrying after Retry-After are not refused again
+        (wait > Duration::zero()).then(|| (wait.num_milliseconds() as u64).div_ceil(1000))
+    }
+
+    /// Refuse the attempt without checking the password if the account or IP
+    /// must wait. Only the first refusal after each failure is audited, so
+    /// clients retrying during a lockout cannot flood the audit trail.
+    pub async fn check(&self, email: &str, ip: IpAddr) -> AppResult<()> {
+        let account_key = account_key(email);
+        let ip_key = ip_key(ip);
+        let account_wait = self.wait_secs(&account_key, self.max_failures_per_account).await;
+        let ip_wait = self.wait_secs(&ip_key, self.max_failures_per_ip).await;
+
+        if let Some(secs) = account_wait.max(ip_wait) {
+            let mut first_refusal = false;
+            if account_wait.is_some() {
+                first_refusal |= self.store.record_refusal(&account_key).await == 1;
+            }
+            if ip_wait.is_some() {
+                first_refusal |= self.store.record_refusal(&ip_key).await == 1;
+            }
+            if first_refusal {
+                self.audit(email, None, ip, "locked_out").await?;
+            }
+            return Err(AppError::RateLimited(
+                format!("Too many failed login attempts. Try again in {} seconds.", secs),
+                secs,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Count a wallet challenge request against the client IP, refusing it once
+    /// the IP has made too many without a quiet lockout period in between
+    pub async fn throttle_wallet_challenge(&self, ip: IpAddr) -> AppResult<()> {
+        let key = wallet_challenge_key(ip);
+        if let Some(state) = self.store.get(&key).await {
+            let wait = state.last_failure_at + self.lockout - Utc::now();
+            if state.failures >= self.max_wallet_challenges_per_ip && wait > Duration::zero() {
+                let secs = (wait.num_milliseconds() as u64).div_ceil(1000);
+                return Err(AppError::RateLimited(
+                    format!("Too many wallet sign-in requests. Try again in {} seconds.", secs),
+                    secs,
+                ));
+            }
+        }
+
+        // Refused requests are not counted, so the wait ends a lockout period
+        // after the last challenge issued
+        self.store.record_failure(&key, Utc::now(), self.lockout).await;
+        Ok(())
+    }
+
+    /// Count a wrong password or unknown account against both the account and the IP
+    pub async fn record_failure(&self, email: &str, user_id: Option<Uuid>, ip: IpAddr) -> AppResult<()> {
+        let reason = if user_id.is_some() { "invalid_password" } else { "unknown_account" };
+        self.count_failure(email, user_id, ip, reason).await
+    }
+
+    /// Run a password or authentication code check for a known account,
+    /// refusing it while the account or IP must wait and counting an
+    /// authentication failure against both. `reason` is audited on failure.
+    pub async fn attempt<T>(
+        &self,
+        email: &str,
+        user_id: Uuid,
+        ip: IpAddr,
+        reason: &str,
+        check: impl Future<Output = AppResult<T>>,
+    ) -> AppResult<T> {
+        self.check(email, ip).await?;
+
+        let result = check.await;
+        if let Err(AppError::Authentication(_)) = &result {
+            self.count_failure(email, Some(user_id), ip, reason).await?;
+        }
+        result
+    }
+
+    async fn count_failure(&self, email: &str, user_id: Option<Uuid>, ip: IpAddr, reason: &str) -> AppResult<()> {
+        let now = Utc::now();
+        let account = self.store.record_failure(&account_key(email), now, self.lockout).await;
+        self.store.record_failure(&ip_key(ip), now, self.lockout).await;
+
+        if account.failures == self.max_failures_per_account {
+            tracing::warn!(email = %email, ip = %ip, "Account locked after repeated failed logins");
+        }
+
+        self.audit(email, user_id, ip, reason).await
+    }
+
+    /// A completed login resets the account's counter. The IP counter is left
+    /// alone so one valid login cannot clear guesses made against other accounts.
+    pub async fn record_success(&self, email: &str) {
+        self.store.clear(&account_key(email)).await;
+    }
+
+    /// Lift an account's backoff or lockout
+    pub async fn unlock_account(&self, email: &str) {
+        self.store.clear(&account_key(email)).await;
+    }
+
+    async fn audit(&self, email: &str, user_id: Option<Uuid>, ip: IpAddr, reason: &str) -> AppResult<()> {
+        self.audit_repo
+            .record_failed_login(&FailedLoginDb {
+                id: Uuid::new_v4(),
+                email: email.to_string(),
+                user_id,
+                ip_address: ip.to_string(),
+                reason: reason.to_string(),
+                created_at: Utc::now(),
+            })
+            .await
+    }
+
+    /// Rejected attempts, most recent first
+    pub async fn failed_logins(&self, email: Option<&str>, limit: i64) -> AppResult<Vec<FailedLoginDb>> {
+        self.audit_repo.list_failed_logins(email, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_then_locks_out() {
+        let lockout = Duration::minutes(15);
+        assert_eq!(backoff(4, 10, lockout), Duration::zero());
+        assert_eq!(backoff(5, 10, lockout), Duration::seconds(1));
+        assert_eq!(backoff(6, 10, lockout), Duration::seconds(2));
+        assert_eq!(backoff(9, 10, lockout), Duration::seconds(16));
+        assert_eq!(backoff(10, 10, lockout), lockout);
+        assert_eq!(backoff(90, 100, lockout), lockout);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_counts_and_resets() {
+        let store = InMemoryLoginAttemptStore::default();
+        let window = Duration::minutes(15);
+        let start = Utc::now();
+
+        store.record_failure("account:a@example.com", start, window).await;
+        let state = store.record_failure("account:a@example.com", start, window).await;
+        assert_eq!(state.failures, 2);
+
+        // A failure after a quiet window starts a new count
+        let later = start + window + Duration::seconds(1);
+        let state = store.record_failure("account:a@example.com", later, window).await;
+        assert_eq!(state.failures, 1);
+
+        store.clear("account:a@example.com").await;
+        assert!(store.get("account:a@example.com").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_counts_refusals_since_the_last_failure() {
+        let store = InMemoryLoginAttemptStore::default();
+        let window = Duration::minutes(15);
+        let now = Utc::now();
+
+        assert_eq!(store.record_refusal("ip:192.0.2.1").await, 0);
+
+        store.record_failure("ip:192.0.2.1", now, window).await;
+        assert_eq!(store.record_refusal("ip:192.0.2.1").await, 1);
+        assert_eq!(store.record_refusal("ip:192.0.2.1").await, 2);
+
+        // Each failure opens a new wait, whose first refusal is audited again
+        let state = store.record_failure("ip:192.0.2.1", now, window).await;
+        assert_eq!(state.refusals, 0);
+        assert_eq!(store.record_refusal("ip:192.0.2.1").await, 1);
+    }
+}
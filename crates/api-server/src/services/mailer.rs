@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use uuid::Uuid;
+use common::{AppError, AppResult};
+use crate::config::Config;
+
+/// Sends outbound email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> AppResult<()>;
+}
+
+/// Writes emails to the log instead of sending them
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> AppResult<()> {
+        tracing::info!(to = %to, subject = %subject, "Email: {}", body);
+        Ok(())
+    }
+}
+
+/// Drops each email into an outbox directory as an .eml file, for local development
+pub struct FileMailer {
+    outbox_dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(outbox_dir: impl Into<PathBuf>, from: String) -> Self {
+        Self {
+            outbox_dir: outbox_dir.into(),
+            from,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> AppResult<()> {
+        tokio::fs::create_dir_all(&self.outbox_dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create mail outbox: {}", e)))?;
+
+        let now = Utc::now();
+        let path = self.outbox_dir.join(format!("{}-{}.eml", now.format("%Y%m%dT%H%M%S"), Uuid::new_v4()));
+        let message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n{}\r\n",
+            self.from,
+            to,
+            subject,
+            now.to_rfc2822(),
+            body,
+        );
+
+        tokio::fs::write(&path, message)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write email: {}", e)))?;
+
+        tracing::info!(to = %to, subject = %subject, "Email written to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Sends email through an SMTP relay over STARTTLS
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+        from: &str,
+    ) -> anyhow::Result<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?.port(port);
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> AppResult<()> {
+        let to: Mailbox = to
+            .parse()
+            .map_err(|e| AppError::Validation(format!("Invalid email address: {}", e)))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .body(body.to_string())
+            .map_err(|e| AppError::Internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to send email: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Build the mailer selected by MAIL_TRANSPORT
+pub fn mailer_from_config(config: &Config) -> anyhow::Result<Arc<dyn Mailer>> {
+    match config.mail_transport.as_str() {
+        "log" => Ok(Arc::new(LogMailer)),
+        "file" => Ok(Arc::new(FileMailer::new(&config.mail_outbox_dir, config.mail_from.clone()))),
+        "smtp" => {
+            let host = config.smtp_host
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("SMTP_HOST must be set when MAIL_TRANSPORT=smtp"))?;
+            Ok(Arc::new(SmtpMailer::new(
+                host,
+                config.smtp_port,
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+                &config.mail_from,
+            )?))
+        }
+        other => Err(anyhow::anyhow!("MAIL_TRANSPORT must be log, file or smtp, got {}", other)),
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use common::{AppError, AppResult, SealPurpose, TotpSetupResponse, TwoFactorStatus, UserRole};
+use database::{MfaChallengeDb, MfaRepository, MfaRepositoryImpl, UserDb};
+use crate::utils::{qr, totp};
+use super::{
+    session::{hash_token, random_token},
+    KeystoreService,
+};
+
+const TOTP_ISSUER: &str = "Safari Skills Passport";
+const SECRET_LEN: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+/// Recovery codes are compared case-insensitively, ignoring separators
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Ten base32 characters, shown as XXXXX-XXXXX
+fn new_recovery_code() -> String {
+    let mut bytes = [0u8; 8];
+    OsRng.fill_bytes(&mut bytes);
+    let code = totp::base32_encode(&bytes);
+    format!("{}-{}", &code[..5], &code[5..10])
+}
+
+/// TOTP second factor: enrollment, recovery codes and the login challenge
+pub struct MfaService {
+    mfa_repo: Arc<MfaRepositoryImpl>,
+    keystore: Arc<KeystoreService>,
+    required_roles: Vec<UserRole>,
+}
+
+impl MfaService {
+    pub fn new(mfa_repo: Arc<MfaRepositoryImpl>, keystore: Arc<KeystoreService>, required_roles: Vec<UserRole>) -> Self {
+        Self {
+            mfa_repo,
+            keystore,
+            required_roles,
+        }
+    }
+
+    /// Whether accounts with this role must enroll before using the API
+    pub fn is_required_for(&self, role: &UserRole) -> bool {
+        self.required_roles.contains(role)
+    }
+
+    pub async fn is_enabled(&self, user_id: Uuid) -> AppResult<bool> {
+        Ok(self.mfa_repo
+            .get_totp(user_id)
+            .await?
+            .is_some_and(|totp| totp.enabled_at.is_some()))
+    }
+
+    pub async fn status(&self, user_id: Uuid, role: &UserRole) -> AppResult<TwoFactorStatus> {
+        let enabled = self.is_enabled(user_id).await?;
+        let recovery_codes_remaining = if enabled {
+            self.mfa_repo.count_recovery_codes(user_id).await?
+        } else {
+            0
+        };
+
+        Ok(TwoFactorStatus {
+            enabled,
+            required: self.is_required_for(role),
+            recovery_codes_remaining,
+        })
+    }
+
+    /// Generate a new secret for the user to scan; it takes effect once confirmed
+    pub async fn begin_setup(&self, user: &UserDb) -> AppResult<TotpSetupResponse> {
+        let mut secret = [0u8; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+
+        let sealed = self.keystore.seal(SealPurpose::TotpSecret, user.id, &secret)?;
+        if !self.mfa_repo.save_pending_totp(user.id, &sealed).await? {
+            return Err(AppError::Validation("Two-factor authentication is already enabled".to_string()));
+        }
+
+        let otpauth_uri = totp::otpauth_uri(TOTP_ISSUER, &user.email, &secret);
+        Ok(TotpSetupResponse {
+            secret: totp::base32_encode(&secret),
+            qr_code: qr::generate_qr_code(&otpauth_uri)?,
+            otpauth_uri,
+        })
+    }
+
+    /// Confirm enrollment with a first code, returning the initial recovery codes
+    pub async fn enable(&self, user_id: Uuid, code: &str) -> AppResult<Vec<String>> {
+        let pending = self.mfa_repo
+            .get_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_none())
+            .ok_or_else(|| AppError::Validation("Start two-factor setup first".to_string()))?;
+
+        let secret = self.keystore.open(SealPurpose::TotpSecret, user_id, &pending.encrypted_secret)?;
+        let step = totp::verify(&secret, code, Utc::now().timestamp())
+            .ok_or_else(|| AppError::Validation("Invalid authentication code".to_string()))?;
+
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| new_recovery_code()).collect();
+        let hashes: Vec<String> = codes.iter().map(|code| hash_token(&normalize_recovery_code(code))).collect();
+
+        if !self.mfa_repo.enable_totp(user_id, step, &hashes).await? {
+            return Err(AppError::Validation("Two-factor authentication is already enabled".to_string()));
+        }
+
+        tracing::info!(user_id = %user_id, "Two-factor authentication enabled");
+        Ok(codes)
+    }
+
+    /// Check a TOTP code or an unused recovery code. Each is accepted only once.
+    pub async fn verify_code(&self, user_id: Uuid, code: &str) -> AppResult<bool> {
+        let Some(enrolled) = self.mfa_repo
+            .get_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_some())
+        else {
+            return Ok(false);
+        };
+
+        let secret = self.keystore.open(SealPurpose::TotpSecret, user_id, &enrolled.encrypted_secret)?;
+        if let Some(step) = totp::verify(&secret, code, Utc::now().timestamp()) {
+            return self.mfa_repo.record_totp_step(user_id, step).await;
+        }
+
+        let recovery_code = normalize_recovery_code(code);
+        if recovery_code.is_empty() {
+            return Ok(false);
+        }
+        let used = self.mfa_repo.use_recovery_code(user_id, &hash_token(&recovery_code)).await?;
+        if used {
+            tracing::info!(user_id = %user_id, "Recovery code used");
+        }
+        Ok(used)
+    }
+
+    async fn require_code(&self, user_id: Uuid, code: &str) -> AppResult<()> {
+        if !self.verify_code(user_id, code).await? {
+            return Err(AppError::Authentication("Invalid authentication code".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn disable(&self, user_id: Uuid, role: &UserRole, code: &str) -> AppResult<()> {
+        if self.is_required_for(role) {
+            return Err(AppError::Validation("Two-factor authentication is required for your role".to_string()));
+        }
+        self.require_code(user_id, code).await?;
+
+        self.mfa_repo.delete_totp(user_id).await?;
+        tracing::info!(user_id = %user_id, "Two-factor authentication disabled");
+        Ok(())
+    }
+
+    /// Replace all recovery codes, invalidating the old ones
+    pub async fn regenerate_recovery_codes(&self, user_id: Uuid, code: &str) -> AppResult<Vec<String>> {
+        self.require_code(user_id, code).await?;
+
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| new_recovery_code()).collect();
+        let hashes: Vec<String> = codes.iter().map(|code| hash_token(&normalize_recovery_code(code))).collect();
+        self.mfa_repo.replace_recovery_codes(user_id, &hashes).await?;
+
+        Ok(codes)
+    }
+
+    /// Start the second login step after the first factor was accepted.
+    /// Returns the challenge token and its lifetime in seconds.
+    pub async fn create_challenge(&self, user_id: Uuid, login_method: &str) -> AppResult<(String, i64)> {
+        let token = random_token();
+        let now = Utc::now();
+        let ttl = Duration::minutes(CHALLENGE_TTL_MINUTES);
+
+        self.mfa_repo
+            .create_challenge(&MfaChallengeDb {
+                id: Uuid::new_v4(),
+                user_id,
+                token_hash: hash_token(&token),
+                attempts: 0,
+                created_at: now,
+                expires_at: now + ttl,
+                completed_at: None,
+                login_method: login_method.to_string(),
+            })
+            .await?;
+
+        Ok((token, ttl.num_seconds()))
+    }
+
+    /// An open login challenge, so the caller can see whose login it continues
+    pub async fn get_challenge(&self, challenge_token: &str) -> AppResult<MfaChallengeDb> {
+        self.mfa_repo
+            .get_challenge(&hash_token(challenge_token))
+            .await?
+            .filter(|challenge| challenge.completed_at.is_none() && challenge.expires_at > Utc::now())
+            .ok_or_else(|| AppError::Authentication("Invalid or expired login challenge".to_string()))
+    }
+
+    /// Finish the second login step
+    pub async fn complete_challenge(&self, challenge: &MfaChallengeDb, code: &str) -> AppResult<()> {
+        if self.mfa_repo.record_challenge_attempt(challenge.id).await? > MAX_CHALLENGE_ATTEMPTS {
+            return Err(AppError::Authentication("Too many attempts; log in again".to_string()));
+        }
+
+        self.require_code(challenge.user_id, code).await?;
+
+        if !self.mfa_repo.complete_challenge(challenge.id).await? {
+            return Err(AppError::Authentication("Invalid or expired login challenge".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery_codes_normalize_to_their_stored_form() {
+        let code = new_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(&code[5..6], "-");
+
+        let normalized = normalize_recovery_code(&code);
+        assert_eq!(normalize_recovery_code(&code.to_lowercase()), normalized);
+        assert_eq!(normalize_recovery_code(&normalized), normalized);
+    }
+}
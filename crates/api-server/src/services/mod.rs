@@ -1,32 +1,75 @@
 pub mod auth;
+pub mod document_store;
+pub mod document_keys;
 pub mod ipfs;
+pub mod s3;
 pub mod blockchain;
 pub mod credential;
+pub mod keystore;
+pub mod notifier;
+pub mod expiry;
+pub mod session;
+pub mod mailer;
+pub mod email_token;
+pub mod mfa;
+pub mod login_guard;
+pub mod password_policy;
+pub mod api_key;
+pub mod oidc;
+pub mod convert;
 
 use std::sync::Arc;
 use sqlx::PgPool;
+use common::AppResult;
 use crate::config::Config;
+use convert::parse_user_role;
 
 pub use auth::AuthService;
-pub use ipfs::IpfsService;
+pub use document_store::{DocumentStore, document_store_from_config};
+pub use document_keys::DocumentKeyService;
 pub use blockchain::BlockchainService;
-pub use credential::CredentialService;
+pub use credential::{CredentialService, StatusChange};
+pub use keystore::KeystoreService;
+pub use notifier::{Notifier, MailNotifier};
+pub use mailer::{Mailer, mailer_from_config};
+pub use email_token::EmailTokenService;
+pub use mfa::MfaService;
+pub use login_guard::{LoginGuard, InMemoryLoginAttemptStore};
+pub use password_policy::PasswordPolicy;
+pub use api_key::ApiKeyService;
+pub use oidc::OidcService;
+pub use expiry::ExpiryWorker;
+pub use session::SessionService;
 
-use database::{UserRepositoryImpl, InstitutionRepositoryImpl, CredentialRepositoryImpl};
+use database::{
+    UserRepositoryImpl, InstitutionRepositoryImpl, InstitutionMemberRepositoryImpl, AccreditationAuthorityRepositoryImpl,
+    CredentialRepositoryImpl, CredentialStatusEventRepositoryImpl, IssuerKeyRepositoryImpl, WalletKeyRepositoryImpl,
+    SessionRepositoryImpl, JwtKeyRepositoryImpl, EmailTokenRepositoryImpl, MfaRepositoryImpl, WalletLoginRepositoryImpl,
+    LoginAuditRepositoryImpl, ApiKeyRepositoryImpl, OAuthRepositoryImpl, DocumentKeyRepositoryImpl,
+    SealedSecretRepositoryImpl,
+};
 
 pub struct AppState {
-    #[allow(dead_code)]
     pub config: Config,
     #[allow(dead_code)]
     pub db_pool: PgPool,
     pub auth_service: Arc<AuthService>,
-    #[allow(dead_code)]
-    pub ipfs_service: Arc<IpfsService>,
+    pub session_service: Arc<SessionService>,
+    pub email_token_service: Arc<EmailTokenService>,
+    pub mfa_service: Arc<MfaService>,
+    pub login_guard: Arc<LoginGuard>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub oidc_service: Arc<OidcService>,
+    pub document_key_service: Arc<DocumentKeyService>,
     #[allow(dead_code)]
     pub blockchain_service: Arc<BlockchainService>,
     pub credential_service: Arc<CredentialService>,
+    pub keystore: Arc<KeystoreService>,
+    pub notifier: Arc<dyn Notifier>,
     pub user_repo: Arc<UserRepositoryImpl>,
     pub institution_repo: Arc<InstitutionRepositoryImpl>,
+    pub member_repo: Arc<InstitutionMemberRepositoryImpl>,
+    pub authority_repo: Arc<AccreditationAuthorityRepositoryImpl>,
     pub credential_repo: Arc<CredentialRepositoryImpl>,
 }
 
@@ -34,37 +77,108 @@ impl AppState {
     pub async fn new(config: Config, db_pool: PgPool) -> anyhow::Result<Self> {
         let user_repo = Arc::new(UserRepositoryImpl::new(db_pool.clone()));
         let institution_repo = Arc::new(InstitutionRepositoryImpl::new(db_pool.clone()));
+        let member_repo = Arc::new(InstitutionMemberRepositoryImpl::new(db_pool.clone()));
+        let authority_repo = Arc::new(AccreditationAuthorityRepositoryImpl::new(db_pool.clone()));
         let credential_repo = Arc::new(CredentialRepositoryImpl::new(db_pool.clone()));
+        let issuer_key_repo = Arc::new(IssuerKeyRepositoryImpl::new(db_pool.clone()));
+        let status_event_repo = Arc::new(CredentialStatusEventRepositoryImpl::new(db_pool.clone()));
+        let wallet_key_repo = Arc::new(WalletKeyRepositoryImpl::new(db_pool.clone()));
+
+        let keystore = Arc::new(KeystoreService::new(&config.keystore_master_key, wallet_key_repo)?);
+        let relabelled = keystore
+            .relabel_legacy_secrets(&SealedSecretRepositoryImpl::new(db_pool.clone()))
+            .await?;
+        if relabelled > 0 {
+            tracing::info!("Re-sealed {} secrets with their purpose", relabelled);
+        }
 
         let auth_service = Arc::new(AuthService::new(
-            config.jwt_secret.clone(),
-            config.jwt_expiration_hours,
+            &config,
+            Arc::new(JwtKeyRepositoryImpl::new(db_pool.clone())),
+            Arc::new(WalletLoginRepositoryImpl::new(db_pool.clone())),
+            keystore.clone(),
+        )?);
+        auth_service.init().await?;
+        let session_service = Arc::new(SessionService::new(
+            auth_service.clone(),
+            Arc::new(SessionRepositoryImpl::new(db_pool.clone())),
+            user_repo.clone(),
+            config.refresh_token_ttl_days,
+        ));
+
+        let mailer = mailer_from_config(&config)?;
+        let email_token_service = Arc::new(EmailTokenService::new(
+            &config,
+            Arc::new(EmailTokenRepositoryImpl::new(db_pool.clone())),
+            user_repo.clone(),
+            auth_service.clone(),
+            session_service.clone(),
+            mailer.clone(),
+        ));
+
+        let mfa_service = Arc::new(MfaService::new(
+            Arc::new(MfaRepositoryImpl::new(db_pool.clone())),
+            keystore.clone(),
+            config.totp_required_roles
+                .iter()
+                .map(|role| parse_user_role(role))
+                .collect::<AppResult<_>>()?,
         ));
 
-        let ipfs_service = match IpfsService::new(&config.ipfs_url) {
-            Ok(service) => Arc::new(service),
-            Err(e) => {
-                tracing::warn!("Failed to connect to IPFS: {}. Using mock IPFS service.", e);
-                Arc::new(IpfsService::mock())
-            }
-        };
+        let login_guard = Arc::new(LoginGuard::new(
+            &config,
+            Arc::new(InMemoryLoginAttemptStore::default()),
+            Arc::new(LoginAuditRepositoryImpl::new(db_pool.clone())),
+        ));
+
+        let api_key_service = Arc::new(ApiKeyService::new(Arc::new(ApiKeyRepositoryImpl::new(db_pool.clone()))));
+
+        let oidc_service = Arc::new(OidcService::new(
+            Arc::new(OAuthRepositoryImpl::new(db_pool.clone())),
+            user_repo.clone(),
+            credential_repo.clone(),
+            auth_service.clone(),
+            config.oauth_access_token_ttl_minutes,
+        ));
+
+        let document_store = document_store_from_config(&config)?;
+        tracing::info!("Storing documents with the {} store", config.document_store);
+        let document_key_service = Arc::new(DocumentKeyService::new(
+            keystore.clone(),
+            Arc::new(DocumentKeyRepositoryImpl::new(db_pool.clone())),
+        ));
         let blockchain_service = Arc::new(BlockchainService::new(&config.blockchain_node_url).await?);
         
         let credential_service = Arc::new(CredentialService::new(
-            ipfs_service.clone(),
+            document_store,
+            document_key_service.clone(),
             blockchain_service.clone(),
             credential_repo.clone(),
+            institution_repo.clone(),
+            issuer_key_repo,
+            status_event_repo,
+            keystore.clone(),
         ));
 
         Ok(Self {
             config,
             db_pool,
             auth_service,
-            ipfs_service,
+            session_service,
+            email_token_service,
+            mfa_service,
+            login_guard,
+            api_key_service,
+            oidc_service,
+            document_key_service,
             blockchain_service,
             credential_service,
+            keystore,
+            notifier: Arc::new(MailNotifier::new(mailer)),
             user_repo,
             institution_repo,
+            member_repo,
+            authority_repo,
             credential_repo,
         })
     }
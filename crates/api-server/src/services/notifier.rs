@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use common::AppResult;
+use database::UserDb;
+use super::Mailer;
+
+/// Delivers notifications to users
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, recipient: &UserDb, subject: &str, body: &str) -> AppResult<()>;
+}
+
+/// Emails notifications to the user's address
+pub struct MailNotifier {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl MailNotifier {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl Notifier for MailNotifier {
+    async fn notify(&self, recipient: &UserDb, subject: &str, body: &str) -> AppResult<()> {
+        let body = format!("Hello {},\n\n{}\n\n- Safari Skills Passport", recipient.name, body);
+        self.mailer.send(&recipient.email, subject, &body).await
+    }
+}
@@ -0,0 +1,525 @@
+use std::sync::Arc;
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+use uuid::Uuid;
+
+use common::{AppError, AppResult, AuthorizationRequest, OAuthScope, OAuthTokenResponse, User, UserInfoResponse};
+use database::{
+    CredentialDb, CredentialRepository, CredentialRepositoryImpl, OAuthAccessTokenDb, OAuthAuthorizationCodeDb,
+    OAuthClientDb, OAuthGrantDb, OAuthRepository, OAuthRepositoryImpl, UserRepository, UserRepositoryImpl,
+};
+use super::convert::{oauth_scope_name, parse_oauth_scope, user_from_db};
+use super::{AuthService, session::{hash_token, random_token}};
+
+const CLIENT_ID_TAG: &str = "sspc";
+const CODE_TTL_MINUTES: i64 = 5;
+const MAX_NAME_LEN: usize = 100;
+const MAX_REDIRECT_URIS: usize = 10;
+/// A base64url-encoded SHA-256 digest, as S256 code challenges are
+const CODE_CHALLENGE_LEN: usize = 43;
+
+/// An error from the token endpoint, as RFC 6749 section 5.2 classifies them
+#[derive(Debug)]
+pub enum TokenError {
+    InvalidRequest(String),
+    InvalidClient,
+    InvalidGrant(String),
+    UnsupportedGrantType,
+    Server(AppError),
+}
+
+impl From<AppError> for TokenError {
+    fn from(e: AppError) -> Self {
+        TokenError::Server(e)
+    }
+}
+
+/// An authentication request whose client, redirect URI, scopes and PKCE
+/// challenge have been checked
+pub struct ValidAuthorization {
+    pub client: OAuthClientDb,
+    pub scopes: Vec<OAuthScope>,
+    code_challenge: String,
+}
+
+/// Claims of an ID token: who signed in, for which client, and what the
+/// granted scopes release about them
+#[derive(Serialize)]
+struct IdTokenClaims {
+    iss: String,
+    aud: String,
+    exp: i64,
+    iat: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(flatten)]
+    user: UserInfoResponse,
+}
+
+/// Whether `verifier` hashes to `challenge` under the PKCE S256 method (RFC 7636)
+fn pkce_matches(verifier: &str, challenge: &str) -> bool {
+    let well_formed = (43..=128).contains(&verifier.len())
+        && verifier.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b));
+    well_formed && URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == challenge
+}
+
+/// Redirect URIs must be absolute, without a fragment, and use https unless
+/// they point back at the developer's own machine
+fn check_redirect_uri(uri: &str) -> AppResult<()> {
+    let invalid = |reason: &str| AppError::Validation(format!("Invalid redirect URI {}: {}", uri, reason));
+
+    let url = Url::parse(uri).map_err(|_| invalid("not an absolute URL"))?;
+    if url.fragment().is_some() {
+        return Err(invalid("must not contain a fragment"));
+    }
+    let loopback = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => Err(invalid("must use https")),
+    }
+}
+
+/// `redirect_uri` with the response parameters added to its query
+fn redirect_with(redirect_uri: &str, params: &[(&str, &str)]) -> AppResult<String> {
+    let mut url = Url::parse(redirect_uri)
+        .map_err(|_| AppError::Validation("Invalid redirect_uri".to_string()))?;
+    url.query_pairs_mut().extend_pairs(params);
+    Ok(url.into())
+}
+
+/// What the consent screen tells the user each scope shares
+pub fn scope_description(scope: OAuthScope) -> &'static str {
+    match scope {
+        OAuthScope::OpenId => "Sign you in with your Safari Skills Passport account",
+        OAuthScope::Profile => "See your name",
+        OAuthScope::Email => "See your email address and whether it is verified",
+        OAuthScope::CredentialsRead => "Read the credentials you select below, including their current status",
+    }
+}
+
+/// Claims about the user released by the granted scopes
+fn user_claims(user: &User, scopes: &[String]) -> UserInfoResponse {
+    let granted = |scope: OAuthScope| scopes.iter().any(|s| s == oauth_scope_name(scope));
+    let email = granted(OAuthScope::Email);
+
+    UserInfoResponse {
+        sub: user.id.to_string(),
+        name: granted(OAuthScope::Profile).then(|| user.name.clone()),
+        email: email.then(|| user.email.clone()),
+        email_verified: email.then_some(user.is_verified),
+    }
+}
+
+/// OpenID Connect provider for partner portals: employers register OAuth
+/// clients, users consent to share their profile and chosen credentials, and
+/// clients redeem an authorization code, bound to a PKCE challenge, for an ID
+/// token and an opaque access token.
+pub struct OidcService {
+    oauth_repo: Arc<OAuthRepositoryImpl>,
+    user_repo: Arc<UserRepositoryImpl>,
+    credential_repo: Arc<CredentialRepositoryImpl>,
+    auth_service: Arc<AuthService>,
+    access_token_ttl: Duration,
+}
+
+impl OidcService {
+    pub fn new(
+        oauth_repo: Arc<OAuthRepositoryImpl>,
+        user_repo: Arc<UserRepositoryImpl>,
+        credential_repo: Arc<CredentialRepositoryImpl>,
+        auth_service: Arc<AuthService>,
+        access_token_ttl_minutes: i64,
+    ) -> Self {
+        Self {
+            oauth_repo,
+            user_repo,
+            credential_repo,
+            auth_service,
+            access_token_ttl: Duration::minutes(access_token_ttl_minutes),
+        }
+    }
+
+    /// Issuer identifier, which is also where discovery metadata is served
+    pub fn issuer(&self) -> &str {
+        self.auth_service.public_base_url()
+    }
+
+    /// Register a client. Confidential clients get a secret, returned only here.
+    pub async fn register_client(
+        &self,
+        owner_id: Uuid,
+        name: &str,
+        redirect_uris: &[String],
+        confidential: bool,
+    ) -> AppResult<(OAuthClientDb, Option<String>)> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(AppError::Validation(format!("Name must be 1 to {} characters", MAX_NAME_LEN)));
+        }
+        if redirect_uris.is_empty() || redirect_uris.len() > MAX_REDIRECT_URIS {
+            return Err(AppError::Validation(format!("Register 1 to {} redirect URIs", MAX_REDIRECT_URIS)));
+        }
+        for uri in redirect_uris {
+            check_redirect_uri(uri)?;
+        }
+
+        let mut id = [0u8; 12];
+        OsRng.fill_bytes(&mut id);
+        let secret = confidential.then(random_token);
+
+        let client = OAuthClientDb {
+            id: Uuid::new_v4(),
+            client_id: format!("{}_{}", CLIENT_ID_TAG, hex::encode(id)),
+            client_secret_hash: secret.as_deref().map(hash_token),
+            name: name.to_string(),
+            redirect_uris: redirect_uris.to_vec(),
+            owner_id,
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+        let created = self.oauth_repo.create_client(&client).await?;
+
+        tracing::info!(owner_id = %owner_id, client_id = %created.client_id, "OAuth client registered");
+        Ok((created, secret))
+    }
+
+    /// All clients the user registered, newest first, including revoked ones
+    pub async fn list_clients(&self, owner_id: Uuid) -> AppResult<Vec<OAuthClientDb>> {
+        self.oauth_repo.get_clients(owner_id).await
+    }
+
+    /// Stop a client signing anyone in. Its access tokens stop working at once.
+    pub async fn revoke_client(&self, owner_id: Uuid, client_id: &str) -> AppResult<()> {
+        if !self.oauth_repo.revoke_client(owner_id, client_id).await? {
+            return Err(AppError::NotFound("Active OAuth client not found".to_string()));
+        }
+
+        tracing::info!(owner_id = %owner_id, client_id = %client_id, "OAuth client revoked");
+        Ok(())
+    }
+
+    /// Check an authentication request before showing the consent screen or
+    /// acting on the user's decision
+    pub async fn validate_authorization(&self, request: &AuthorizationRequest) -> AppResult<ValidAuthorization> {
+        let client = self.oauth_repo
+            .get_client(&request.client_id)
+            .await?
+            .filter(|client| client.revoked_at.is_none())
+            .ok_or_else(|| AppError::Validation("Unknown client_id".to_string()))?;
+
+        if !client.redirect_uris.contains(&request.redirect_uri) {
+            return Err(AppError::Validation("redirect_uri is not registered for this client".to_string()));
+        }
+        if request.response_type != "code" {
+            return Err(AppError::Validation("Only response_type=code is supported".to_string()));
+        }
+
+        let mut scopes = Vec::new();
+        for name in request.scope.split_whitespace() {
+            let scope = parse_oauth_scope(name)
+                .ok_or_else(|| AppError::Validation(format!("Unknown scope: {}", name)))?;
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        if !scopes.contains(&OAuthScope::OpenId) {
+            return Err(AppError::Validation("The openid scope is required".to_string()));
+        }
+
+        if request.code_challenge_method.as_deref() != Some("S256") {
+            return Err(AppError::Validation("PKCE with code_challenge_method=S256 is required".to_string()));
+        }
+        let code_challenge = request.code_challenge
+            .clone()
+            .filter(|challenge| {
+                challenge.len() == CODE_CHALLENGE_LEN
+                    && challenge.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            })
+            .ok_or_else(|| AppError::Validation("Invalid code_challenge".to_string()))?;
+
+        Ok(ValidAuthorization { client, scopes, code_challenge })
+    }
+
+    /// Credentials of the user that the consent screen offers to share
+    pub async fn shareable_credentials(&self, user_id: Uuid) -> AppResult<Vec<CredentialDb>> {
+        self.credential_repo.get_credentials_by_holder(user_id).await
+    }
+
+    /// Record the user's consent and return the redirect carrying a single-use
+    /// authorization code
+    pub async fn approve(
+        &self,
+        user_id: Uuid,
+        request: &AuthorizationRequest,
+        credential_ids: &[String],
+    ) -> AppResult<String> {
+        let valid = self.validate_authorization(request).await?;
+
+        let mut shared: Vec<String> = Vec::new();
+        if !credential_ids.is_empty() {
+            if !valid.scopes.contains(&OAuthScope::CredentialsRead) {
+                return Err(AppError::Validation(
+                    "Credentials can only be shared when the credentials:read scope is requested".to_string(),
+                ));
+            }
+            let owned = self.credential_repo.get_credentials_by_holder(user_id).await?;
+            for credential_id in credential_ids {
+                if !owned.iter().any(|credential| &credential.credential_id == credential_id) {
+                    return Err(AppError::Validation(format!("Credential {} is not yours", credential_id)));
+                }
+                if !shared.contains(credential_id) {
+                    shared.push(credential_id.clone());
+                }
+            }
+        }
+
+        let code = random_token();
+        let now = Utc::now();
+        let grant = OAuthGrantDb {
+            id: Uuid::new_v4(),
+            client_id: valid.client.id,
+            user_id,
+            scopes: valid.scopes.iter().map(|scope| oauth_scope_name(*scope).to_string()).collect(),
+            credential_ids: shared,
+            created_at: now,
+            revoked_at: None,
+        };
+        let code_row = OAuthAuthorizationCodeDb {
+            id: Uuid::new_v4(),
+            grant_id: grant.id,
+            code_hash: hash_token(&code),
+            redirect_uri: request.redirect_uri.clone(),
+            code_challenge: valid.code_challenge,
+            nonce: request.nonce.clone(),
+            created_at: now,
+            expires_at: now + Duration::minutes(CODE_TTL_MINUTES),
+            used_at: None,
+        };
+        let grant = self.oauth_repo.create_grant(&grant, &code_row).await?;
+
+        tracing::info!(user_id = %user_id, client_id = %valid.client.client_id, grant_id = %grant.id, "OAuth consent granted");
+
+        let mut params = vec![("code", code.as_str()), ("iss", self.issuer())];
+        if let Some(state) = &request.state {
+            params.push(("state", state));
+        }
+        redirect_with(&request.redirect_uri, &params)
+    }
+
+    /// The redirect telling the client the user declined
+    pub async fn deny(&self, request: &AuthorizationRequest) -> AppResult<String> {
+        self.validate_authorization(request).await?;
+
+        let mut params = vec![("error", "access_denied"), ("iss", self.issuer())];
+        if let Some(state) = &request.state {
+            params.push(("state", state));
+        }
+        redirect_with(&request.redirect_uri, &params)
+    }
+
+    /// Redeem an authorization code for an access token and ID token.
+    /// Presenting a code twice revokes the grant it created (RFC 6749 section 4.1.2).
+    pub async fn exchange_code(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+    ) -> Result<OAuthTokenResponse, TokenError> {
+        let client = self.oauth_repo
+            .get_client(client_id)
+            .await?
+            .filter(|client| client.revoked_at.is_none())
+            .ok_or(TokenError::InvalidClient)?;
+
+        let authenticated = match (&client.client_secret_hash, client_secret) {
+            (Some(secret_hash), Some(secret)) => *secret_hash == hash_token(secret),
+            (None, None) => true,
+            _ => false,
+        };
+        if !authenticated {
+            return Err(TokenError::InvalidClient);
+        }
+
+        let invalid = || TokenError::InvalidGrant("Invalid or expired authorization code".to_string());
+
+        let code_row = self.oauth_repo
+            .get_authorization_code(&hash_token(code))
+            .await?
+            .ok_or_else(invalid)?;
+        let grant = self.oauth_repo
+            .get_grant(code_row.grant_id)
+            .await?
+            .filter(|grant| grant.client_id == client.id)
+            .ok_or_else(invalid)?;
+
+        if code_row.used_at.is_some() {
+            self.oauth_repo.revoke_grant(grant.user_id, grant.id).await?;
+            tracing::warn!(client_id = %client.client_id, grant_id = %grant.id, "Authorization code reused; grant revoked");
+            return Err(invalid());
+        }
+        if code_row.expires_at <= Utc::now() || grant.revoked_at.is_some() {
+            return Err(invalid());
+        }
+        if code_row.redirect_uri != redirect_uri {
+            return Err(TokenError::InvalidGrant("redirect_uri does not match the authorization request".to_string()));
+        }
+        if !pkce_matches(code_verifier, &code_row.code_challenge) {
+            return Err(TokenError::InvalidGrant("code_verifier does not match the code_challenge".to_string()));
+        }
+
+        // Losing a race to redeem the same code twice fails here
+        if !self.oauth_repo.consume_authorization_code(code_row.id).await? {
+            return Err(invalid());
+        }
+
+        let user = self.user_repo
+            .get_user_by_id(grant.user_id)
+            .await?
+            .map(user_from_db)
+            .ok_or_else(invalid)?;
+
+        let access_token = random_token();
+        let now = Utc::now();
+        self.oauth_repo.create_access_token(&OAuthAccessTokenDb {
+            id: Uuid::new_v4(),
+            grant_id: grant.id,
+            token_hash: hash_token(&access_token),
+            created_at: now,
+            expires_at: now + self.access_token_ttl,
+        }).await?;
+
+        let claims = IdTokenClaims {
+            iss: self.issuer().to_string(),
+            aud: client.client_id.clone(),
+            exp: now.timestamp() + self.auth_service.access_token_ttl_secs(),
+            iat: now.timestamp(),
+            nonce: code_row.nonce,
+            user: user_claims(&user, &grant.scopes),
+        };
+        let id_token = self.auth_service.generate_id_token(&claims).await?;
+
+        tracing::info!(client_id = %client.client_id, grant_id = %grant.id, "OAuth tokens issued");
+
+        Ok(OAuthTokenResponse {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: self.access_token_ttl.num_seconds(),
+            id_token,
+            scope: grant.scopes.join(" "),
+        })
+    }
+
+    /// The grant behind an unexpired access token whose grant and client are still active
+    pub async fn authenticate_access_token(&self, token: &str) -> AppResult<OAuthGrantDb> {
+        let invalid = || AppError::Authentication("Invalid or expired access token".to_string());
+
+        let row = self.oauth_repo
+            .get_access_token(&hash_token(token))
+            .await?
+            .filter(|row| row.expires_at > Utc::now())
+            .ok_or_else(invalid)?;
+        let grant = self.oauth_repo
+            .get_grant(row.grant_id)
+            .await?
+            .filter(|grant| grant.revoked_at.is_none())
+            .ok_or_else(invalid)?;
+        self.oauth_repo
+            .get_client_by_id(grant.client_id)
+            .await?
+            .filter(|client| client.revoked_at.is_none())
+            .ok_or_else(invalid)?;
+
+        Ok(grant)
+    }
+
+    pub async fn user_info(&self, grant: &OAuthGrantDb) -> AppResult<UserInfoResponse> {
+        let user = self.user_repo
+            .get_user_by_id(grant.user_id)
+            .await?
+            .map(user_from_db)
+            .ok_or_else(|| AppError::Authentication("Invalid or expired access token".to_string()))?;
+
+        Ok(user_claims(&user, &grant.scopes))
+    }
+
+    /// Credentials the user chose to share that they still hold
+    pub async fn shared_credentials(&self, grant: &OAuthGrantDb) -> AppResult<Vec<CredentialDb>> {
+        if !grant.scopes.iter().any(|scope| scope == oauth_scope_name(OAuthScope::CredentialsRead)) {
+            return Err(AppError::Authorization("Access token lacks the credentials:read scope".to_string()));
+        }
+
+        let mut credentials = Vec::with_capacity(grant.credential_ids.len());
+        for credential_id in &grant.credential_ids {
+            let credential = self.credential_repo.get_credential_by_credential_id(credential_id).await?;
+            if let Some(credential) = credential.filter(|credential| credential.holder_id == grant.user_id) {
+                credentials.push(credential);
+            }
+        }
+        Ok(credentials)
+    }
+
+    /// Whether the user shared this credential with the grant's client
+    pub fn shares_credential(&self, grant: &OAuthGrantDb, credential: &CredentialDb) -> bool {
+        grant.scopes.iter().any(|scope| scope == oauth_scope_name(OAuthScope::CredentialsRead))
+            && grant.credential_ids.contains(&credential.credential_id)
+            && credential.holder_id == grant.user_id
+    }
+
+    /// Clients the user has signed in to and not revoked, newest first
+    pub async fn list_grants(&self, user_id: Uuid) -> AppResult<Vec<(OAuthGrantDb, OAuthClientDb)>> {
+        let mut grants = Vec::new();
+        for grant in self.oauth_repo.get_active_grants(user_id).await? {
+            if let Some(client) = self.oauth_repo.get_client_by_id(grant.client_id).await? {
+                grants.push((grant, client));
+            }
+        }
+        Ok(grants)
+    }
+
+    /// Withdraw consent. Access tokens issued under the grant stop working at once.
+    pub async fn revoke_grant(&self, user_id: Uuid, grant_id: Uuid) -> AppResult<()> {
+        if !self.oauth_repo.revoke_grant(user_id, grant_id).await? {
+            return Err(AppError::NotFound("Active grant not found".to_string()));
+        }
+
+        tracing::info!(user_id = %user_id, grant_id = %grant_id, "OAuth grant revoked");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_s256_matches_rfc_7636_example() {
+        // RFC 7636 appendix B
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        assert!(pkce_matches(verifier, challenge));
+        assert!(!pkce_matches("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXK", challenge));
+        // The plain method is not accepted
+        assert!(!pkce_matches(challenge, challenge));
+        assert!(!pkce_matches("too-short", &URL_SAFE_NO_PAD.encode(Sha256::digest(b"too-short"))));
+    }
+
+    #[test]
+    fn test_redirect_uris_must_be_https_or_loopback() {
+        assert!(check_redirect_uri("https://portal.example.com/callback").is_ok());
+        assert!(check_redirect_uri("http://localhost:3000/callback").is_ok());
+        assert!(check_redirect_uri("http://127.0.0.1/callback").is_ok());
+
+        assert!(check_redirect_uri("http://portal.example.com/callback").is_err());
+        assert!(check_redirect_uri("https://portal.example.com/callback#token").is_err());
+        assert!(check_redirect_uri("/callback").is_err());
+        assert!(check_redirect_uri("javascript:alert(1)").is_err());
+    }
+}
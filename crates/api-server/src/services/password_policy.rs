@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use anyhow::Context;
+
+use common::{AppError, AppResult};
+use crate::config::Config;
+
+/// Passwords longer than this are refused so hashing cost stays bounded
+const MAX_LENGTH: usize = 128;
+/// Shorter names and email local parts are too common to be worth refusing
+const MIN_IDENTITY_LEN: usize = 3;
+
+/// Shipped with the server; `PASSWORD_BLOCKLIST_FILE` adds a larger local list
+const COMMON_PASSWORDS: &str = include_str!("../../data/common-passwords.txt");
+
+/// Rules every new password must meet: a minimum length, not a known common
+/// or breached password, and not built from the account's email or name
+pub struct PasswordPolicy {
+    min_length: usize,
+    blocklist: HashSet<String>,
+}
+
+/// One password per line; blank lines and `#` comments are skipped
+fn parse_blocklist(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+}
+
+/// Lowercased with whitespace removed, so "Jane Doe" also matches "janedoe"
+fn compact(value: &str) -> String {
+    value.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl PasswordPolicy {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let mut blocklist: HashSet<String> = parse_blocklist(COMMON_PASSWORDS).collect();
+
+        if let Some(path) = &config.password_blocklist_file {
+            let list = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read PASSWORD_BLOCKLIST_FILE {}", path))?;
+            blocklist.extend(parse_blocklist(&list));
+        }
+
+        Ok(Self {
+            min_length: config.password_min_length,
+            blocklist,
+        })
+    }
+
+    /// Check a new password for the account with this email and name
+    pub fn check(&self, password: &str, email: &str, name: &str) -> AppResult<()> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(AppError::Validation(format!(
+                "Password must be at least {} characters",
+                self.min_length
+            )));
+        }
+        if length > MAX_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Password must be at most {} characters",
+                MAX_LENGTH
+            )));
+        }
+
+        let lowered = password.to_lowercase();
+        if self.blocklist.contains(&lowered) {
+            return Err(AppError::Validation(
+                "This password is too common or has appeared in a data breach".to_string(),
+            ));
+        }
+
+        let local_part = email.split('@').next().unwrap_or_default();
+        let compacted = compact(password);
+        let contains_identity = [compact(local_part), compact(name)]
+            .iter()
+            .any(|part| part.chars().count() >= MIN_IDENTITY_LEN && compacted.contains(part.as_str()));
+        if contains_identity {
+            return Err(AppError::Validation(
+                "Password must not contain your email address or name".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            blocklist: parse_blocklist(COMMON_PASSWORDS).collect(),
+        }
+    }
+
+    #[test]
+    fn test_policy_rejects_short_common_and_personal_passwords() {
+        let policy = policy();
+        let email = "amina.wanjiru@example.com";
+        let name = "Amina Wanjiru";
+
+        assert!(policy.check("Sh0rt!", email, name).is_err());
+        assert!(policy.check("Password123", email, name).is_err());
+        assert!(policy.check("x-amina.wanjiru-9", email, name).is_err());
+        assert!(policy.check("AminaWanjiru2024", email, name).is_err());
+        assert!(policy.check(&"a".repeat(MAX_LENGTH + 1), email, name).is_err());
+
+        assert!(policy.check("SecurePass123!", email, name).is_ok());
+    }
+}
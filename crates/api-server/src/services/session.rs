@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use common::{AppError, AppResult, UserRole};
+use database::{
+    AuthSessionDb, RefreshTokenDb, SessionRepository, SessionRepositoryImpl, UserDb, UserRepository,
+    UserRepositoryImpl,
+};
+use super::convert::user_from_db;
+use super::AuthService;
+
+/// Tokens handed to a client when a session starts or is refreshed
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // Access token lifetime in seconds
+}
+
+/// Login sessions with rotating, single-use refresh tokens.
+/// Presenting a refresh token twice revokes its whole session.
+pub struct SessionService {
+    auth_service: Arc<AuthService>,
+    session_repo: Arc<SessionRepositoryImpl>,
+    user_repo: Arc<UserRepositoryImpl>,
+    refresh_token_ttl: Duration,
+}
+
+/// First factors a session can start with
+pub const LOGIN_PASSWORD: &str = "password";
+pub const LOGIN_WALLET: &str = "wallet";
+
+/// Authentication methods (RFC 8176) for the session's access tokens
+fn session_amr(session: &AuthSessionDb) -> Vec<String> {
+    let first_factor = match session.login_method.as_str() {
+        LOGIN_WALLET => "swk", // Proof of possession of a software-secured key
+        _ => "pwd",
+    };
+
+    let mut amr = vec![first_factor.to_string()];
+    if session.mfa_verified_at.is_some() {
+        amr.push("otp".to_string());
+    }
+    amr
+}
+
+/// Tokens are stored only as a hex SHA-256 hash
+pub(super) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// A random, URL-safe 256-bit token
+pub(super) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Generate a refresh token and its database row
+fn new_refresh_token(session_id: Uuid, ttl: Duration) -> (String, RefreshTokenDb) {
+    let token = random_token();
+
+    let now = Utc::now();
+    let row = RefreshTokenDb {
+        id: Uuid::new_v4(),
+        session_id,
+        token_hash: hash_token(&token),
+        created_at: now,
+        expires_at: now + ttl,
+        used_at: None,
+    };
+
+    (token, row)
+}
+
+impl SessionService {
+    pub fn new(
+        auth_service: Arc<AuthService>,
+        session_repo: Arc<SessionRepositoryImpl>,
+        user_repo: Arc<UserRepositoryImpl>,
+        refresh_token_ttl_days: i64,
+    ) -> Self {
+        Self {
+            auth_service,
+            session_repo,
+            user_repo,
+            refresh_token_ttl: Duration::days(refresh_token_ttl_days),
+        }
+    }
+
+    async fn tokens(
+        &self,
+        session: &AuthSessionDb,
+        email: String,
+        role: UserRole,
+        refresh_token: String,
+    ) -> AppResult<SessionTokens> {
+        Ok(SessionTokens {
+            access_token: self.auth_service
+                .generate_token(session.user_id, session.id, email, role, session_amr(session))
+                .await?,
+            refresh_token,
+            expires_in: self.auth_service.access_token_ttl_secs(),
+        })
+    }
+
+    /// Start a login session. `login_method` is the first factor used (`LOGIN_PASSWORD`
+    /// or `LOGIN_WALLET`); `mfa_verified` records that a second factor was presented.
+    pub async fn start_session(
+        &self,
+        user_id: Uuid,
+        email: String,
+        role: UserRole,
+        login_method: &str,
+        mfa_verified: bool,
+    ) -> AppResult<SessionTokens> {
+        let now = Utc::now();
+        let session = AuthSessionDb {
+            id: Uuid::new_v4(),
+            user_id,
+            created_at: now,
+            last_refreshed_at: now,
+            revoked_at: None,
+            revoked_reason: None,
+            mfa_verified_at: mfa_verified.then_some(now),
+            login_method: login_method.to_string(),
+        };
+        let (refresh_token, row) = new_refresh_token(session.id, self.refresh_token_ttl);
+
+        self.session_repo.create_session(&session, &row).await?;
+
+        self.tokens(&session, email, role, refresh_token).await
+    }
+
+    /// Exchange a refresh token for new tokens, detecting reuse of spent tokens
+    pub async fn refresh(&self, refresh_token: &str) -> AppResult<(SessionTokens, UserDb)> {
+        let invalid = || AppError::Authentication("Invalid refresh token".to_string());
+
+        let token = self.session_repo
+            .get_refresh_token(&hash_token(refresh_token))
+            .await?
+            .ok_or_else(invalid)?;
+
+        if token.used_at.is_some() {
+            self.revoke_for_reuse(token.session_id).await?;
+            return Err(invalid());
+        }
+        if token.expires_at <= Utc::now() {
+            return Err(AppError::Authentication("Refresh token has expired".to_string()));
+        }
+
+        let session = self.session_repo
+            .get_session(token.session_id)
+            .await?
+            .filter(|session| session.revoked_at.is_none())
+            .ok_or_else(invalid)?;
+
+        let (next_token, row) = new_refresh_token(session.id, self.refresh_token_ttl);
+
+        // Losing the race to exchange the token counts as reuse
+        if !self.session_repo.rotate_refresh_token(token.id, &row).await? {
+            self.revoke_for_reuse(session.id).await?;
+            return Err(invalid());
+        }
+
+        let user = self.user_repo
+            .get_user_by_id(session.user_id)
+            .await?
+            .ok_or_else(invalid)?;
+        let role = user_from_db(user.clone()).role;
+
+        let tokens = self.tokens(&session, user.email.clone(), role, next_token).await?;
+        Ok((tokens, user))
+    }
+
+    async fn revoke_for_reuse(&self, session_id: Uuid) -> AppResult<()> {
+        if self.session_repo.revoke_session(session_id, "refresh_token_reuse").await? {
+            tracing::warn!(session_id = %session_id, "Refresh token reused; session revoked");
+        }
+        Ok(())
+    }
+
+    /// The session, unless it has been revoked
+    pub async fn get_active_session(&self, session_id: Uuid) -> AppResult<Option<AuthSessionDb>> {
+        Ok(self.session_repo
+            .get_session(session_id)
+            .await?
+            .filter(|session| session.revoked_at.is_none()))
+    }
+
+    /// Record that the session's user presented a second factor, e.g. while enrolling
+    pub async fn mark_mfa_verified(&self, session_id: Uuid) -> AppResult<()> {
+        self.session_repo.mark_session_mfa_verified(session_id).await
+    }
+
+    pub async fn end_session(&self, session_id: Uuid) -> AppResult<()> {
+        self.session_repo.revoke_session(session_id, "logout").await?;
+        Ok(())
+    }
+
+    /// Revoke every session of a user, returning how many were active
+    pub async fn end_all_sessions(&self, user_id: Uuid) -> AppResult<u64> {
+        self.revoke_all_sessions(user_id, "logout_all").await
+    }
+
+    /// Revoke every session of a user for a security reason, e.g. a password reset
+    pub async fn revoke_all_sessions(&self, user_id: Uuid, reason: &str) -> AppResult<u64> {
+        self.session_repo.revoke_user_sessions(user_id, reason).await
+    }
+
+    /// Revoke every session of a user except the one making the request
+    pub async fn revoke_other_sessions(&self, user_id: Uuid, keep_session_id: Uuid, reason: &str) -> AppResult<u64> {
+        self.session_repo.revoke_other_sessions(user_id, keep_session_id, reason).await
+    }
+}
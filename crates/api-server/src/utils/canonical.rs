@@ -0,0 +1,27 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Serialize a JSON value with object keys sorted recursively, so that the
+/// same logical document always produces the same bytes regardless of how it
+/// was parsed or how Postgres reordered the keys of a JSONB column.
+pub fn to_canonical_json(value: &Value) -> String {
+    serde_json::to_string(&canonicalize(value)).unwrap_or_default()
+}
+
+fn canonicalize(value: &Value) -> CanonicalValue<'_> {
+    match value {
+        Value::Object(map) => CanonicalValue::Object(
+            map.iter().map(|(k, v)| (k.as_str(), canonicalize(v))).collect(),
+        ),
+        Value::Array(items) => CanonicalValue::Array(items.iter().map(canonicalize).collect()),
+        other => CanonicalValue::Scalar(other),
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum CanonicalValue<'a> {
+    Object(BTreeMap<&'a str, CanonicalValue<'a>>),
+    Array(Vec<CanonicalValue<'a>>),
+    Scalar(&'a Value),
+}
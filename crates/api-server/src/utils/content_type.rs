@@ -0,0 +1,51 @@
+// Detect a document's type from its leading bytes, since uploads carry no content type
+
+/// The media type and file extension of a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DocumentType {
+    pub media_type: &'static str,
+    pub extension: &'static str,
+}
+
+const OCTET_STREAM: DocumentType = DocumentType { media_type: "application/octet-stream", extension: "bin" };
+
+/// Sniff the type of a credential document. HTML, SVG and other types a
+/// browser would run scripts in are deliberately served as plain text or bytes.
+pub fn sniff(data: &[u8]) -> DocumentType {
+    let known = |media_type, extension| DocumentType { media_type, extension };
+
+    if data.starts_with(b"%PDF-") {
+        known("application/pdf", "pdf")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        known("image/png", "png")
+    } else if data.starts_with(b"\xFF\xD8\xFF") {
+        known("image/jpeg", "jpg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        known("image/gif", "gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        known("image/webp", "webp")
+    } else if data.starts_with(b"PK\x03\x04") {
+        known("application/zip", "zip")
+    } else if serde_json::from_slice::<serde_json::Value>(data).is_ok_and(|value| value.is_object() || value.is_array()) {
+        known("application/json", "json")
+    } else if std::str::from_utf8(data).is_ok() {
+        known("text/plain; charset=utf-8", "txt")
+    } else {
+        OCTET_STREAM
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_document_types() {
+        assert_eq!(sniff(b"%PDF-1.7\n...").media_type, "application/pdf");
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").extension, "png");
+        assert_eq!(sniff(b"\xFF\xD8\xFF\xE0\0\x10JFIF").media_type, "image/jpeg");
+        assert_eq!(sniff(br#"{"type": "OpenBadgeCredential"}"#).media_type, "application/json");
+        assert_eq!(sniff(b"<html><script>alert(1)</script></html>").media_type, "text/plain; charset=utf-8");
+        assert_eq!(sniff(b"\0\x9F\x92\x96"), OCTET_STREAM);
+    }
+}
@@ -1 +1,8 @@
+pub mod canonical;
+pub mod content_type;
+pub mod open_badges;
 pub mod qr;
+pub mod sd_jwt;
+pub mod status_list;
+pub mod totp;
+pub mod vc;
@@ -0,0 +1,171 @@
+// 1EdTech Open Badges 3.0 (OpenBadgeCredential) mapping
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use common::{AppError, AppResult, Credential, CredentialType, Institution, User};
+use super::vc::{self, VC_CONTEXT_V2};
+
+pub const OB_CONTEXT_V3: &str = "https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.3.json";
+
+/// Only skills and certificates are published as badges
+pub fn is_badge_type(credential_type: &CredentialType) -> bool {
+    matches!(credential_type, CredentialType::Skill | CredentialType::Certificate)
+}
+
+fn achievement_type(credential_type: &CredentialType) -> &'static str {
+    match credential_type {
+        CredentialType::Skill => "Competency",
+        _ => "Certificate",
+    }
+}
+
+/// Build an OpenBadgeCredential from a credential, its issuer and holder.
+/// `image` is a URL or data URI for the badge artwork.
+pub fn to_open_badge(
+    credential: &Credential,
+    issuer: &Institution,
+    holder: &User,
+    image: &str,
+    base_url: &str,
+) -> Value {
+    let id = vc::credential_url(base_url, &credential.credential_id);
+
+    // Issuers may describe how the achievement is earned; fall back to the description
+    let criteria = credential.metadata
+        .get("criteria")
+        .and_then(Value::as_str)
+        .unwrap_or(&credential.description);
+
+    let mut badge = json!({
+        "@context": [VC_CONTEXT_V2, OB_CONTEXT_V3],
+        "id": id,
+        "type": ["VerifiableCredential", "OpenBadgeCredential"],
+        "name": credential.title,
+        "issuer": {
+            "id": vc::issuer_urn(issuer),
+            "type": ["Profile"],
+            "name": issuer.institution_name,
+            "address": {
+                "type": ["Address"],
+                "addressCountry": issuer.country,
+            },
+        },
+        "validFrom": vc::format_date(&credential.issue_date),
+        "credentialSubject": {
+            "id": vc::holder_urn(holder),
+            "type": ["AchievementSubject"],
+            "achievement": {
+                "id": format!("{}#achievement", id),
+                "type": ["Achievement"],
+                "achievementType": achievement_type(&credential.credential_type),
+                "name": credential.title,
+                "description": credential.description,
+                "criteria": { "narrative": criteria },
+                "image": { "id": image, "type": "Image" },
+            },
+        },
+        "proof": vc::data_integrity_proof(credential),
+    });
+
+    if let Some(expiry_date) = &credential.expiry_date {
+        badge["validUntil"] = json!(vc::format_date(expiry_date));
+    }
+
+    badge
+}
+
+/// The fields of an externally issued badge that we need to raise a claim
+#[derive(Debug)]
+pub struct ImportedBadge {
+    pub issuer_id: Option<Uuid>,
+    pub issuer_name: Option<String>,
+    pub credential_type: CredentialType,
+    pub title: String,
+    pub description: String,
+    pub criteria: Option<String>,
+    pub issue_date: DateTime<Utc>,
+    pub expiry_date: Option<DateTime<Utc>>,
+}
+
+fn parse_date(value: Option<&Value>) -> AppResult<Option<DateTime<Utc>>> {
+    match value.and_then(Value::as_str) {
+        Some(date) => DateTime::parse_from_rfc3339(date)
+            .map(|date| Some(date.with_timezone(&Utc)))
+            .map_err(|e| AppError::Validation(format!("Invalid badge date: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// Parse an OpenBadgeCredential (or AchievementCredential) document
+pub fn parse_open_badge(badge: &Value) -> AppResult<ImportedBadge> {
+    let is_badge = badge
+        .get("type")
+        .and_then(Value::as_array)
+        .is_some_and(|types| {
+            types.iter().any(|t| t == "OpenBadgeCredential" || t == "AchievementCredential")
+        });
+    if !is_badge {
+        return Err(AppError::Validation("Document is not an OpenBadgeCredential".to_string()));
+    }
+
+    // The issuer may be a bare IRI or a Profile object
+    let issuer = badge.get("issuer");
+    let issuer_iri = issuer
+        .and_then(|issuer| issuer.get("id").or(Some(issuer)))
+        .and_then(Value::as_str);
+    let issuer_id = issuer_iri
+        .and_then(|iri| iri.strip_prefix("urn:uuid:"))
+        .and_then(|id| Uuid::parse_str(id).ok());
+    let issuer_name = issuer
+        .and_then(|issuer| issuer.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let achievement = badge
+        .pointer("/credentialSubject/achievement")
+        .ok_or_else(|| AppError::Validation("Badge has no achievement".to_string()))?;
+    let title = achievement
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| AppError::Validation("Badge achievement has no name".to_string()))?
+        .to_string();
+    let description = achievement
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let criteria = achievement
+        .pointer("/criteria/narrative")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let credential_type = match achievement.get("achievementType").and_then(Value::as_str) {
+        Some("Competency") | Some("Skill") => CredentialType::Skill,
+        _ => CredentialType::Certificate,
+    };
+
+    // OB 2.0 style issuanceDate is still common in the wild
+    let now = Utc::now();
+    let issue_date = parse_date(badge.get("validFrom").or_else(|| badge.get("issuanceDate")))?
+        .unwrap_or(now);
+    let expiry_date = parse_date(badge.get("validUntil").or_else(|| badge.get("expirationDate")))?;
+
+    // A claim must be issuable once approved
+    if issue_date > now {
+        return Err(AppError::Validation("Badge is not valid yet".to_string()));
+    }
+    if expiry_date.is_some_and(|expiry_date| expiry_date <= now) {
+        return Err(AppError::Validation("Badge has expired".to_string()));
+    }
+
+    Ok(ImportedBadge {
+        issuer_id,
+        issuer_name,
+        credential_type,
+        title,
+        description,
+        criteria,
+        issue_date,
+        expiry_date,
+    })
+}
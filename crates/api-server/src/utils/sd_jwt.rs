@@ -0,0 +1,293 @@
+// SD-JWT (Selective Disclosure for JWTs) encoding
+//
+// Issuer-signed JWTs are signed with the institution's sr25519 key. There is no
+// registered JOSE algorithm for sr25519, so tokens carry `"alg": "Sr25519"` and
+// the issuer's SS58 public key as `kid`; they are verified by this service.
+
+use std::collections::{HashMap, HashSet};
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use common::{AppError, AppResult};
+
+pub const SD_ALG: &str = "sha-256";
+pub const JWS_ALG: &str = "Sr25519";
+pub const SD_JWT_TYP: &str = "vc+sd-jwt";
+
+/// An encoded disclosure together with the dotted claim path it reveals
+#[derive(Debug, Clone)]
+pub struct Disclosure {
+    pub path: String,
+    pub encoded: String,
+}
+
+fn new_salt() -> String {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    URL_SAFE_NO_PAD.encode(salt)
+}
+
+fn digest(encoded: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(encoded.as_bytes()))
+}
+
+fn make_disclosure(name: &str, value: &Value) -> (String, String) {
+    let disclosure = json!([new_salt(), name, value]);
+    let encoded = URL_SAFE_NO_PAD.encode(disclosure.to_string());
+    let digest = digest(&encoded);
+    (encoded, digest)
+}
+
+/// Builds an SD-JWT payload in which chosen claims are replaced by digests
+pub struct SdJwtBuilder {
+    payload: Map<String, Value>,
+    digests: Vec<String>,
+    disclosures: Vec<Disclosure>,
+}
+
+impl SdJwtBuilder {
+    /// Start from the claims that are always visible
+    pub fn new(visible_claims: Map<String, Value>) -> Self {
+        Self {
+            payload: visible_claims,
+            digests: Vec::new(),
+            disclosures: Vec::new(),
+        }
+    }
+
+    /// Add a top-level claim that is only revealed through its disclosure
+    pub fn disclosable(&mut self, name: &str, value: Value) {
+        let (encoded, digest) = make_disclosure(name, &value);
+        self.digests.push(digest);
+        self.disclosures.push(Disclosure { path: name.to_string(), encoded });
+    }
+
+    /// Add an always-visible object whose keys are each individually disclosable
+    pub fn disclosable_object(&mut self, name: &str, object: &Map<String, Value>) {
+        let mut digests = Vec::new();
+        for (key, value) in object {
+            let (encoded, digest) = make_disclosure(key, value);
+            digests.push(digest);
+            self.disclosures.push(Disclosure { path: format!("{}.{}", name, key), encoded });
+        }
+        digests.sort();
+        self.payload.insert(name.to_string(), json!({ "_sd": digests }));
+    }
+
+    pub fn finish(mut self) -> (Value, Vec<Disclosure>) {
+        // Sort digests so their order reveals nothing about the claims
+        self.digests.sort();
+        self.payload.insert("_sd".to_string(), json!(self.digests));
+        self.payload.insert("_sd_alg".to_string(), json!(SD_ALG));
+        (Value::Object(self.payload), self.disclosures)
+    }
+}
+
+/// Encode and sign a compact JWS
+pub fn encode_jws(
+    header: &Value,
+    payload: &Value,
+    sign: impl FnOnce(&[u8]) -> AppResult<Vec<u8>>,
+) -> AppResult<String> {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(payload.to_string()),
+    );
+    let signature = sign(signing_input.as_bytes())?;
+    Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// A compact JWS split into its parts
+pub struct DecodedJws {
+    pub header: Value,
+    pub payload: Value,
+    pub signing_input: String,
+    pub signature: Vec<u8>,
+}
+
+pub fn decode_jws(jws: &str) -> AppResult<DecodedJws> {
+    let invalid = || AppError::Validation("Malformed SD-JWT".to_string());
+
+    let mut parts = jws.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+
+    let decode_json = |part: &str| -> AppResult<Value> {
+        let bytes = URL_SAFE_NO_PAD.decode(part).map_err(|_| invalid())?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    };
+
+    Ok(DecodedJws {
+        header: decode_json(header)?,
+        payload: decode_json(payload)?,
+        signing_input: format!("{}.{}", header, payload),
+        signature: URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?,
+    })
+}
+
+/// Combine an issuer-signed JWT with the disclosures to reveal
+pub fn compose_presentation<'a>(jwt: &str, disclosures: impl IntoIterator<Item = &'a Disclosure>) -> String {
+    let mut presentation = format!("{}~", jwt);
+    for disclosure in disclosures {
+        presentation.push_str(&disclosure.encoded);
+        presentation.push('~');
+    }
+    presentation
+}
+
+/// Split a presentation into the issuer-signed JWT and its disclosures.
+/// Key binding JWTs are not supported, so the presentation must end in `~`.
+pub fn split_presentation(presentation: &str) -> AppResult<(&str, Vec<&str>)> {
+    let body = presentation
+        .trim()
+        .strip_suffix('~')
+        .ok_or_else(|| AppError::Validation("Malformed SD-JWT presentation".to_string()))?;
+
+    let mut parts = body.split('~');
+    let jwt = parts.next().unwrap_or_default();
+    Ok((jwt, parts.collect()))
+}
+
+/// Check each disclosure against the digests in the signed payload and return
+/// the visible claims merged with the disclosed ones.
+pub fn resolve_disclosures(payload: &Value, disclosures: &[&str]) -> AppResult<Value> {
+    if payload.get("_sd_alg").and_then(Value::as_str) != Some(SD_ALG) {
+        return Err(AppError::Validation("Unsupported SD-JWT digest algorithm".to_string()));
+    }
+
+    // Map each digest to the path of the object that contains it
+    let mut digest_paths = HashMap::new();
+    collect_digests(payload, &mut Vec::new(), &mut digest_paths);
+
+    let mut claims = strip_digests(payload);
+    let mut seen = HashSet::new();
+
+    for encoded in disclosures {
+        let digest = digest(encoded);
+        if !seen.insert(digest.clone()) {
+            return Err(AppError::Validation("Duplicate disclosure in presentation".to_string()));
+        }
+        let path = digest_paths
+            .get(&digest)
+            .ok_or_else(|| AppError::Validation("Disclosure does not belong to this credential".to_string()))?;
+
+        let decoded = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+            .ok_or_else(|| AppError::Validation("Malformed disclosure".to_string()))?;
+        let (Some(name), Some(value)) = (decoded.get(1).and_then(Value::as_str), decoded.get(2)) else {
+            return Err(AppError::Validation("Malformed disclosure".to_string()));
+        };
+
+        let mut target = &mut claims;
+        for segment in path {
+            target = target
+                .get_mut(segment)
+                .ok_or_else(|| AppError::Validation("Malformed SD-JWT".to_string()))?;
+        }
+        let target = target
+            .as_object_mut()
+            .ok_or_else(|| AppError::Validation("Malformed SD-JWT".to_string()))?;
+        if target.contains_key(name) {
+            return Err(AppError::Validation("Disclosure overwrites a visible claim".to_string()));
+        }
+        target.insert(name.to_string(), value.clone());
+    }
+
+    Ok(claims)
+}
+
+fn collect_digests(value: &Value, path: &mut Vec<String>, out: &mut HashMap<String, Vec<String>>) {
+    let Some(object) = value.as_object() else {
+        return;
+    };
+
+    if let Some(digests) = object.get("_sd").and_then(Value::as_array) {
+        for digest in digests.iter().filter_map(Value::as_str) {
+            out.insert(digest.to_string(), path.clone());
+        }
+    }
+
+    for (key, child) in object {
+        if key != "_sd" {
+            path.push(key.clone());
+            collect_digests(child, path, out);
+            path.pop();
+        }
+    }
+}
+
+fn strip_digests(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .filter(|(key, _)| key.as_str() != "_sd" && key.as_str() != "_sd_alg")
+                .map(|(key, child)| (key.clone(), strip_digests(child)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build() -> (Value, Vec<Disclosure>) {
+        let mut visible = Map::new();
+        visible.insert("iss".to_string(), json!("urn:uuid:issuer"));
+
+        let mut builder = SdJwtBuilder::new(visible);
+        builder.disclosable("title", json!("Registered Nurse"));
+        builder.disclosable("expiry_date", json!("2027-06-30T00:00:00Z"));
+        let metadata = json!({ "license_class": "A", "ward": "ICU" });
+        builder.disclosable_object("metadata", metadata.as_object().unwrap());
+        builder.finish()
+    }
+
+    #[test]
+    fn test_selective_disclosure_reveals_only_chosen_claims() {
+        let (payload, disclosures) = build();
+        let chosen: Vec<&str> = disclosures
+            .iter()
+            .filter(|d| d.path == "expiry_date" || d.path == "metadata.license_class")
+            .map(|d| d.encoded.as_str())
+            .collect();
+
+        let claims = resolve_disclosures(&payload, &chosen).unwrap();
+        assert_eq!(
+            claims,
+            json!({
+                "iss": "urn:uuid:issuer",
+                "expiry_date": "2027-06-30T00:00:00Z",
+                "metadata": { "license_class": "A" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_foreign_disclosure_is_rejected() {
+        let (payload, _) = build();
+        let (_, other_disclosures) = build();
+
+        let result = resolve_disclosures(&payload, &[other_disclosures[0].encoded.as_str()]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_presentation_round_trip() {
+        let (_, disclosures) = build();
+        let presentation = compose_presentation("header.payload.signature", &disclosures[..2]);
+
+        let (jwt, parts) = split_presentation(&presentation).unwrap();
+        assert_eq!(jwt, "header.payload.signature");
+        assert_eq!(parts, vec![disclosures[0].encoded.as_str(), disclosures[1].encoded.as_str()]);
+    }
+}
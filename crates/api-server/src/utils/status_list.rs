@@ -0,0 +1,149 @@
+// W3C Bitstring Status List v1.0 encoding
+//
+// Status list credentials are signed over their own canonical JSON (sorted
+// keys, proof removed), unlike exported VCs whose proof is the issuance
+// signature, so they are reported under a separate cryptosuite name.
+
+use std::io::{Read, Write};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use common::{AppError, AppResult, Institution, StatusPurpose};
+use super::vc::{self, VC_CONTEXT_V2};
+
+pub const STATUS_LIST_CRYPTOSUITE: &str = "ssp-sr25519-jcs-2025";
+
+/// How long verifiers may cache a status list, in seconds
+pub const STATUS_LIST_TTL_SECS: u64 = 300;
+
+pub fn purpose_name(purpose: StatusPurpose) -> &'static str {
+    match purpose {
+        StatusPurpose::Revocation => "revocation",
+        StatusPurpose::Suspension => "suspension",
+    }
+}
+
+/// Public URL of the status list credential for one purpose
+pub fn status_list_url(base_url: &str, list_id: Uuid, purpose: StatusPurpose) -> String {
+    format!(
+        "{}/api/status-lists/{}/{}",
+        base_url.trim_end_matches('/'),
+        list_id,
+        purpose_name(purpose),
+    )
+}
+
+/// gzip-compress a bitstring and encode it as multibase base64url
+pub fn encode_list(bits: &[u8]) -> AppResult<String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(bits)
+        .map_err(|e| AppError::Internal(format!("Failed to compress status list: {}", e)))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| AppError::Internal(format!("Failed to compress status list: {}", e)))?;
+
+    Ok(format!("u{}", URL_SAFE_NO_PAD.encode(compressed)))
+}
+
+/// Decode a multibase base64url, gzip-compressed bitstring
+#[allow(dead_code)]
+pub fn decode_list(encoded: &str) -> AppResult<Vec<u8>> {
+    let invalid = || AppError::Validation("Malformed status list".to_string());
+
+    let compressed = encoded
+        .strip_prefix('u')
+        .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+        .ok_or_else(invalid)?;
+    let mut bits = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut bits)
+        .map_err(|_| invalid())?;
+
+    Ok(bits)
+}
+
+/// Read an entry; index 0 is the most significant bit of the first byte
+#[allow(dead_code)]
+pub fn is_set(bits: &[u8], index: usize) -> bool {
+    bits.get(index / 8)
+        .is_some_and(|byte| byte & (0x80 >> (index % 8)) != 0)
+}
+
+/// `credentialStatus` entries pointing at a credential's index in its issuer's lists
+pub fn status_entries(base_url: &str, list_id: Uuid, index: i32) -> Value {
+    let entry = |purpose: StatusPurpose| {
+        let list_url = status_list_url(base_url, list_id, purpose);
+        json!({
+            "id": format!("{}#{}", list_url, index),
+            "type": "BitstringStatusListEntry",
+            "statusPurpose": purpose_name(purpose),
+            "statusListIndex": index.to_string(),
+            "statusListCredential": list_url,
+        })
+    };
+
+    json!([entry(StatusPurpose::Revocation), entry(StatusPurpose::Suspension)])
+}
+
+/// Build an unsigned BitstringStatusListCredential for one of an issuer's lists
+pub fn to_status_list_credential(
+    base_url: &str,
+    list_id: Uuid,
+    purpose: StatusPurpose,
+    issuer: &Institution,
+    encoded_list: &str,
+    valid_from: &DateTime<Utc>,
+) -> Value {
+    let id = status_list_url(base_url, list_id, purpose);
+
+    json!({
+        "@context": [VC_CONTEXT_V2],
+        "id": id,
+        "type": ["VerifiableCredential", "BitstringStatusListCredential"],
+        "issuer": {
+            "id": vc::issuer_urn(issuer),
+            "name": issuer.institution_name,
+        },
+        "validFrom": vc::format_date(valid_from),
+        "credentialSubject": {
+            "id": format!("{}#list", id),
+            "type": "BitstringStatusList",
+            "statusPurpose": purpose_name(purpose),
+            "encodedList": encoded_list,
+            "ttl": STATUS_LIST_TTL_SECS * 1000,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoded_list_round_trip() {
+        let mut bits = vec![0u8; 16_384];
+        bits[0] = 0b1000_0001;
+        bits[16_383] = 0b0000_0001;
+
+        let encoded = encode_list(&bits).unwrap();
+        assert!(encoded.starts_with('u'));
+        // An almost empty list compresses to a small fraction of its size
+        assert!(encoded.len() < 1_000);
+
+        let decoded = decode_list(&encoded).unwrap();
+        assert_eq!(decoded, bits);
+        assert!(is_set(&decoded, 0));
+        assert!(!is_set(&decoded, 1));
+        assert!(is_set(&decoded, 7));
+        assert!(is_set(&decoded, 131_071));
+    }
+
+    #[test]
+    fn test_malformed_list_is_rejected() {
+        assert!(decode_list("not-multibase").is_err());
+        assert!(decode_list("uAAAA").is_err());
+    }
+}
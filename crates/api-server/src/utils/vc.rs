@@ -0,0 +1,135 @@
+// W3C Verifiable Credentials Data Model 2.0 mapping
+//
+// The proof embedded in an exported VC is the issuer's sr25519 signature over
+// the canonical credential payload (see `credential_signing_payload`), not over
+// the JSON-LD document itself, so it is reported under our own cryptosuite name.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use common::{Credential, CredentialType, Institution, User};
+
+pub const VC_CONTEXT_V2: &str = "https://www.w3.org/ns/credentials/v2";
+pub const PROOF_CRYPTOSUITE: &str = "ssp-sr25519-2025";
+
+/// Public URL that identifies a credential and resolves to its verification result
+pub fn credential_url(base_url: &str, credential_id: &str) -> String {
+    format!("{}/api/credentials/verify/{}", base_url.trim_end_matches('/'), credential_id)
+}
+
+pub fn issuer_urn(institution: &Institution) -> String {
+    format!("urn:uuid:{}", institution.id)
+}
+
+pub fn holder_urn(holder: &User) -> String {
+    format!("urn:uuid:{}", holder.id)
+}
+
+pub fn credential_type_name(credential_type: &CredentialType) -> &'static str {
+    match credential_type {
+        CredentialType::Certificate => "CertificateCredential",
+        CredentialType::License => "LicenseCredential",
+        CredentialType::Degree => "DegreeCredential",
+        CredentialType::WorkExperience => "WorkExperienceCredential",
+        CredentialType::Skill => "SkillCredential",
+    }
+}
+
+pub fn format_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Build a VC JSON-LD document from a credential, its issuing institution and its holder
+pub fn to_verifiable_credential(
+    credential: &Credential,
+    issuer: &Institution,
+    holder: &User,
+    base_url: &str,
+) -> Value {
+    let id = credential_url(base_url, &credential.credential_id);
+
+    let mut vc = json!({
+        "@context": [VC_CONTEXT_V2],
+        "id": id,
+        "type": ["VerifiableCredential", credential_type_name(&credential.credential_type)],
+        "name": credential.title,
+        "description": credential.description,
+        "issuer": {
+            "id": issuer_urn(issuer),
+            "name": issuer.institution_name,
+            "institutionType": issuer.institution_type,
+            "country": issuer.country,
+        },
+        "validFrom": format_date(&credential.issue_date),
+        "credentialSubject": {
+            "id": holder_urn(holder),
+            "name": holder.name,
+            "walletAddress": holder.wallet_address,
+            "credential": {
+                "credentialType": credential.credential_type,
+                "title": credential.title,
+                "description": credential.description,
+                "metadata": credential.metadata,
+                "documentHash": credential.ipfs_hash,
+            },
+        },
+        "credentialStatus": {
+            "id": format!("{}#status", id),
+            "type": "SafariSkillsStatusCheck",
+            "statusPurpose": "revocation",
+        },
+        "proof": data_integrity_proof(credential),
+    });
+
+    if let Some(expiry_date) = &credential.expiry_date {
+        vc["validUntil"] = json!(format_date(expiry_date));
+    }
+
+    vc
+}
+
+/// The issuer's signature over the credential payload, expressed as a VC proof
+pub fn data_integrity_proof(credential: &Credential) -> Value {
+    json!({
+        "type": "DataIntegrityProof",
+        "cryptosuite": PROOF_CRYPTOSUITE,
+        "created": format_date(&credential.created_at),
+        "verificationMethod": credential.issuer_public_key,
+        "proofPurpose": "assertionMethod",
+        "proofValue": format!("0x{}", credential.issuer_signature),
+    })
+}
+
+/// Extract our credential id from a credential URL built by `credential_url`
+pub fn credential_id_from_url(url: &str) -> Option<String> {
+    let (_, credential_id) = url.rsplit_once("/api/credentials/verify/")?;
+    if credential_id.is_empty() || credential_id.contains('/') {
+        return None;
+    }
+    Some(credential_id.to_string())
+}
+
+/// Extract our credential id from a presented VC's `id` URL
+pub fn credential_id_from_vc(vc: &Value) -> Option<String> {
+    credential_id_from_url(vc.get("id")?.as_str()?)
+}
+
+/// Compare the claims of a presented VC with the VC we would issue from our records.
+/// Display-only fields such as names may change after issuance and are ignored.
+pub fn claims_match(presented: &Value, expected: &Value) -> bool {
+    const CLAIM_POINTERS: [&str; 10] = [
+        "/id",
+        "/type",
+        "/issuer/id",
+        "/validFrom",
+        "/validUntil",
+        "/credentialSubject/id",
+        "/credentialSubject/credential",
+        "/proof/cryptosuite",
+        "/proof/verificationMethod",
+        "/proof/proofValue",
+    ];
+
+    CLAIM_POINTERS
+        .iter()
+        .all(|pointer| presented.pointer(pointer) == expected.pointer(pointer))
+}
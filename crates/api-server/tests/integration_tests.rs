@@ -114,7 +114,7 @@ mod tests {
             "institution_name": "Test University",
             "institution_type": "University",
             "country": "Kenya",
-            "accreditation_number": "TU-ACC-2024"
+            "accreditation_number": "CUE/UNI/0422"
         });
         
         let institution_response = client
@@ -189,7 +189,11 @@ mod tests {
 // Unit tests for individual components
 #[cfg(test)]
 mod unit_tests {
-    use common::{UserRole, CredentialType, CredentialStatus};
+    use chrono::{Duration, TimeZone, Utc};
+    use common::{
+        credential_status_at, AccreditationStatus, ApiKeyScope, CredentialStatus, CredentialStatusEvent, CredentialType,
+        InstitutionPermission, InstitutionRole, OAuthScope, Permission, PublicCredential, SuspensionReason, UserRole,
+    };
     
     #[test]
     fn test_user_role_serialization() {
@@ -198,6 +202,83 @@ mod unit_tests {
         assert_eq!(serialized, "\"professional\"");
     }
     
+    #[test]
+    fn test_admin_role_serialization() {
+        let serialized = serde_json::to_string(&UserRole::Admin).unwrap();
+        assert_eq!(serialized, "\"admin\"");
+    }
+
+    #[test]
+    fn test_accreditation_status_serialization() {
+        let serialized = serde_json::to_string(&AccreditationStatus::Suspended).unwrap();
+        assert_eq!(serialized, "\"suspended\"");
+
+        let status: AccreditationStatus = serde_json::from_str("\"pending\"").unwrap();
+        assert_eq!(status, AccreditationStatus::Pending);
+    }
+
+    #[test]
+    fn test_institution_role_permissions() {
+        use InstitutionPermission::*;
+
+        assert!(InstitutionRole::Owner.allows(ManageMembers));
+        assert!(InstitutionRole::Owner.allows(ManageApiKeys));
+        assert!(InstitutionRole::Issuer.allows(IssueCredentials));
+        assert!(InstitutionRole::Issuer.allows(RevokeCredentials));
+        assert!(!InstitutionRole::Issuer.allows(ManageMembers));
+        assert!(!InstitutionRole::Issuer.allows(ManageApiKeys));
+        assert!(InstitutionRole::Reviewer.allows(SuspendCredentials));
+        assert!(!InstitutionRole::Reviewer.allows(IssueCredentials));
+        assert!(!InstitutionRole::Reviewer.allows(RevokeCredentials));
+        assert!(InstitutionRole::Auditor.allows(ViewCredentials));
+        assert!(InstitutionRole::Auditor.allows(ViewInstitution));
+        assert!(InstitutionRole::Reviewer.allows(ViewInstitution));
+        assert!(!InstitutionRole::Auditor.allows(SuspendCredentials));
+    }
+
+    #[test]
+    fn test_user_role_permissions() {
+        use Permission::*;
+
+        assert!(UserRole::Institution.allows(CredentialIssue));
+        assert!(UserRole::Institution.allows(CredentialSuspend));
+        assert!(!UserRole::Institution.allows(InstitutionAccredit));
+        assert!(UserRole::Admin.allows(InstitutionAccredit));
+        assert!(!UserRole::Admin.allows(CredentialIssue));
+        assert!(UserRole::Employer.allows(OAuthClientManage));
+        assert!(!UserRole::Employer.allows(CredentialReadIssued));
+        assert!(UserRole::Institution.allows(InstitutionRead));
+        assert!(!UserRole::Employer.allows(InstitutionRead));
+        assert!(!UserRole::Professional.allows(CredentialIssue));
+
+        // Staff need the matching platform permission as well as their institution role
+        assert_eq!(InstitutionPermission::SuspendCredentials.platform_permission(), CredentialSuspend);
+        assert_eq!(InstitutionPermission::ManageApiKeys.platform_permission(), InstitutionManage);
+        assert_eq!(InstitutionPermission::ViewInstitution.platform_permission(), InstitutionRead);
+    }
+
+    #[test]
+    fn test_permission_serialization() {
+        assert_eq!(serde_json::to_string(&Permission::CredentialIssue).unwrap(), "\"credential:issue\"");
+        let permission: Permission = serde_json::from_str("\"institution:accredit\"").unwrap();
+        assert_eq!(permission, Permission::InstitutionAccredit);
+    }
+
+    #[test]
+    fn test_api_key_scope_serialization() {
+        let scopes: Vec<ApiKeyScope> = serde_json::from_str(r#"["issue", "read_issued"]"#).unwrap();
+        assert_eq!(scopes, vec![ApiKeyScope::Issue, ApiKeyScope::ReadIssued]);
+        assert!(serde_json::from_str::<ApiKeyScope>("\"admin\"").is_err());
+    }
+
+    #[test]
+    fn test_oauth_scope_serialization() {
+        let scopes: Vec<OAuthScope> = serde_json::from_str(r#"["openid", "credentials:read"]"#).unwrap();
+        assert_eq!(scopes, vec![OAuthScope::OpenId, OAuthScope::CredentialsRead]);
+        assert_eq!(serde_json::to_string(&OAuthScope::Email).unwrap(), "\"email\"");
+        assert!(serde_json::from_str::<OAuthScope>("\"credentials_read\"").is_err());
+    }
+
     #[test]
     fn test_credential_type_serialization() {
         let cred_type = CredentialType::Certificate;
@@ -211,4 +292,107 @@ mod unit_tests {
         let serialized = serde_json::to_string(&status).unwrap();
         assert_eq!(serialized, "\"issued\"");
     }
+
+    #[test]
+    fn test_suspended_status_serialization() {
+        let serialized = serde_json::to_string(&CredentialStatus::Suspended).unwrap();
+        assert_eq!(serialized, "\"suspended\"");
+    }
+
+    #[test]
+    fn test_public_credential_omits_metadata() {
+        let credential = PublicCredential {
+            credential_id: "SSP-test".to_string(),
+            institution_id: uuid::Uuid::new_v4(),
+            credential_type: CredentialType::Degree,
+            title: "Bachelor of Science".to_string(),
+            description: "Computer Science".to_string(),
+            ipfs_hash: "sha256-abc".to_string(),
+            chain_hash: "abc".to_string(),
+            issuer_signature: "sig".to_string(),
+            issuer_public_key: "key".to_string(),
+            issue_date: Utc::now(),
+            expiry_date: None,
+            status: CredentialStatus::Issued,
+        };
+
+        let serialized = serde_json::to_value(&credential).unwrap();
+        assert!(serialized.get("metadata").is_none());
+        assert_eq!(serialized["title"], "Bachelor of Science");
+    }
+
+    #[test]
+    fn test_credential_status_transitions() {
+        assert!(CredentialStatus::Issued.can_transition_to(&CredentialStatus::Suspended));
+        assert!(CredentialStatus::Suspended.can_transition_to(&CredentialStatus::Issued));
+        assert!(CredentialStatus::Suspended.can_transition_to(&CredentialStatus::Revoked));
+        assert!(CredentialStatus::Expired.can_transition_to(&CredentialStatus::Revoked));
+
+        // Revocation is terminal
+        assert!(!CredentialStatus::Revoked.can_transition_to(&CredentialStatus::Issued));
+        assert!(!CredentialStatus::Revoked.can_transition_to(&CredentialStatus::Suspended));
+        assert!(!CredentialStatus::Revoked.can_transition_to(&CredentialStatus::Revoked));
+
+        // Expired credentials cannot be reinstated or suspended
+        assert!(!CredentialStatus::Expired.can_transition_to(&CredentialStatus::Issued));
+        assert!(!CredentialStatus::Expired.can_transition_to(&CredentialStatus::Suspended));
+        assert!(!CredentialStatus::Pending.can_transition_to(&CredentialStatus::Suspended));
+    }
+
+    #[test]
+    fn test_suspension_reason_deserialization() {
+        let reason: SuspensionReason = serde_json::from_str("\"under_investigation\"").unwrap();
+        assert_eq!(reason, SuspensionReason::UnderInvestigation);
+        assert!(serde_json::from_str::<SuspensionReason>("\"because\"").is_err());
+    }
+
+    #[test]
+    fn test_credential_status_at_point_in_time() {
+        let issue_date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let event = |from, to, days| CredentialStatusEvent {
+            from_status: from,
+            to_status: to,
+            reason: None,
+            note: None,
+            actor_id: None,
+            effective_at: issue_date + Duration::days(days),
+            recorded_at: issue_date + Duration::days(200),
+        };
+        let events = vec![
+            event(CredentialStatus::Issued, CredentialStatus::Suspended, 30),
+            event(CredentialStatus::Suspended, CredentialStatus::Issued, 60),
+            // Back-dated revocation recorded long after it took effect
+            event(CredentialStatus::Issued, CredentialStatus::Revoked, 90),
+        ];
+        let status_at = |days| credential_status_at(
+            &CredentialStatus::Revoked,
+            &issue_date,
+            None,
+            &events,
+            &(issue_date + Duration::days(days)),
+        );
+
+        assert_eq!(status_at(-1), CredentialStatus::Pending);
+        assert_eq!(status_at(10), CredentialStatus::Issued);
+        assert_eq!(status_at(45), CredentialStatus::Suspended);
+        assert_eq!(status_at(75), CredentialStatus::Issued);
+        assert_eq!(status_at(120), CredentialStatus::Revoked);
+    }
+
+    #[test]
+    fn test_credential_status_at_applies_expiry() {
+        let issue_date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let expiry_date = issue_date + Duration::days(365);
+
+        let status_at = |days| credential_status_at(
+            &CredentialStatus::Issued,
+            &issue_date,
+            Some(&expiry_date),
+            &[],
+            &(issue_date + Duration::days(days)),
+        );
+
+        assert_eq!(status_at(100), CredentialStatus::Issued);
+        assert_eq!(status_at(400), CredentialStatus::Expired);
+    }
 }
@@ -11,6 +11,7 @@ pub enum UserRole {
     Professional,
     Institution,
     Employer,
+    Admin,
 }
 
 /// User information (would be stored on-chain in production)
@@ -37,6 +38,7 @@ pub enum CredentialStatus {
     Issued,
     Revoked,
     Expired,
+    Suspended, // Appended to keep the SCALE encoding of existing variants
 }
 
 /// Credential information (would be stored on-chain in production)
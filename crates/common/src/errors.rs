@@ -1,7 +1,7 @@
 use thiserror::Error;
 use axum::{
     response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     Json,
 };
 use serde_json::json;
@@ -43,10 +43,18 @@ pub enum AppError {
 
     #[error("Institution not accredited")]
     InstitutionNotAccredited,
+
+    #[error("Too many requests: {0}")]
+    RateLimited(String, u64), // Message, seconds until the client may retry
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::RateLimited(_, secs) => Some(*secs),
+            _ => None,
+        };
+
         let (status, message) = match self {
             AppError::Database(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::Authentication(msg) => (StatusCode::UNAUTHORIZED, msg),
@@ -60,13 +68,18 @@ impl IntoResponse for AppError {
             AppError::UserExists => (StatusCode::CONFLICT, "User already exists".to_string()),
             AppError::InvalidCredential => (StatusCode::BAD_REQUEST, "Invalid credential".to_string()),
             AppError::InstitutionNotAccredited => (StatusCode::FORBIDDEN, "Institution not accredited".to_string()),
+            AppError::RateLimited(msg, _) => (StatusCode::TOO_MANY_REQUESTS, msg),
         };
 
         let body = Json(json!({
             "error": message,
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
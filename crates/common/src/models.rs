@@ -9,6 +9,16 @@ pub enum UserRole {
     Professional,
     Institution,
     Employer,
+    Admin, // Platform operator; granted out of band, never self-registered
+}
+
+/// How a user's wallet key is held
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletCustody {
+    Custodial, // Seed held encrypted by the platform keystore
+    External,  // Address supplied by the user, key held by the user
+    Unmanaged, // Legacy address whose seed was never stored
 }
 
 /// User account information
@@ -24,6 +34,16 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Where an institution stands in the platform's accreditation review
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccreditationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Suspended,
+}
+
 /// Institution information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Institution {
@@ -34,9 +54,143 @@ pub struct Institution {
     pub country: String,
     pub accreditation_number: Option<String>,
     pub is_accredited: bool,
+    pub accreditation_status: AccreditationStatus,
+    pub accreditation_authority_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
+/// A staff member's role within an institution
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstitutionRole {
+    Owner,
+    Issuer,
+    Reviewer,
+    Auditor,
+}
+
+/// Actions staff take on behalf of an institution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstitutionPermission {
+    IssueCredentials,
+    RevokeCredentials,
+    SuspendCredentials, // Suspend and reinstate
+    ViewCredentials,
+    ViewInstitution, // Institution details and staff
+    ManageMembers,
+    ManageApiKeys,
+}
+
+impl InstitutionRole {
+    /// Owners do everything, issuers manage credentials, reviewers may suspend
+    /// and reinstate, and auditors only read.
+    pub fn allows(&self, permission: InstitutionPermission) -> bool {
+        use InstitutionPermission::*;
+
+        match self {
+            InstitutionRole::Owner => true,
+            InstitutionRole::Issuer => !matches!(permission, ManageMembers | ManageApiKeys),
+            InstitutionRole::Reviewer => matches!(permission, SuspendCredentials | ViewCredentials | ViewInstitution),
+            InstitutionRole::Auditor => matches!(permission, ViewCredentials | ViewInstitution),
+        }
+    }
+}
+
+/// Platform-wide actions guarded by a user's role. Routes declare the one they
+/// require; staff additionally need an institution role that allows the action.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Permission {
+    #[serde(rename = "credential:issue")]
+    CredentialIssue,
+    #[serde(rename = "credential:revoke")]
+    CredentialRevoke,
+    #[serde(rename = "credential:suspend")]
+    CredentialSuspend, // Suspend and reinstate
+    #[serde(rename = "credential:read_issued")]
+    CredentialReadIssued,
+    #[serde(rename = "institution:read")]
+    InstitutionRead, // Institution details and staff
+    #[serde(rename = "institution:register")]
+    InstitutionRegister,
+    #[serde(rename = "institution:manage")]
+    InstitutionManage, // Staff and API keys
+    #[serde(rename = "institution:accredit")]
+    InstitutionAccredit,
+    #[serde(rename = "oauth_client:manage")]
+    OAuthClientManage,
+    #[serde(rename = "security:manage")]
+    SecurityManage, // Signing keys and account lockouts
+}
+
+impl UserRole {
+    /// Institutions issue and manage credentials, employers run partner portals
+    /// and admins accredit institutions and look after platform security.
+    /// Everything else is open to any signed-in user, subject to ownership.
+    pub fn allows(&self, permission: Permission) -> bool {
+        use Permission::*;
+
+        match self {
+            UserRole::Professional => false,
+            UserRole::Institution => matches!(
+                permission,
+                CredentialIssue | CredentialRevoke | CredentialSuspend | CredentialReadIssued
+                    | InstitutionRead | InstitutionRegister | InstitutionManage
+            ),
+            UserRole::Employer => permission == OAuthClientManage,
+            UserRole::Admin => matches!(permission, InstitutionAccredit | SecurityManage),
+        }
+    }
+}
+
+impl InstitutionPermission {
+    /// The platform permission a staff member's account role must also grant
+    pub fn platform_permission(self) -> Permission {
+        match self {
+            InstitutionPermission::IssueCredentials => Permission::CredentialIssue,
+            InstitutionPermission::RevokeCredentials => Permission::CredentialRevoke,
+            InstitutionPermission::SuspendCredentials => Permission::CredentialSuspend,
+            InstitutionPermission::ViewCredentials => Permission::CredentialReadIssued,
+            InstitutionPermission::ViewInstitution => Permission::InstitutionRead,
+            InstitutionPermission::ManageMembers | InstitutionPermission::ManageApiKeys => Permission::InstitutionManage,
+        }
+    }
+}
+
+/// What an institution API key may be used for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    Issue,
+    Revoke,
+    ReadIssued,
+    Verify,
+}
+
+/// What a partner portal may read about a user who signs in with their passport
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OAuthScope {
+    #[serde(rename = "openid")]
+    OpenId,
+    #[serde(rename = "profile")]
+    Profile,
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "credentials:read")]
+    CredentialsRead, // Only the credentials the user selects on the consent screen
+}
+
+/// A body that accredits institutions in one country
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccreditationAuthority {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub country: String,
+    pub country_code: String,
+    pub number_example: String, // A correctly formatted accreditation number
+    pub website: Option<String>,
+}
+
 /// Credential types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -54,22 +208,117 @@ pub enum CredentialType {
 pub enum CredentialStatus {
     Pending,
     Issued,
+    Suspended,
     Revoked,
     Expired,
 }
 
+impl CredentialStatus {
+    /// Whether the credential lifecycle allows moving from this status to `next`.
+    /// Revocation is terminal and expired credentials can only be revoked.
+    pub fn can_transition_to(&self, next: &CredentialStatus) -> bool {
+        use CredentialStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Issued) | (Pending, Revoked)
+                | (Issued, Suspended) | (Issued, Revoked) | (Issued, Expired)
+                | (Suspended, Issued) | (Suspended, Revoked) | (Suspended, Expired)
+                | (Expired, Revoked)
+        )
+    }
+}
+
+/// The status a credential had at time `at`, given its current status and its
+/// status change events in effective order. Changes before the first recorded
+/// event are unknown, so the first event's prior status is assumed until then.
+pub fn credential_status_at(
+    current: &CredentialStatus,
+    issue_date: &DateTime<Utc>,
+    expiry_date: Option<&DateTime<Utc>>,
+    events: &[CredentialStatusEvent],
+    at: &DateTime<Utc>,
+) -> CredentialStatus {
+    if at < issue_date {
+        return CredentialStatus::Pending;
+    }
+
+    let status = match events.iter().rev().find(|event| event.effective_at <= *at) {
+        Some(event) => event.to_status.clone(),
+        None => events.first().map_or_else(|| current.clone(), |event| event.from_status.clone()),
+    };
+
+    let expired = expiry_date.is_some_and(|expiry_date| expiry_date <= at);
+    match status {
+        CredentialStatus::Issued | CredentialStatus::Suspended if expired => CredentialStatus::Expired,
+        status => status,
+    }
+}
+
+/// Why an issuer revoked a credential
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    Misconduct,
+    IssuedInError,
+    Superseded,
+    HolderRequest,
+}
+
+/// Why an issuer suspended a credential
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspensionReason {
+    UnderInvestigation,
+    DisciplinaryAction,
+    ComplianceLapse,
+    HolderRequest,
+}
+
+/// Why an issuer reinstated a suspended credential
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReinstatementReason {
+    InvestigationCleared,
+    SanctionCompleted,
+    ComplianceRestored,
+    SuspendedInError,
+}
+
+/// Purpose of a bitstring status list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusPurpose {
+    Revocation,
+    Suspension,
+}
+
+/// What a secret sealed by the keystore is for. Sealed values are bound to
+/// their purpose, so one cannot be opened as another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SealPurpose {
+    WalletPhrase,
+    TotpSecret,
+    IssuerSeed,
+    DocumentWrappingKey,
+    TokenSigningKey,
+}
+
 /// Credential information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credential {
     pub id: Uuid,
     pub credential_id: String, // Unique on-chain identifier
     pub holder_id: Uuid,
-    pub issuer_id: Uuid,
+    pub issuer_id: Uuid, // Staff member who issued the credential
+    pub institution_id: Uuid,
     pub credential_type: CredentialType,
     pub title: String,
     pub description: String,
     pub ipfs_hash: String, // Hash of the credential document stored on IPFS
     pub chain_hash: String, // Hash recorded on blockchain
+    pub issuer_signature: String, // Issuer's sr25519 signature over the credential payload
+    pub issuer_public_key: String, // SS58 address of the issuer's signing key
     pub issue_date: DateTime<Utc>,
     pub expiry_date: Option<DateTime<Utc>>,
     pub status: CredentialStatus,
@@ -84,13 +333,61 @@ pub struct VerificationRequest {
     pub verifier_id: Option<Uuid>,
 }
 
+/// Credential details that are safe to show to anonymous verifiers. Leaves
+/// out `metadata`, which holders reveal selectively through SD-JWT presentations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicCredential {
+    pub credential_id: String,
+    pub institution_id: Uuid,
+    pub credential_type: CredentialType,
+    pub title: String,
+    pub description: String,
+    pub ipfs_hash: String,
+    pub chain_hash: String,
+    pub issuer_signature: String,
+    pub issuer_public_key: String,
+    pub issue_date: DateTime<Utc>,
+    pub expiry_date: Option<DateTime<Utc>>,
+    pub status: CredentialStatus,
+}
+
+/// Holder details that are safe to show to anonymous verifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialHolder {
+    pub name: String,
+    pub wallet_address: String,
+}
+
 /// Verification response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationResponse {
     pub valid: bool,
-    pub credential: Option<Credential>,
+    pub credential: Option<PublicCredential>,
     pub issuer: Option<Institution>,
-    pub holder: Option<User>,
+    pub accredited_by: Option<AccreditationAuthority>, // Only for issuers with approved accreditation
+    pub holder: Option<CredentialHolder>,
+    pub message: String,
+    pub checked_at: DateTime<Utc>, // Point in time the status was evaluated for
+}
+
+/// Result of checking a presented W3C Verifiable Credential against our records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VcVerificationResponse {
+    pub valid: bool,
+    pub credential_id: Option<String>,
+    pub proof_valid: bool,
+    pub claims_match: bool,
+    pub status: Option<CredentialStatus>,
+    pub message: String,
+}
+
+/// Result of checking an SD-JWT presentation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SdJwtVerificationResponse {
+    pub valid: bool,
+    pub issuer: Option<String>, // Issuing institution name
+    pub claims: serde_json::Value, // Visible and disclosed claims only
+    pub status: Option<CredentialStatus>,
     pub message: String,
 }
 
@@ -110,6 +407,7 @@ pub struct RegisterInstitutionRequest {
     pub institution_type: String,
     pub country: String,
     pub accreditation_number: Option<String>,
+    pub accreditation_authority: Option<String>, // Authority code; inferred from the number when omitted
 }
 
 #[derive(Debug, Deserialize)]
@@ -120,10 +418,102 @@ pub struct LoginRequest {
 
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
-    pub token: String,
+    pub token: String, // Short-lived access token
+    pub refresh_token: String, // Single use; exchange at /api/auth/refresh
+    pub expires_in: i64, // Access token lifetime in seconds
     pub user: User,
 }
 
+/// Returned by login instead of tokens when the account has two-factor authentication
+#[derive(Debug, Serialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub challenge_token: String, // Exchange with a code at /api/auth/login/mfa
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    MfaRequired(MfaChallengeResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaLoginRequest {
+    pub challenge_token: String,
+    pub code: String, // Current TOTP code or an unused recovery code
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpSetupResponse {
+    pub secret: String, // Base32, for manual entry
+    pub otpauth_uri: String,
+    pub qr_code: String, // Base64 encoded QR code of the otpauth URI
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>, // Shown once; each can replace a code a single time
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub required: bool, // Enforced for the account's role
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WalletChallengeRequest {
+    pub wallet_address: String, // SS58 address of the account's wallet
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletChallengeResponse {
+    pub message: String, // Sign this exact text with the wallet's sr25519 key
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WalletLoginRequest {
+    pub nonce: String,
+    pub signature: String, // Hex encoded sr25519 signature over the challenge message
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IssueCredentialRequest {
     pub holder_email: String,
@@ -141,6 +531,8 @@ pub struct IssueCredentialResponse {
     pub credential_id: String,
     pub ipfs_hash: String,
     pub chain_hash: String,
+    pub issuer_signature: String,
+    pub issuer_public_key: String,
     pub qr_code: String, // Base64 encoded QR code image
 }
 
@@ -149,3 +541,362 @@ pub struct CredentialListResponse {
     pub credentials: Vec<Credential>,
     pub total: usize,
 }
+
+#[derive(Debug, Serialize)]
+pub struct WalletResponse {
+    pub wallet_address: String,
+    pub custody: WalletCustody,
+    pub mnemonic_exported: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportMnemonicRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportMnemonicResponse {
+    pub wallet_address: String,
+    pub mnemonic: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkWalletRequest {
+    pub wallet_address: String, // SS58 address controlled by the user
+    pub signature: String, // Hex encoded sr25519 signature over the link message
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignMessageRequest {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignMessageResponse {
+    pub wallet_address: String,
+    pub signed_message: String, // The message with the platform prefix, as signed
+    pub signature: String, // Hex encoded sr25519 signature over signed_message
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SdJwtPresentationRequest {
+    pub claims: Option<Vec<String>>, // Dotted claim paths to reveal; all when omitted
+}
+
+#[derive(Debug, Serialize)]
+pub struct SdJwtPresentationResponse {
+    pub presentation: String,
+    pub disclosed_claims: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SdJwtVerifyRequest {
+    pub presentation: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportBadgeRequest {
+    pub badge: serde_json::Value, // OpenBadgeCredential JSON-LD document
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportBadgeResponse {
+    pub credential_id: String,
+    pub status: CredentialStatus,
+    pub institution_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RejectClaimRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeCredentialRequest {
+    pub reason: RevocationReason,
+    pub note: Option<String>,
+    pub effective_at: Option<DateTime<Utc>>, // May be back-dated; defaults to now
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendCredentialRequest {
+    pub reason: SuspensionReason,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReinstateCredentialRequest {
+    pub reason: ReinstatementReason,
+    pub note: Option<String>,
+}
+
+/// A recorded change of credential status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialStatusEvent {
+    pub from_status: CredentialStatus,
+    pub to_status: CredentialStatus,
+    pub reason: Option<String>,
+    pub note: Option<String>,
+    pub actor_id: Option<Uuid>, // None for system changes such as expiry
+    pub effective_at: DateTime<Utc>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialHistoryResponse {
+    pub credential_id: String,
+    pub status: CredentialStatus,
+    pub events: Vec<CredentialStatusEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccreditationDecisionRequest {
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstitutionRegistrant {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+}
+
+/// An institution as seen by a platform admin reviewing its accreditation
+#[derive(Debug, Serialize)]
+pub struct InstitutionReview {
+    pub institution: Institution,
+    pub accreditation_authority: Option<AccreditationAuthority>,
+    pub registered_by: Option<InstitutionRegistrant>,
+    pub review_notes: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub shares_accreditation_number_with: Vec<String>, // Names of other institutions using the same number
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstitutionReviewListResponse {
+    pub institutions: Vec<InstitutionReview>,
+    pub total: usize,
+}
+
+/// A key that signs or verifies access tokens
+#[derive(Debug, Serialize)]
+pub struct JwtSigningKey {
+    pub kid: String,
+    pub algorithm: String,
+    pub current: bool, // Signs new tokens; the others only verify
+    pub created_at: DateTime<Utc>,
+    pub rotated_out_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JwtSigningKeyListResponse {
+    pub keys: Vec<JwtSigningKey>,
+    pub total: usize,
+}
+
+/// Outcome of rotating the keys that wrap credential document keys
+#[derive(Debug, Serialize)]
+pub struct DocumentKeyRotationResponse {
+    pub rotated_keys: usize,
+    pub rewrapped_document_keys: usize,
+}
+
+/// A rejected password login, from the audit trail
+#[derive(Debug, Serialize)]
+pub struct FailedLoginAttempt {
+    pub id: Uuid,
+    pub email: String,
+    pub user_id: Option<Uuid>, // None when no account has the email
+    pub ip_address: String,
+    pub reason: String, // invalid_password, unknown_account or locked_out
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedLoginListResponse {
+    pub attempts: Vec<FailedLoginAttempt>,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccreditationAuthorityListResponse {
+    pub authorities: Vec<AccreditationAuthority>,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstitutionMember {
+    pub user_id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub role: InstitutionRole,
+    pub added_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstitutionMemberListResponse {
+    pub members: Vec<InstitutionMember>,
+    pub total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddInstitutionMemberRequest {
+    pub email: String, // An existing institution account
+    pub role: InstitutionRole,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateInstitutionMemberRequest {
+    pub role: InstitutionRole,
+}
+
+/// An institution API key, without its secret
+#[derive(Debug, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String, // Start of the key, to tell keys apart
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyListResponse {
+    pub api_keys: Vec<ApiKey>,
+    pub total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_in_days: Option<i64>, // Defaults to a year
+}
+
+/// A new or rotated key. The secret `key` is shown only in this response.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+/// An OAuth client registered by an employer portal, without its secret
+#[derive(Debug, Serialize)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub confidential: bool, // Whether the client authenticates with a secret
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthClientListResponse {
+    pub clients: Vec<OAuthClient>,
+    pub total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterOAuthClientRequest {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub confidential: Option<bool>, // Defaults to true; single-page and mobile apps rely on PKCE alone
+}
+
+/// A new client. The `client_secret` of a confidential client is shown only in this response.
+#[derive(Debug, Serialize)]
+pub struct RegisteredOAuthClientResponse {
+    pub client_secret: Option<String>,
+    pub client: OAuthClient,
+}
+
+/// The parameters of an OpenID Connect authentication request, as the
+/// client put them on the authorization URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String, // Space-separated
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentScope {
+    pub scope: OAuthScope,
+    pub description: String,
+}
+
+/// What the consent screen shows the user before they approve a client
+#[derive(Debug, Serialize)]
+pub struct OAuthConsentResponse {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<ConsentScope>,
+    pub credentials: Vec<Credential>, // The user's credentials to choose from, when credentials:read is requested
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorizationDecisionRequest {
+    #[serde(flatten)]
+    pub request: AuthorizationRequest,
+    pub approve: bool,
+    #[serde(default)]
+    pub credential_ids: Vec<String>, // Credentials to share under credentials:read
+}
+
+/// Where to send the browser back to the client, carrying the code or an error
+#[derive(Debug, Serialize)]
+pub struct AuthorizationRedirectResponse {
+    pub redirect_to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// A client a user has signed in to, and what they agreed to share
+#[derive(Debug, Serialize)]
+pub struct OAuthGrant {
+    pub id: Uuid,
+    pub client_id: String,
+    pub client_name: String,
+    pub scopes: Vec<OAuthScope>,
+    pub credential_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthGrantListResponse {
+    pub grants: Vec<OAuthGrant>,
+    pub total: usize,
+}
+
+/// Standard OpenID Connect claims about the user, limited to the granted scopes
+#[derive(Debug, Serialize)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
@@ -0,0 +1,15 @@
+-- Institution signing keys (sr25519) used to sign issued credentials
+CREATE TABLE IF NOT EXISTS issuer_keys (
+    id UUID PRIMARY KEY,
+    institution_id UUID NOT NULL REFERENCES institutions(id) ON DELETE CASCADE,
+    public_key VARCHAR(255) UNIQUE NOT NULL,
+    secret_seed VARCHAR(255) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_issuer_keys_institution_id ON issuer_keys(institution_id);
+
+-- Issuer signature over the canonical credential payload
+ALTER TABLE credentials ADD COLUMN issuer_signature TEXT NOT NULL DEFAULT '';
+ALTER TABLE credentials ADD COLUMN issuer_public_key VARCHAR(255) NOT NULL DEFAULT '';
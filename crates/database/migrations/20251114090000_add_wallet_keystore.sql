@@ -0,0 +1,19 @@
+-- How a user's wallet address is controlled:
+--   custodial: seed held encrypted in wallet_keys
+--   external:  user-supplied address, key held by the user
+--   unmanaged: legacy address whose seed was never stored
+ALTER TABLE users ADD COLUMN wallet_custody VARCHAR(20) NOT NULL DEFAULT 'unmanaged'
+    CHECK (wallet_custody IN ('custodial', 'external', 'unmanaged'));
+
+-- Custodial wallet seeds, encrypted with the server master key
+CREATE TABLE IF NOT EXISTS wallet_keys (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    wallet_address VARCHAR(255) UNIQUE NOT NULL,
+    encrypted_phrase TEXT NOT NULL,
+    mnemonic_exported_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Issuer signing seeds are sealed by the keystore on startup
+ALTER TABLE issuer_keys ADD COLUMN encrypted_seed TEXT NOT NULL DEFAULT '';
+ALTER TABLE issuer_keys ALTER COLUMN secret_seed DROP NOT NULL;
@@ -0,0 +1,19 @@
+-- Bitstring status lists published per issuing institution.
+-- Each credential gets one index; the same index is used in both bitstrings.
+CREATE TABLE IF NOT EXISTS status_lists (
+    id UUID PRIMARY KEY,
+    institution_id UUID NOT NULL REFERENCES institutions(id) ON DELETE CASCADE,
+    list_number INTEGER NOT NULL,
+    revocation_bits BYTEA NOT NULL,
+    suspension_bits BYTEA NOT NULL,
+    next_index INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (institution_id, list_number)
+);
+
+ALTER TABLE credentials ADD COLUMN status_list_id UUID REFERENCES status_lists(id);
+ALTER TABLE credentials ADD COLUMN status_list_index INTEGER;
+
+CREATE UNIQUE INDEX idx_credentials_status_list_entry
+    ON credentials(status_list_id, status_list_index)
+    WHERE status_list_id IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Allow credentials to be suspended and record why a status last changed
+ALTER TABLE credentials DROP CONSTRAINT IF EXISTS credentials_status_check;
+ALTER TABLE credentials ADD CONSTRAINT credentials_status_check
+    CHECK (status IN ('pending', 'issued', 'suspended', 'revoked', 'expired'));
+
+ALTER TABLE credentials ADD COLUMN status_reason VARCHAR(50);
@@ -0,0 +1,17 @@
+-- Audit trail of credential status changes.
+-- effective_at may be back-dated (e.g. a revocation for misconduct that
+-- occurred earlier); recorded_at is when the change was made.
+CREATE TABLE IF NOT EXISTS credential_status_events (
+    id UUID PRIMARY KEY,
+    credential_id UUID NOT NULL REFERENCES credentials(id) ON DELETE CASCADE,
+    from_status VARCHAR(50) NOT NULL,
+    to_status VARCHAR(50) NOT NULL,
+    reason VARCHAR(50),
+    note TEXT,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    effective_at TIMESTAMPTZ NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_credential_status_events_credential
+    ON credential_status_events(credential_id, effective_at);
@@ -0,0 +1,9 @@
+-- Expiry reminders already sent, one per credential and lead time
+CREATE TABLE IF NOT EXISTS credential_expiry_notices (
+    credential_id UUID NOT NULL REFERENCES credentials(id) ON DELETE CASCADE,
+    lead_days INTEGER NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (credential_id, lead_days)
+);
+
+CREATE INDEX idx_credentials_expiry_date ON credentials(expiry_date) WHERE expiry_date IS NOT NULL;
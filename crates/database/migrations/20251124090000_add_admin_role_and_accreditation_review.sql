@@ -0,0 +1,16 @@
+-- Platform administrators
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check
+    CHECK (role IN ('professional', 'institution', 'employer', 'admin'));
+
+-- Accreditation review; is_accredited is kept in step for existing readers
+ALTER TABLE institutions ADD COLUMN accreditation_status VARCHAR(20) NOT NULL DEFAULT 'pending'
+    CHECK (accreditation_status IN ('pending', 'approved', 'rejected', 'suspended'));
+ALTER TABLE institutions ADD COLUMN review_notes TEXT;
+ALTER TABLE institutions ADD COLUMN reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE institutions ADD COLUMN reviewed_at TIMESTAMPTZ;
+
+UPDATE institutions SET accreditation_status = 'approved' WHERE is_accredited;
+
+CREATE INDEX idx_institutions_accreditation_status ON institutions(accreditation_status);
+CREATE INDEX idx_institutions_accreditation_number ON institutions(accreditation_number);
@@ -0,0 +1,29 @@
+-- Bodies that accredit institutions, with the format of the numbers they issue
+CREATE TABLE IF NOT EXISTS accreditation_authorities (
+    id UUID PRIMARY KEY,
+    code VARCHAR(20) UNIQUE NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    country VARCHAR(100) NOT NULL,
+    country_code CHAR(2) NOT NULL, -- ISO 3166-1 alpha-2
+    number_pattern TEXT NOT NULL, -- POSIX regex matched against the upper-cased number
+    number_example VARCHAR(100) NOT NULL,
+    website VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_accreditation_authorities_country_code ON accreditation_authorities(country_code);
+
+INSERT INTO accreditation_authorities (id, code, name, country, country_code, number_pattern, number_example, website)
+VALUES
+    (gen_random_uuid(), 'CUE', 'Commission for University Education', 'Kenya', 'KE',
+        '^CUE/(UNI|ACC|REG)/[0-9]{3,5}$', 'CUE/UNI/0421', 'https://www.cue.or.ke'),
+    (gen_random_uuid(), 'NCHE', 'National Council for Higher Education', 'Uganda', 'UG',
+        '^NCHE/[0-9]{4}/[0-9]{3,5}$', 'NCHE/2019/0087', 'https://unche.or.ug'),
+    (gen_random_uuid(), 'TCU', 'Tanzania Commission for Universities', 'Tanzania', 'TZ',
+        '^TCU/[A-Z]{2,3}/[0-9]{2,4}$', 'TCU/UN/045', 'https://www.tcu.go.tz'),
+    (gen_random_uuid(), 'HEC', 'Higher Education Council', 'Rwanda', 'RW',
+        '^HEC/[0-9]{2,4}/[0-9]{4}$', 'HEC/112/2021', 'https://hec.gov.rw')
+ON CONFLICT (code) DO NOTHING;
+
+ALTER TABLE institutions ADD COLUMN accreditation_authority_id UUID
+    REFERENCES accreditation_authorities(id) ON DELETE SET NULL;
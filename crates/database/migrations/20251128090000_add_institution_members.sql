@@ -0,0 +1,26 @@
+-- Staff accounts acting for an institution
+CREATE TABLE IF NOT EXISTS institution_members (
+    institution_id UUID NOT NULL REFERENCES institutions(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('owner', 'issuer', 'reviewer', 'auditor')),
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (institution_id, user_id),
+    UNIQUE (user_id) -- A staff account acts for a single institution
+);
+
+-- The registering account owns each existing institution
+INSERT INTO institution_members (institution_id, user_id, role, created_at)
+SELECT id, user_id, 'owner', created_at FROM institutions
+ON CONFLICT DO NOTHING;
+
+-- Credentials record the institution as well as the staff member who issued them
+ALTER TABLE credentials ADD COLUMN institution_id UUID REFERENCES institutions(id) ON DELETE CASCADE;
+
+UPDATE credentials c SET institution_id = i.id
+FROM institutions i
+WHERE i.user_id = c.issuer_id;
+
+ALTER TABLE credentials ALTER COLUMN institution_id SET NOT NULL;
+
+CREATE INDEX idx_credentials_institution_id ON credentials(institution_id);
@@ -0,0 +1,12 @@
+-- Asymmetric keys that sign access tokens, published at /.well-known/jwks.json
+CREATE TABLE IF NOT EXISTS jwt_signing_keys (
+    id UUID PRIMARY KEY, -- Published as the token's `kid`
+    algorithm VARCHAR(10) NOT NULL CHECK (algorithm IN ('EdDSA', 'ES256')),
+    public_jwk JSONB NOT NULL,
+    encrypted_private_key TEXT NOT NULL, -- PKCS#8 private key sealed by the keystore
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rotated_out_at TIMESTAMPTZ -- Stops signing here; still verifies until issued tokens expire
+);
+
+-- Exactly one key signs new tokens at a time
+CREATE UNIQUE INDEX idx_jwt_signing_keys_current ON jwt_signing_keys ((TRUE)) WHERE rotated_out_at IS NULL;
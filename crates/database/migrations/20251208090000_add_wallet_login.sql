@@ -0,0 +1,14 @@
+-- Sign-in with a wallet: single-use messages for the user's sr25519 key to sign
+CREATE TABLE IF NOT EXISTS wallet_login_challenges (
+    id UUID PRIMARY KEY,
+    wallet_address VARCHAR(64) NOT NULL,
+    nonce VARCHAR(64) UNIQUE NOT NULL,
+    message TEXT NOT NULL, -- Exact text the wallet signs
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+-- How the first factor was presented: password or wallet
+ALTER TABLE auth_sessions ADD COLUMN login_method VARCHAR(20) NOT NULL DEFAULT 'password';
+ALTER TABLE mfa_challenges ADD COLUMN login_method VARCHAR(20) NOT NULL DEFAULT 'password';
@@ -0,0 +1,13 @@
+-- Audit trail of rejected password logins. Attempt counters for backoff and
+-- lockout are kept by the API server, not here.
+CREATE TABLE IF NOT EXISTS failed_logins (
+    id UUID PRIMARY KEY,
+    email VARCHAR(255) NOT NULL, -- As submitted, lowercased
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL, -- None for unknown accounts
+    ip_address VARCHAR(45) NOT NULL,
+    reason VARCHAR(30) NOT NULL, -- invalid_password, unknown_account or locked_out
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_failed_logins_email ON failed_logins(email, created_at DESC);
+CREATE INDEX idx_failed_logins_created_at ON failed_logins(created_at DESC);
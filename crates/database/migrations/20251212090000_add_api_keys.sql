@@ -0,0 +1,18 @@
+-- API keys that let an institution's own systems call the API without a user session.
+-- Keys are looked up by their public prefix and stored only as a SHA-256 hash.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    institution_id UUID NOT NULL REFERENCES institutions(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    prefix VARCHAR(32) UNIQUE NOT NULL,
+    key_hash VARCHAR(64) NOT NULL,
+    scopes TEXT[] NOT NULL, -- issue, revoke, read_issued, verify
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- Credentials issued with the key name this user as issuer
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    replaced_by UUID REFERENCES api_keys(id) ON DELETE SET NULL -- Set when the key is rotated
+);
+
+CREATE INDEX idx_api_keys_institution_id ON api_keys(institution_id, created_at DESC);
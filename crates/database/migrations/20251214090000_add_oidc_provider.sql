@@ -0,0 +1,50 @@
+-- OAuth 2.0 / OpenID Connect provider, so partner portals can offer
+-- "Sign in with Safari Skills Passport". Secrets, codes and tokens are stored
+-- only as SHA-256 hashes.
+CREATE TABLE IF NOT EXISTS oauth_clients (
+    id UUID PRIMARY KEY,
+    client_id VARCHAR(64) UNIQUE NOT NULL,
+    client_secret_hash VARCHAR(64), -- NULL for public clients, which rely on PKCE alone
+    name VARCHAR(100) NOT NULL,
+    redirect_uris TEXT[] NOT NULL, -- Matched exactly
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_oauth_clients_owner_id ON oauth_clients(owner_id, created_at DESC);
+
+-- A user's consent to a client, with the credentials they chose to share
+CREATE TABLE IF NOT EXISTS oauth_grants (
+    id UUID PRIMARY KEY,
+    client_id UUID NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    scopes TEXT[] NOT NULL, -- openid, profile, email, credentials:read
+    credential_ids TEXT[] NOT NULL DEFAULT '{}', -- Readable under credentials:read
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_oauth_grants_user_id ON oauth_grants(user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS oauth_authorization_codes (
+    id UUID PRIMARY KEY,
+    grant_id UUID NOT NULL REFERENCES oauth_grants(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) UNIQUE NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    code_challenge VARCHAR(128) NOT NULL, -- PKCE, S256 only
+    nonce TEXT, -- Echoed in the ID token
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS oauth_access_tokens (
+    id UUID PRIMARY KEY,
+    grant_id UUID NOT NULL REFERENCES oauth_grants(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_oauth_access_tokens_grant_id ON oauth_access_tokens(grant_id);
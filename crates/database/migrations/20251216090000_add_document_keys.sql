@@ -0,0 +1,25 @@
+-- Keys that wrap credential document keys: one current key per holder and per issuing institution
+CREATE TABLE IF NOT EXISTS document_wrapping_keys (
+    id UUID PRIMARY KEY,
+    owner_type VARCHAR(20) NOT NULL CHECK (owner_type IN ('holder', 'issuer')),
+    owner_id UUID NOT NULL, -- users.id for holders, institutions.id for issuers
+    encrypted_key TEXT NOT NULL, -- AES-256 key sealed by the keystore
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rotated_out_at TIMESTAMPTZ -- Its document keys have been rewrapped under a newer key
+);
+
+CREATE UNIQUE INDEX idx_document_wrapping_keys_current
+    ON document_wrapping_keys(owner_type, owner_id) WHERE rotated_out_at IS NULL;
+
+-- Each credential document is encrypted under its own data key, stored here
+-- wrapped once for the holder and once for the issuer. Credentials without
+-- rows predate encryption and have plaintext documents.
+CREATE TABLE IF NOT EXISTS credential_document_keys (
+    credential_id UUID NOT NULL REFERENCES credentials(id) ON DELETE CASCADE,
+    wrapping_key_id UUID NOT NULL REFERENCES document_wrapping_keys(id),
+    wrapped_key TEXT NOT NULL, -- Hex encoded nonce || AES-256-GCM ciphertext
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (credential_id, wrapping_key_id)
+);
+
+CREATE INDEX idx_credential_document_keys_wrapping_key ON credential_document_keys(wrapping_key_id);
@@ -0,0 +1,7 @@
+-- Whether a credential's stored document is encrypted, rather than inferring
+-- it from the presence of document keys. Documents stored before encryption
+-- stay plaintext.
+ALTER TABLE credentials ADD COLUMN IF NOT EXISTS document_encrypted BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE credentials SET document_encrypted = TRUE
+WHERE id IN (SELECT credential_id FROM credential_document_keys);
@@ -0,0 +1,16 @@
+-- Issuer seeds are only ever stored sealed. Keys whose seed was never sealed
+-- are retired instead; credentials they signed still verify by public key.
+UPDATE issuer_keys SET is_active = FALSE WHERE encrypted_seed = '';
+ALTER TABLE issuer_keys DROP COLUMN secret_seed;
+
+-- One active signing key per institution. Where concurrent first issuances
+-- created several, the newest stays active as it is the one in use.
+UPDATE issuer_keys k SET is_active = FALSE
+WHERE k.is_active AND EXISTS (
+    SELECT 1 FROM issuer_keys newer
+    WHERE newer.institution_id = k.institution_id
+      AND newer.is_active
+      AND (newer.created_at, newer.id) > (k.created_at, k.id)
+);
+
+CREATE UNIQUE INDEX idx_issuer_keys_active_institution ON issuer_keys(institution_id) WHERE is_active;
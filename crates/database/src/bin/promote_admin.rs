@@ -0,0 +1,34 @@
+use sqlx::postgres::PgPoolOptions;
+
+/// Grant the platform admin role to an existing user
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+
+    let email = std::env::args()
+        .nth(1)
+        .expect("Usage: promote-admin <email>");
+
+    let database_url = std::env::var("DATABASE_URL")
+        .expect("DATABASE_URL must be set");
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+
+    let result = sqlx::query("UPDATE users SET role = 'admin', updated_at = NOW() WHERE LOWER(email) = LOWER($1)")
+        .bind(&email)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        eprintln!("❌ No user found with email {}", email);
+        std::process::exit(1);
+    }
+
+    println!("✅ {} is now a platform admin", email);
+    println!("They must log in again for the new role to take effect.");
+
+    Ok(())
+}
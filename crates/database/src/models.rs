@@ -10,6 +10,7 @@ pub struct UserDb {
     pub name: String,
     pub role: String,
     pub is_verified: bool,
+    pub wallet_custody: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -24,6 +25,33 @@ pub struct InstitutionDb {
     pub accreditation_number: Option<String>,
     pub is_accredited: bool,
     pub created_at: DateTime<Utc>,
+    pub accreditation_status: String, // pending, approved, rejected or suspended
+    pub review_notes: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub accreditation_authority_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct InstitutionMemberDb {
+    pub institution_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String, // owner, issuer, reviewer or auditor
+    pub added_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AccreditationAuthorityDb {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub country: String,
+    pub country_code: String,
+    pub number_pattern: String,
+    pub number_example: String,
+    pub website: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -38,9 +66,234 @@ pub struct CredentialDb {
     pub ipfs_hash: String,
     pub chain_hash: String,
     pub qr_code: String, // Base64 encoded QR code image
+    pub issuer_signature: String, // Hex encoded sr25519 signature
+    pub issuer_public_key: String, // SS58 address of the signing key
     pub issue_date: DateTime<Utc>,
     pub expiry_date: Option<DateTime<Utc>>,
     pub status: String,
     pub metadata: sqlx::types::JsonValue,
     pub created_at: DateTime<Utc>,
+    pub status_list_id: Option<Uuid>,
+    pub status_list_index: Option<i32>,
+    pub status_reason: Option<String>, // Reason code for the latest status change
+    pub institution_id: Uuid, // issuer_id is the staff member who issued it
+    pub document_encrypted: bool, // False for documents stored before encryption
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IssuerKeyDb {
+    pub id: Uuid,
+    pub institution_id: Uuid,
+    pub public_key: String, // SS58 encoded sr25519 public key
+    pub encrypted_seed: String, // sr25519 seed sealed by the keystore
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WalletKeyDb {
+    pub user_id: Uuid,
+    pub wallet_address: String,
+    pub encrypted_phrase: String, // Mnemonic sealed by the keystore
+    pub mnemonic_exported_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Entries per status list; the spec's minimum size for herd privacy (16KB)
+pub const STATUS_LIST_SIZE: i32 = 131_072;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StatusListDb {
+    pub id: Uuid,
+    pub institution_id: Uuid,
+    pub list_number: i32,
+    pub revocation_bits: Vec<u8>, // Index 0 is the most significant bit of the first byte
+    pub suspension_bits: Vec<u8>,
+    pub next_index: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CredentialStatusEventDb {
+    pub id: Uuid,
+    pub credential_id: Uuid,
+    pub from_status: String,
+    pub to_status: String,
+    pub reason: Option<String>,
+    pub note: Option<String>,
+    pub actor_id: Option<Uuid>, // None for system changes
+    pub effective_at: DateTime<Utc>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AuthSessionDb {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_refreshed_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>,
+    pub mfa_verified_at: Option<DateTime<Utc>>,
+    pub login_method: String, // password or wallet
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RefreshTokenDb {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub token_hash: String, // Hex SHA-256 of the token
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>, // Set once the token has been exchanged
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct JwtSigningKeyDb {
+    pub id: Uuid,
+    pub algorithm: String, // EdDSA or ES256
+    pub public_jwk: sqlx::types::JsonValue,
+    pub encrypted_private_key: String,
+    pub created_at: DateTime<Utc>,
+    pub rotated_out_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailTokenDb {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: String, // verify_email or reset_password
+    pub token_hash: String, // Hex SHA-256 of the mailed token
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserTotpDb {
+    pub user_id: Uuid,
+    pub encrypted_secret: String, // Sealed by the keystore
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub enabled_at: Option<DateTime<Utc>>, // None while enrollment is pending
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MfaChallengeDb {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub login_method: String, // First factor that started the login
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FailedLoginDb {
+    pub id: Uuid,
+    pub email: String,
+    pub user_id: Option<Uuid>, // None when no account has the email
+    pub ip_address: String,
+    pub reason: String, // invalid_password, unknown_account or locked_out
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WalletLoginChallengeDb {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub nonce: String,
+    pub message: String, // Exact text the wallet signs
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyDb {
+    pub id: Uuid,
+    pub institution_id: Uuid,
+    pub name: String,
+    pub prefix: String, // Public part of the key, used to look it up
+    pub key_hash: String, // Hex SHA-256 of the full key
+    pub scopes: Vec<String>, // issue, revoke, read_issued or verify
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<Uuid>, // The key that replaced this one on rotation
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OAuthClientDb {
+    pub id: Uuid,
+    pub client_id: String, // Public identifier the client presents
+    pub client_secret_hash: Option<String>, // None for public clients
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub owner_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OAuthGrantDb {
+    pub id: Uuid,
+    pub client_id: Uuid, // oauth_clients.id
+    pub user_id: Uuid,
+    pub scopes: Vec<String>,
+    pub credential_ids: Vec<String>, // Credentials the user chose to share
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OAuthAuthorizationCodeDb {
+    pub id: Uuid,
+    pub grant_id: Uuid,
+    pub code_hash: String,
+    pub redirect_uri: String,
+    pub code_challenge: String,
+    pub nonce: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OAuthAccessTokenDb {
+    pub id: Uuid,
+    pub grant_id: Uuid,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DocumentWrappingKeyDb {
+    pub id: Uuid,
+    pub owner_type: String, // holder or issuer
+    pub owner_id: Uuid, // users.id for holders, institutions.id for issuers
+    pub encrypted_key: String, // AES-256 key sealed by the keystore
+    pub created_at: DateTime<Utc>,
+    pub rotated_out_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CredentialDocumentKeyDb {
+    pub credential_id: Uuid, // credentials.id
+    pub wrapping_key_id: Uuid,
+    pub wrapped_key: String, // The document's data key wrapped by the wrapping key
+    pub created_at: DateTime<Utc>,
+}
+
+/// A keystore-sealed value and the ids it was sealed under
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SealedSecretDb {
+    pub id: Uuid, // Primary key of the row holding it
+    pub owner_id: Uuid, // The id the value is bound to
+    pub sealed: String,
 }
@@ -1,16 +1,19 @@
 use sqlx::PgPool;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use async_trait::async_trait;
-use common::{AppError, AppResult};
+use common::{AppError, AppResult, SealPurpose, StatusPurpose};
 use crate::models::*;
 
 #[async_trait]
 pub trait UserRepository: Send + Sync {
-    async fn create_user(&self, user: &UserDb) -> AppResult<UserDb>;
+    async fn create_user_with_wallet_key(&self, user: &UserDb, wallet_key: &WalletKeyDb) -> AppResult<UserDb>;
     async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<UserDb>>;
     async fn get_user_by_email(&self, email: &str) -> AppResult<Option<UserDb>>;
     async fn get_user_by_wallet(&self, wallet_address: &str) -> AppResult<Option<UserDb>>;
     async fn update_user_verification(&self, id: Uuid, verified: bool) -> AppResult<()>;
+    async fn update_wallet(&self, id: Uuid, wallet_address: &str, custody: &str) -> AppResult<()>;
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> AppResult<()>;
 }
 
 #[async_trait]
@@ -18,17 +21,185 @@ pub trait InstitutionRepository: Send + Sync {
     async fn create_institution(&self, institution: &InstitutionDb) -> AppResult<InstitutionDb>;
     async fn get_institution_by_user_id(&self, user_id: Uuid) -> AppResult<Option<InstitutionDb>>;
     async fn get_institution_by_id(&self, id: Uuid) -> AppResult<Option<InstitutionDb>>;
-    async fn update_accreditation(&self, id: Uuid, accredited: bool) -> AppResult<()>;
+    async fn get_institution_by_name(&self, institution_name: &str) -> AppResult<Option<InstitutionDb>>;
+    async fn get_institutions_by_accreditation_status(&self, status: &str) -> AppResult<Vec<InstitutionDb>>;
+    async fn get_institutions_by_accreditation_number(&self, accreditation_number: &str) -> AppResult<Vec<InstitutionDb>>;
+    async fn review_accreditation(
+        &self,
+        id: Uuid,
+        from: &[&str],
+        to: &str,
+        notes: Option<&str>,
+        reviewer_id: Uuid,
+    ) -> AppResult<Option<InstitutionDb>>;
+}
+
+#[async_trait]
+pub trait InstitutionMemberRepository: Send + Sync {
+    async fn add_member(&self, member: &InstitutionMemberDb) -> AppResult<Option<InstitutionMemberDb>>;
+    async fn get_membership(&self, user_id: Uuid) -> AppResult<Option<InstitutionMemberDb>>;
+    async fn get_members(&self, institution_id: Uuid) -> AppResult<Vec<InstitutionMemberDb>>;
+    async fn update_member_role(&self, institution_id: Uuid, user_id: Uuid, role: &str) -> AppResult<Option<InstitutionMemberDb>>;
+    async fn remove_member(&self, institution_id: Uuid, user_id: Uuid) -> AppResult<bool>;
+}
+
+#[async_trait]
+pub trait AccreditationAuthorityRepository: Send + Sync {
+    async fn list_authorities(&self, country: Option<&str>) -> AppResult<Vec<AccreditationAuthorityDb>>;
+    async fn get_authority_by_id(&self, id: Uuid) -> AppResult<Option<AccreditationAuthorityDb>>;
+    async fn get_authority_by_code(&self, code: &str) -> AppResult<Option<AccreditationAuthorityDb>>;
+    async fn matches_number_format(&self, id: Uuid, accreditation_number: &str) -> AppResult<bool>;
 }
 
 #[async_trait]
 pub trait CredentialRepository: Send + Sync {
-    async fn create_credential(&self, credential: &CredentialDb) -> AppResult<CredentialDb>;
+    async fn create_credential_with_document_keys(
+        &self,
+        credential: &CredentialDb,
+        document_keys: &[CredentialDocumentKeyDb],
+    ) -> AppResult<CredentialDb>;
+    async fn approve_pending_credential(&self, credential: &CredentialDb, event: &CredentialStatusEventDb) -> AppResult<bool>;
     async fn get_credential_by_id(&self, id: Uuid) -> AppResult<Option<CredentialDb>>;
     async fn get_credential_by_credential_id(&self, credential_id: &str) -> AppResult<Option<CredentialDb>>;
     async fn get_credentials_by_holder(&self, holder_id: Uuid) -> AppResult<Vec<CredentialDb>>;
-    async fn get_credentials_by_issuer(&self, issuer_id: Uuid) -> AppResult<Vec<CredentialDb>>;
-    async fn update_credential_status(&self, id: Uuid, status: &str) -> AppResult<()>;
+    async fn get_credentials_by_institution(&self, institution_id: Uuid) -> AppResult<Vec<CredentialDb>>;
+    async fn allocate_status_list_index(&self, institution_id: Uuid) -> AppResult<(Uuid, i32)>;
+    async fn get_status_list(&self, id: Uuid) -> AppResult<Option<StatusListDb>>;
+    async fn get_expired_credentials(&self, now: DateTime<Utc>, skip: &[Uuid], limit: i64) -> AppResult<Vec<CredentialDb>>;
+    async fn get_credentials_expiring_before(&self, now: DateTime<Utc>, until: DateTime<Utc>) -> AppResult<Vec<CredentialDb>>;
+    async fn get_expiry_notices(&self, credential_id: Uuid) -> AppResult<Vec<i32>>;
+    async fn record_expiry_notice(&self, credential_id: Uuid, lead_days: i32) -> AppResult<bool>;
+}
+
+#[async_trait]
+pub trait CredentialStatusEventRepository: Send + Sync {
+    async fn record_status_change(
+        &self,
+        event: &CredentialStatusEventDb,
+        status_bit: Option<(StatusPurpose, bool)>,
+    ) -> AppResult<bool>;
+    async fn get_status_events(&self, credential_id: Uuid) -> AppResult<Vec<CredentialStatusEventDb>>;
+}
+
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create_session(&self, session: &AuthSessionDb, refresh_token: &RefreshTokenDb) -> AppResult<()>;
+    async fn get_session(&self, id: Uuid) -> AppResult<Option<AuthSessionDb>>;
+    async fn get_refresh_token(&self, token_hash: &str) -> AppResult<Option<RefreshTokenDb>>;
+    async fn rotate_refresh_token(&self, used_id: Uuid, replacement: &RefreshTokenDb) -> AppResult<bool>;
+    async fn revoke_session(&self, id: Uuid, reason: &str) -> AppResult<bool>;
+    async fn revoke_user_sessions(&self, user_id: Uuid, reason: &str) -> AppResult<u64>;
+    async fn revoke_other_sessions(&self, user_id: Uuid, keep_session_id: Uuid, reason: &str) -> AppResult<u64>;
+    async fn mark_session_mfa_verified(&self, id: Uuid) -> AppResult<()>;
+}
+
+#[async_trait]
+pub trait MfaRepository: Send + Sync {
+    async fn get_totp(&self, user_id: Uuid) -> AppResult<Option<UserTotpDb>>;
+    async fn save_pending_totp(&self, user_id: Uuid, encrypted_secret: &str) -> AppResult<bool>;
+    async fn enable_totp(&self, user_id: Uuid, step: i64, recovery_code_hashes: &[String]) -> AppResult<bool>;
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> AppResult<bool>;
+    async fn delete_totp(&self, user_id: Uuid) -> AppResult<()>;
+    async fn replace_recovery_codes(&self, user_id: Uuid, code_hashes: &[String]) -> AppResult<()>;
+    async fn use_recovery_code(&self, user_id: Uuid, code_hash: &str) -> AppResult<bool>;
+    async fn count_recovery_codes(&self, user_id: Uuid) -> AppResult<i64>;
+    async fn create_challenge(&self, challenge: &MfaChallengeDb) -> AppResult<()>;
+    async fn get_challenge(&self, token_hash: &str) -> AppResult<Option<MfaChallengeDb>>;
+    async fn record_challenge_attempt(&self, id: Uuid) -> AppResult<i32>;
+    async fn complete_challenge(&self, id: Uuid) -> AppResult<bool>;
+}
+
+#[async_trait]
+pub trait EmailTokenRepository: Send + Sync {
+    async fn create_email_token(&self, token: &EmailTokenDb) -> AppResult<()>;
+    async fn get_email_token(&self, token_hash: &str, purpose: &str) -> AppResult<Option<EmailTokenDb>>;
+    async fn consume_email_token(&self, token_hash: &str, purpose: &str) -> AppResult<Option<EmailTokenDb>>;
+}
+
+#[async_trait]
+pub trait LoginAuditRepository: Send + Sync {
+    async fn record_failed_login(&self, attempt: &FailedLoginDb) -> AppResult<()>;
+    async fn list_failed_logins(&self, email: Option<&str>, limit: i64) -> AppResult<Vec<FailedLoginDb>>;
+}
+
+#[async_trait]
+pub trait WalletLoginRepository: Send + Sync {
+    async fn create_wallet_challenge(&self, challenge: &WalletLoginChallengeDb) -> AppResult<()>;
+    async fn get_wallet_challenge(&self, nonce: &str) -> AppResult<Option<WalletLoginChallengeDb>>;
+    async fn consume_wallet_challenge(&self, id: Uuid) -> AppResult<bool>;
+    async fn delete_stale_wallet_challenges(&self, now: DateTime<Utc>) -> AppResult<u64>;
+}
+
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn create_api_key(&self, key: &ApiKeyDb) -> AppResult<ApiKeyDb>;
+    async fn get_api_key(&self, institution_id: Uuid, id: Uuid) -> AppResult<Option<ApiKeyDb>>;
+    async fn get_api_key_by_prefix(&self, prefix: &str) -> AppResult<Option<ApiKeyDb>>;
+    async fn get_api_keys(&self, institution_id: Uuid) -> AppResult<Vec<ApiKeyDb>>;
+    async fn touch_api_key(&self, id: Uuid) -> AppResult<()>;
+    async fn revoke_api_key(&self, institution_id: Uuid, id: Uuid) -> AppResult<bool>;
+    async fn rotate_api_key(&self, institution_id: Uuid, old_id: Uuid, new_key: &ApiKeyDb) -> AppResult<Option<ApiKeyDb>>;
+}
+
+#[async_trait]
+pub trait OAuthRepository: Send + Sync {
+    async fn create_client(&self, client: &OAuthClientDb) -> AppResult<OAuthClientDb>;
+    async fn get_client(&self, client_id: &str) -> AppResult<Option<OAuthClientDb>>;
+    async fn get_client_by_id(&self, id: Uuid) -> AppResult<Option<OAuthClientDb>>;
+    async fn get_clients(&self, owner_id: Uuid) -> AppResult<Vec<OAuthClientDb>>;
+    async fn revoke_client(&self, owner_id: Uuid, client_id: &str) -> AppResult<bool>;
+    async fn create_grant(&self, grant: &OAuthGrantDb, code: &OAuthAuthorizationCodeDb) -> AppResult<OAuthGrantDb>;
+    async fn get_grant(&self, id: Uuid) -> AppResult<Option<OAuthGrantDb>>;
+    async fn get_active_grants(&self, user_id: Uuid) -> AppResult<Vec<OAuthGrantDb>>;
+    async fn revoke_grant(&self, user_id: Uuid, id: Uuid) -> AppResult<bool>;
+    async fn get_authorization_code(&self, code_hash: &str) -> AppResult<Option<OAuthAuthorizationCodeDb>>;
+    async fn consume_authorization_code(&self, id: Uuid) -> AppResult<bool>;
+    async fn create_access_token(&self, token: &OAuthAccessTokenDb) -> AppResult<()>;
+    async fn get_access_token(&self, token_hash: &str) -> AppResult<Option<OAuthAccessTokenDb>>;
+}
+
+#[async_trait]
+pub trait JwtKeyRepository: Send + Sync {
+    async fn create_first_key(&self, key: &JwtSigningKeyDb) -> AppResult<Option<JwtSigningKeyDb>>;
+    async fn get_verification_keys(&self, rotated_out_after: DateTime<Utc>) -> AppResult<Vec<JwtSigningKeyDb>>;
+    async fn rotate_key(&self, key: &JwtSigningKeyDb) -> AppResult<JwtSigningKeyDb>;
+}
+
+#[async_trait]
+pub trait IssuerKeyRepository: Send + Sync {
+    async fn create_issuer_key(&self, key: &IssuerKeyDb) -> AppResult<Option<IssuerKeyDb>>;
+    async fn get_active_issuer_key(&self, institution_id: Uuid) -> AppResult<Option<IssuerKeyDb>>;
+    async fn get_issuer_key_by_public_key(&self, public_key: &str) -> AppResult<Option<IssuerKeyDb>>;
+}
+
+#[async_trait]
+pub trait DocumentKeyRepository: Send + Sync {
+    async fn create_wrapping_key(&self, key: &DocumentWrappingKeyDb) -> AppResult<Option<DocumentWrappingKeyDb>>;
+    async fn get_current_wrapping_key(&self, owner_type: &str, owner_id: Uuid) -> AppResult<Option<DocumentWrappingKeyDb>>;
+    async fn get_current_wrapping_keys(&self) -> AppResult<Vec<DocumentWrappingKeyDb>>;
+    async fn get_wrapping_keys(&self, ids: &[Uuid]) -> AppResult<Vec<DocumentWrappingKeyDb>>;
+    async fn get_document_keys(&self, credential_id: Uuid) -> AppResult<Vec<CredentialDocumentKeyDb>>;
+    async fn get_document_keys_for_owner(&self, owner_type: &str, owner_id: Uuid) -> AppResult<Vec<CredentialDocumentKeyDb>>;
+    async fn rotate_wrapping_key(
+        &self,
+        old_key_id: Uuid,
+        new_key: &DocumentWrappingKeyDb,
+        rewrapped: &[CredentialDocumentKeyDb],
+    ) -> AppResult<Option<DocumentWrappingKeyDb>>;
+}
+
+#[async_trait]
+pub trait WalletKeyRepository: Send + Sync {
+    async fn set_custodial_wallet(&self, key: &WalletKeyDb) -> AppResult<WalletKeyDb>;
+    async fn get_wallet_key(&self, user_id: Uuid) -> AppResult<Option<WalletKeyDb>>;
+    async fn mark_mnemonic_exported(&self, user_id: Uuid) -> AppResult<bool>;
+    async fn delete_wallet_key(&self, user_id: Uuid) -> AppResult<()>;
+}
+
+#[async_trait]
+pub trait SealedSecretRepository: Send + Sync {
+    async fn get_sealed_secrets_without_prefix(&self, purpose: SealPurpose, prefix: &str) -> AppResult<Vec<SealedSecretDb>>;
+    async fn replace_sealed_secret(&self, purpose: SealPurpose, secret: &SealedSecretDb, sealed: &str) -> AppResult<bool>;
 }
 
 pub struct UserRepositoryImpl {
@@ -43,11 +214,18 @@ impl UserRepositoryImpl {
 
 #[async_trait]
 impl UserRepository for UserRepositoryImpl {
-    async fn create_user(&self, user: &UserDb) -> AppResult<UserDb> {
+    /// Create a user together with their custodial wallet key, so no account
+    /// is left with an address whose key was never stored
+    async fn create_user_with_wallet_key(&self, user: &UserDb, wallet_key: &WalletKeyDb) -> AppResult<UserDb> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
         let result = sqlx::query_as::<_, UserDb>(
             r#"
-            INSERT INTO users (id, wallet_address, email, password_hash, name, role, is_verified, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO users (id, wallet_address, email, password_hash, name, role, is_verified, created_at, updated_at, wallet_custody)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#
         )
@@ -60,10 +238,17 @@ impl UserRepository for UserRepositoryImpl {
         .bind(user.is_verified)
         .bind(user.created_at)
         .bind(user.updated_at)
-        .fetch_one(&self.pool)
+        .bind(&user.wallet_custody)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        insert_wallet_key(&mut tx, wallet_key).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
         Ok(result)
     }
 
@@ -114,6 +299,31 @@ impl UserRepository for UserRepositoryImpl {
 
         Ok(())
     }
+
+    async fn update_wallet(&self, id: Uuid, wallet_address: &str, custody: &str) -> AppResult<()> {
+        sqlx::query("UPDATE users SET wallet_address = $1, wallet_custody = $2, updated_at = $3 WHERE id = $4")
+            .bind(wallet_address)
+            .bind(custody)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> AppResult<()> {
+        sqlx::query("UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3")
+            .bind(password_hash)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
 }
 
 pub struct InstitutionRepositoryImpl {
@@ -129,10 +339,18 @@ impl InstitutionRepositoryImpl {
 #[async_trait]
 impl InstitutionRepository for InstitutionRepositoryImpl {
     async fn create_institution(&self, institution: &InstitutionDb) -> AppResult<InstitutionDb> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
         let result = sqlx::query_as::<_, InstitutionDb>(
             r#"
-            INSERT INTO institutions (id, user_id, institution_name, institution_type, country, accreditation_number, is_accredited, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO institutions (
+                id, user_id, institution_name, institution_type, country, accreditation_number,
+                is_accredited, created_at, accreditation_status, accreditation_authority_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#
         )
@@ -144,10 +362,27 @@ impl InstitutionRepository for InstitutionRepositoryImpl {
         .bind(&institution.accreditation_number)
         .bind(institution.is_accredited)
         .bind(institution.created_at)
-        .fetch_one(&self.pool)
+        .bind(&institution.accreditation_status)
+        .bind(institution.accreditation_authority_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // The registering account becomes the institution's first owner
+        sqlx::query(
+            "INSERT INTO institution_members (institution_id, user_id, role, created_at) VALUES ($1, $2, 'owner', $3)"
+        )
+        .bind(result.id)
+        .bind(result.user_id)
+        .bind(result.created_at)
+        .execute(&mut *tx)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
         Ok(result)
     }
 
@@ -175,69 +410,115 @@ impl InstitutionRepository for InstitutionRepositoryImpl {
         Ok(result)
     }
 
-    async fn update_accreditation(&self, id: Uuid, accredited: bool) -> AppResult<()> {
-        sqlx::query("UPDATE institutions SET is_accredited = $1 WHERE id = $2")
-            .bind(accredited)
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| AppError::Database(e.to_string()))?;
+    async fn get_institution_by_name(&self, institution_name: &str) -> AppResult<Option<InstitutionDb>> {
+        let result = sqlx::query_as::<_, InstitutionDb>(
+            "SELECT * FROM institutions WHERE LOWER(institution_name) = LOWER($1) ORDER BY created_at LIMIT 1"
+        )
+        .bind(institution_name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
 
-        Ok(())
+        Ok(result)
+    }
+
+    async fn get_institutions_by_accreditation_status(&self, status: &str) -> AppResult<Vec<InstitutionDb>> {
+        let result = sqlx::query_as::<_, InstitutionDb>(
+            "SELECT * FROM institutions WHERE accreditation_status = $1 ORDER BY created_at"
+        )
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_institutions_by_accreditation_number(&self, accreditation_number: &str) -> AppResult<Vec<InstitutionDb>> {
+        let result = sqlx::query_as::<_, InstitutionDb>(
+            "SELECT * FROM institutions WHERE UPPER(accreditation_number) = UPPER($1) ORDER BY created_at"
+        )
+        .bind(accreditation_number)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn review_accreditation(
+        &self,
+        id: Uuid,
+        from: &[&str],
+        to: &str,
+        notes: Option<&str>,
+        reviewer_id: Uuid,
+    ) -> AppResult<Option<InstitutionDb>> {
+        // Only apply the decision if the institution is still in an expected state
+        let result = sqlx::query_as::<_, InstitutionDb>(
+            r#"
+            UPDATE institutions
+            SET accreditation_status = $1,
+                is_accredited = ($1 = 'approved'),
+                review_notes = $2,
+                reviewed_by = $3,
+                reviewed_at = NOW()
+            WHERE id = $4 AND accreditation_status = ANY($5)
+            RETURNING *
+            "#
+        )
+        .bind(to)
+        .bind(notes)
+        .bind(reviewer_id)
+        .bind(id)
+        .bind(from)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
     }
 }
 
-pub struct CredentialRepositoryImpl {
+pub struct InstitutionMemberRepositoryImpl {
     pool: PgPool,
 }
 
-impl CredentialRepositoryImpl {
+impl InstitutionMemberRepositoryImpl {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 }
 
 #[async_trait]
-impl CredentialRepository for CredentialRepositoryImpl {
-    async fn create_credential(&self, credential: &CredentialDb) -> AppResult<CredentialDb> {
-        let result = sqlx::query_as::<_, CredentialDb>(
+impl InstitutionMemberRepository for InstitutionMemberRepositoryImpl {
+    async fn add_member(&self, member: &InstitutionMemberDb) -> AppResult<Option<InstitutionMemberDb>> {
+        // Nothing is inserted if the user already acts for an institution
+        let result = sqlx::query_as::<_, InstitutionMemberDb>(
             r#"
-            INSERT INTO credentials (
-                id, credential_id, holder_id, issuer_id, credential_type, 
-                title, description, ipfs_hash, chain_hash, qr_code, issue_date, 
-                expiry_date, status, metadata, created_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            INSERT INTO institution_members (institution_id, user_id, role, added_by, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
             RETURNING *
             "#
         )
-        .bind(credential.id)
-        .bind(&credential.credential_id)
-        .bind(credential.holder_id)
-        .bind(credential.issuer_id)
-        .bind(&credential.credential_type)
-        .bind(&credential.title)
-        .bind(&credential.description)
-        .bind(&credential.ipfs_hash)
-        .bind(&credential.chain_hash)
-        .bind(&credential.qr_code)
-        .bind(credential.issue_date)
-        .bind(credential.expiry_date)
-        .bind(&credential.status)
-        .bind(&credential.metadata)
-        .bind(credential.created_at)
-        .fetch_one(&self.pool)
+        .bind(member.institution_id)
+        .bind(member.user_id)
+        .bind(&member.role)
+        .bind(member.added_by)
+        .bind(member.created_at)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
         Ok(result)
     }
 
-    async fn get_credential_by_id(&self, id: Uuid) -> AppResult<Option<CredentialDb>> {
-        let result = sqlx::query_as::<_, CredentialDb>(
-            "SELECT * FROM credentials WHERE id = $1"
+    async fn get_membership(&self, user_id: Uuid) -> AppResult<Option<InstitutionMemberDb>> {
+        let result = sqlx::query_as::<_, InstitutionMemberDb>(
+            "SELECT * FROM institution_members WHERE user_id = $1"
         )
-        .bind(id)
+        .bind(user_id)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -245,11 +526,34 @@ impl CredentialRepository for CredentialRepositoryImpl {
         Ok(result)
     }
 
-    async fn get_credential_by_credential_id(&self, credential_id: &str) -> AppResult<Option<CredentialDb>> {
-        let result = sqlx::query_as::<_, CredentialDb>(
-            "SELECT * FROM credentials WHERE credential_id = $1"
+    async fn get_members(&self, institution_id: Uuid) -> AppResult<Vec<InstitutionMemberDb>> {
+        let result = sqlx::query_as::<_, InstitutionMemberDb>(
+            "SELECT * FROM institution_members WHERE institution_id = $1 ORDER BY created_at"
         )
-        .bind(credential_id)
+        .bind(institution_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn update_member_role(&self, institution_id: Uuid, user_id: Uuid, role: &str) -> AppResult<Option<InstitutionMemberDb>> {
+        // Refuse to demote the last remaining owner
+        let result = sqlx::query_as::<_, InstitutionMemberDb>(
+            r#"
+            UPDATE institution_members
+            SET role = $3
+            WHERE institution_id = $1 AND user_id = $2
+              AND (role <> 'owner' OR $3 = 'owner' OR (
+                  SELECT COUNT(*) FROM institution_members WHERE institution_id = $1 AND role = 'owner'
+              ) > 1)
+            RETURNING *
+            "#
+        )
+        .bind(institution_id)
+        .bind(user_id)
+        .bind(role)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -257,11 +561,49 @@ impl CredentialRepository for CredentialRepositoryImpl {
         Ok(result)
     }
 
-    async fn get_credentials_by_holder(&self, holder_id: Uuid) -> AppResult<Vec<CredentialDb>> {
-        let result = sqlx::query_as::<_, CredentialDb>(
-            "SELECT * FROM credentials WHERE holder_id = $1 ORDER BY created_at DESC"
+    async fn remove_member(&self, institution_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+        // Refuse to remove the last remaining owner
+        let result = sqlx::query(
+            r#"
+            DELETE FROM institution_members
+            WHERE institution_id = $1 AND user_id = $2
+              AND (role <> 'owner' OR (
+                  SELECT COUNT(*) FROM institution_members WHERE institution_id = $1 AND role = 'owner'
+              ) > 1)
+            "#
         )
-        .bind(holder_id)
+        .bind(institution_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+pub struct AccreditationAuthorityRepositoryImpl {
+    pool: PgPool,
+}
+
+impl AccreditationAuthorityRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccreditationAuthorityRepository for AccreditationAuthorityRepositoryImpl {
+    async fn list_authorities(&self, country: Option<&str>) -> AppResult<Vec<AccreditationAuthorityDb>> {
+        // Countries may be given by name or ISO code
+        let result = sqlx::query_as::<_, AccreditationAuthorityDb>(
+            r#"
+            SELECT * FROM accreditation_authorities
+            WHERE $1::TEXT IS NULL OR LOWER(country) = LOWER($1) OR LOWER(country_code) = LOWER($1)
+            ORDER BY country, code
+            "#
+        )
+        .bind(country.map(str::trim))
         .fetch_all(&self.pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -269,26 +611,1923 @@ impl CredentialRepository for CredentialRepositoryImpl {
         Ok(result)
     }
 
-    async fn get_credentials_by_issuer(&self, issuer_id: Uuid) -> AppResult<Vec<CredentialDb>> {
-        let result = sqlx::query_as::<_, CredentialDb>(
-            "SELECT * FROM credentials WHERE issuer_id = $1 ORDER BY created_at DESC"
+    async fn get_authority_by_id(&self, id: Uuid) -> AppResult<Option<AccreditationAuthorityDb>> {
+        let result = sqlx::query_as::<_, AccreditationAuthorityDb>(
+            "SELECT * FROM accreditation_authorities WHERE id = $1"
         )
-        .bind(issuer_id)
-        .fetch_all(&self.pool)
+        .bind(id)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
         Ok(result)
     }
 
-    async fn update_credential_status(&self, id: Uuid, status: &str) -> AppResult<()> {
-        sqlx::query("UPDATE credentials SET status = $1 WHERE id = $2")
-            .bind(status)
-            .bind(id)
-            .execute(&self.pool)
+    async fn get_authority_by_code(&self, code: &str) -> AppResult<Option<AccreditationAuthorityDb>> {
+        let result = sqlx::query_as::<_, AccreditationAuthorityDb>(
+            "SELECT * FROM accreditation_authorities WHERE UPPER(code) = UPPER($1)"
+        )
+        .bind(code.trim())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn matches_number_format(&self, id: Uuid, accreditation_number: &str) -> AppResult<bool> {
+        let matches = sqlx::query_as::<_, (bool,)>(
+            "SELECT $2 ~ number_pattern FROM accreditation_authorities WHERE id = $1"
+        )
+        .bind(id)
+        .bind(accreditation_number)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(matches.is_some_and(|(matches,)| matches))
+    }
+}
+
+pub struct CredentialRepositoryImpl {
+    pool: PgPool,
+}
+
+impl CredentialRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CredentialRepository for CredentialRepositoryImpl {
+    /// Insert a credential together with the wrapped keys of its encrypted
+    /// document, so no credential is left with a document it cannot open
+    async fn create_credential_with_document_keys(
+        &self,
+        credential: &CredentialDb,
+        document_keys: &[CredentialDocumentKeyDb],
+    ) -> AppResult<CredentialDb> {
+        let mut tx = self.pool
+            .begin()
             .await
             .map_err(|e| AppError::Database(e.to_string()))?;
 
-        Ok(())
+        let result = sqlx::query_as::<_, CredentialDb>(
+            r#"
+            INSERT INTO credentials (
+                id, credential_id, holder_id, issuer_id, credential_type, 
+                title, description, ipfs_hash, chain_hash, qr_code, issue_date, 
+                expiry_date, status, metadata, created_at, issuer_signature, issuer_public_key,
+                status_list_id, status_list_index, institution_id, document_encrypted
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)
+            RETURNING *
+            "#
+        )
+        .bind(credential.id)
+        .bind(&credential.credential_id)
+        .bind(credential.holder_id)
+        .bind(credential.issuer_id)
+        .bind(&credential.credential_type)
+        .bind(&credential.title)
+        .bind(&credential.description)
+        .bind(&credential.ipfs_hash)
+        .bind(&credential.chain_hash)
+        .bind(&credential.qr_code)
+        .bind(credential.issue_date)
+        .bind(credential.expiry_date)
+        .bind(&credential.status)
+        .bind(&credential.metadata)
+        .bind(credential.created_at)
+        .bind(&credential.issuer_signature)
+        .bind(&credential.issuer_public_key)
+        .bind(credential.status_list_id)
+        .bind(credential.status_list_index)
+        .bind(credential.institution_id)
+        .bind(credential.document_encrypted)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        for key in document_keys {
+            sqlx::query(
+                r#"
+                INSERT INTO credential_document_keys (credential_id, wrapping_key_id, wrapped_key, created_at)
+                VALUES ($1, $2, $3, $4)
+                "#
+            )
+            .bind(key.credential_id)
+            .bind(key.wrapping_key_id)
+            .bind(&key.wrapped_key)
+            .bind(key.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Record the reviewer, signature, chain hash and status list entry of a
+    /// credential that is still pending, together with the status change that
+    /// issues it. False if it is no longer pending.
+    async fn approve_pending_credential(&self, credential: &CredentialDb, event: &CredentialStatusEventDb) -> AppResult<bool> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE credentials
+            SET issuer_id = $1, chain_hash = $2, issuer_signature = $3, issuer_public_key = $4,
+                status_list_id = $5, status_list_index = $6, status = $7, status_reason = $8
+            WHERE id = $9 AND status = 'pending'
+            "#
+        )
+        .bind(credential.issuer_id)
+        .bind(&credential.chain_hash)
+        .bind(&credential.issuer_signature)
+        .bind(&credential.issuer_public_key)
+        .bind(credential.status_list_id)
+        .bind(credential.status_list_index)
+        .bind(&event.to_status)
+        .bind(&event.reason)
+        .bind(credential.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if updated.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        insert_status_event(&mut tx, event).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    async fn get_credential_by_id(&self, id: Uuid) -> AppResult<Option<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            "SELECT * FROM credentials WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_credential_by_credential_id(&self, credential_id: &str) -> AppResult<Option<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            "SELECT * FROM credentials WHERE credential_id = $1"
+        )
+        .bind(credential_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_credentials_by_holder(&self, holder_id: Uuid) -> AppResult<Vec<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            "SELECT * FROM credentials WHERE holder_id = $1 ORDER BY created_at DESC"
+        )
+        .bind(holder_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_credentials_by_institution(&self, institution_id: Uuid) -> AppResult<Vec<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            "SELECT * FROM credentials WHERE institution_id = $1 ORDER BY created_at DESC"
+        )
+        .bind(institution_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn allocate_status_list_index(&self, institution_id: Uuid) -> AppResult<(Uuid, i32)> {
+        loop {
+            // The row lock taken by UPDATE serialises concurrent allocations, and
+            // the capacity check is re-evaluated against the latest row version
+            let allocated = sqlx::query_as::<_, (Uuid, i32)>(
+                r#"
+                UPDATE status_lists
+                SET next_index = next_index + 1, updated_at = NOW()
+                WHERE institution_id = $1
+                  AND next_index < $2
+                  AND list_number = (SELECT MAX(list_number) FROM status_lists WHERE institution_id = $1)
+                RETURNING id, next_index - 1
+                "#
+            )
+            .bind(institution_id)
+            .bind(STATUS_LIST_SIZE)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+            if let Some(allocated) = allocated {
+                return Ok(allocated);
+            }
+
+            // No list yet or the current one is full; a concurrent insert of the
+            // same list number is ignored and the allocation retried
+            let empty_list = vec![0u8; (STATUS_LIST_SIZE / 8) as usize];
+            sqlx::query(
+                r#"
+                INSERT INTO status_lists (id, institution_id, list_number, revocation_bits, suspension_bits)
+                VALUES (
+                    $1, $2,
+                    COALESCE((SELECT MAX(list_number) + 1 FROM status_lists WHERE institution_id = $2), 0),
+                    $3, $3
+                )
+                ON CONFLICT (institution_id, list_number) DO NOTHING
+                "#
+            )
+            .bind(Uuid::new_v4())
+            .bind(institution_id)
+            .bind(&empty_list)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+    }
+
+    async fn get_status_list(&self, id: Uuid) -> AppResult<Option<StatusListDb>> {
+        let result = sqlx::query_as::<_, StatusListDb>(
+            "SELECT * FROM status_lists WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_expired_credentials(&self, now: DateTime<Utc>, skip: &[Uuid], limit: i64) -> AppResult<Vec<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            r#"
+            SELECT * FROM credentials
+            WHERE status IN ('issued', 'suspended') AND expiry_date <= $1 AND id <> ALL($2)
+            ORDER BY expiry_date
+            LIMIT $3
+            "#
+        )
+        .bind(now)
+        .bind(skip)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_credentials_expiring_before(&self, now: DateTime<Utc>, until: DateTime<Utc>) -> AppResult<Vec<CredentialDb>> {
+        let result = sqlx::query_as::<_, CredentialDb>(
+            r#"
+            SELECT * FROM credentials
+            WHERE status = 'issued' AND expiry_date > $1 AND expiry_date <= $2
+            ORDER BY expiry_date
+            "#
+        )
+        .bind(now)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_expiry_notices(&self, credential_id: Uuid) -> AppResult<Vec<i32>> {
+        let result: Vec<(i32,)> = sqlx::query_as(
+            "SELECT lead_days FROM credential_expiry_notices WHERE credential_id = $1"
+        )
+        .bind(credential_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.into_iter().map(|(lead_days,)| lead_days).collect())
+    }
+
+    async fn record_expiry_notice(&self, credential_id: Uuid, lead_days: i32) -> AppResult<bool> {
+        // The primary key keeps a single record of each reminder
+        let result = sqlx::query(
+            r#"
+            INSERT INTO credential_expiry_notices (credential_id, lead_days)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(credential_id)
+        .bind(lead_days)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+pub struct CredentialStatusEventRepositoryImpl {
+    pool: PgPool,
+}
+
+impl CredentialStatusEventRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Set or clear a credential's bit in one of its issuer's status lists
+async fn set_status_list_bit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    list_id: Uuid,
+    index: i32,
+    purpose: StatusPurpose,
+    value: bool,
+) -> AppResult<()> {
+    if !(0..STATUS_LIST_SIZE).contains(&index) {
+        return Err(AppError::Validation("Status list index out of range".to_string()));
+    }
+
+    // Postgres numbers bits from the least significant bit of each byte,
+    // while status lists number them from the most significant bit
+    let bit = (index / 8) * 8 + (7 - index % 8);
+    let query = match purpose {
+        StatusPurpose::Revocation =>
+            "UPDATE status_lists SET revocation_bits = set_bit(revocation_bits, $1, $2), updated_at = NOW() WHERE id = $3",
+        StatusPurpose::Suspension =>
+            "UPDATE status_lists SET suspension_bits = set_bit(suspension_bits, $1, $2), updated_at = NOW() WHERE id = $3",
+    };
+
+    sqlx::query(query)
+        .bind(bit)
+        .bind(value as i32)
+        .bind(list_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+async fn insert_status_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &CredentialStatusEventDb,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO credential_status_events (
+            id, credential_id, from_status, to_status, reason, note, actor_id, effective_at, recorded_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#
+    )
+    .bind(event.id)
+    .bind(event.credential_id)
+    .bind(&event.from_status)
+    .bind(&event.to_status)
+    .bind(&event.reason)
+    .bind(&event.note)
+    .bind(event.actor_id)
+    .bind(event.effective_at)
+    .bind(event.recorded_at)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl CredentialStatusEventRepository for CredentialStatusEventRepositoryImpl {
+    /// Apply a status change, record it and update the credential's status
+    /// list bit, all or nothing. False if the credential's status moved on.
+    async fn record_status_change(
+        &self,
+        event: &CredentialStatusEventDb,
+        status_bit: Option<(StatusPurpose, bool)>,
+    ) -> AppResult<bool> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // Only apply the change if nobody else moved the credential in the meantime
+        let updated = sqlx::query_as::<_, (Option<Uuid>, Option<i32>)>(
+            r#"
+            UPDATE credentials SET status = $1, status_reason = $2
+            WHERE id = $3 AND status = $4
+            RETURNING status_list_id, status_list_index
+            "#
+        )
+        .bind(&event.to_status)
+        .bind(&event.reason)
+        .bind(event.credential_id)
+        .bind(&event.from_status)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let Some(status_list_entry) = updated else {
+            return Ok(false);
+        };
+
+        insert_status_event(&mut tx, event).await?;
+
+        // Credentials issued before status lists existed have no entry
+        if let (Some((purpose, value)), (Some(list_id), Some(index))) = (status_bit, status_list_entry) {
+            set_status_list_bit(&mut tx, list_id, index, purpose, value).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    async fn get_status_events(&self, credential_id: Uuid) -> AppResult<Vec<CredentialStatusEventDb>> {
+        let result = sqlx::query_as::<_, CredentialStatusEventDb>(
+            r#"
+            SELECT * FROM credential_status_events
+            WHERE credential_id = $1
+            ORDER BY effective_at, recorded_at
+            "#
+        )
+        .bind(credential_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+pub struct SessionRepositoryImpl {
+    pool: PgPool,
+}
+
+impl SessionRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SessionRepositoryImpl {
+    async fn create_session(&self, session: &AuthSessionDb, refresh_token: &RefreshTokenDb) -> AppResult<()> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO auth_sessions (id, user_id, created_at, last_refreshed_at, mfa_verified_at, login_method) VALUES ($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(session.id)
+        .bind(session.user_id)
+        .bind(session.created_at)
+        .bind(session.last_refreshed_at)
+        .bind(session.mfa_verified_at)
+        .bind(&session.login_method)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens (id, session_id, token_hash, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(refresh_token.id)
+        .bind(refresh_token.session_id)
+        .bind(&refresh_token.token_hash)
+        .bind(refresh_token.created_at)
+        .bind(refresh_token.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_session(&self, id: Uuid) -> AppResult<Option<AuthSessionDb>> {
+        let result = sqlx::query_as::<_, AuthSessionDb>(
+            "SELECT * FROM auth_sessions WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_refresh_token(&self, token_hash: &str) -> AppResult<Option<RefreshTokenDb>> {
+        let result = sqlx::query_as::<_, RefreshTokenDb>(
+            "SELECT * FROM refresh_tokens WHERE token_hash = $1"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn rotate_refresh_token(&self, used_id: Uuid, replacement: &RefreshTokenDb) -> AppResult<bool> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // Only the first exchange of a token succeeds
+        let used = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET used_at = NOW()
+            WHERE id = $1 AND used_at IS NULL
+              AND session_id IN (SELECT id FROM auth_sessions WHERE revoked_at IS NULL)
+            "#
+        )
+        .bind(used_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if used.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens (id, session_id, token_hash, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(replacement.id)
+        .bind(replacement.session_id)
+        .bind(&replacement.token_hash)
+        .bind(replacement.created_at)
+        .bind(replacement.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query("UPDATE auth_sessions SET last_refreshed_at = $1 WHERE id = $2")
+            .bind(replacement.created_at)
+            .bind(replacement.session_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    async fn revoke_session(&self, id: Uuid, reason: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE auth_sessions SET revoked_at = NOW(), revoked_reason = $1 WHERE id = $2 AND revoked_at IS NULL"
+        )
+        .bind(reason)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_user_sessions(&self, user_id: Uuid, reason: &str) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE auth_sessions SET revoked_at = NOW(), revoked_reason = $1 WHERE user_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(reason)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn revoke_other_sessions(&self, user_id: Uuid, keep_session_id: Uuid, reason: &str) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE auth_sessions SET revoked_at = NOW(), revoked_reason = $1 WHERE user_id = $2 AND id <> $3 AND revoked_at IS NULL"
+        )
+        .bind(reason)
+        .bind(user_id)
+        .bind(keep_session_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn mark_session_mfa_verified(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE auth_sessions SET mfa_verified_at = NOW() WHERE id = $1 AND mfa_verified_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+pub struct MfaRepositoryImpl {
+    pool: PgPool,
+}
+
+impl MfaRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Insert a fresh set of recovery codes, discarding the user's existing ones
+async fn insert_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> AppResult<()> {
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    for code_hash in code_hashes {
+        sqlx::query("INSERT INTO totp_recovery_codes (id, user_id, code_hash) VALUES ($1, $2, $3)")
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl MfaRepository for MfaRepositoryImpl {
+    async fn get_totp(&self, user_id: Uuid) -> AppResult<Option<UserTotpDb>> {
+        let result = sqlx::query_as::<_, UserTotpDb>(
+            "SELECT * FROM user_totp WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Start or restart enrollment. Returns false if TOTP is already enabled.
+    async fn save_pending_totp(&self, user_id: Uuid, encrypted_secret: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, encrypted_secret) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+                SET encrypted_secret = EXCLUDED.encrypted_secret, last_used_step = NULL, created_at = NOW()
+                WHERE user_totp.enabled_at IS NULL
+            "#
+        )
+        .bind(user_id)
+        .bind(encrypted_secret)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Finish enrollment with the step of the confirming code and the first recovery codes
+    async fn enable_totp(&self, user_id: Uuid, step: i64, recovery_code_hashes: &[String]) -> AppResult<bool> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let enabled = sqlx::query(
+            "UPDATE user_totp SET enabled_at = NOW(), last_used_step = $2 WHERE user_id = $1 AND enabled_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if enabled.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        insert_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    /// Accept a code's time step only if it is newer than the last accepted one
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_totp SET last_used_step = $2
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_totp(&self, user_id: Uuid) -> AppResult<()> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        insert_recovery_codes(&mut tx, user_id, &[]).await?;
+
+        sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(&self, user_id: Uuid, code_hashes: &[String]) -> AppResult<()> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        insert_recovery_codes(&mut tx, user_id, code_hashes).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn use_recovery_code(&self, user_id: Uuid, code_hash: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE totp_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn count_recovery_codes(&self, user_id: Uuid) -> AppResult<i64> {
+        let (count,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM totp_recovery_codes WHERE user_id = $1 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(count)
+    }
+
+    async fn create_challenge(&self, challenge: &MfaChallengeDb) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO mfa_challenges (id, user_id, token_hash, created_at, expires_at, login_method) VALUES ($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(challenge.id)
+        .bind(challenge.user_id)
+        .bind(&challenge.token_hash)
+        .bind(challenge.created_at)
+        .bind(challenge.expires_at)
+        .bind(&challenge.login_method)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_challenge(&self, token_hash: &str) -> AppResult<Option<MfaChallengeDb>> {
+        let result = sqlx::query_as::<_, MfaChallengeDb>(
+            "SELECT * FROM mfa_challenges WHERE token_hash = $1"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Count a code attempt against the challenge, returning the new total
+    async fn record_challenge_attempt(&self, id: Uuid) -> AppResult<i32> {
+        let (attempts,) = sqlx::query_as::<_, (i32,)>(
+            "UPDATE mfa_challenges SET attempts = attempts + 1 WHERE id = $1 RETURNING attempts"
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(attempts)
+    }
+
+    async fn complete_challenge(&self, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE mfa_challenges SET completed_at = NOW() WHERE id = $1 AND completed_at IS NULL"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+pub struct EmailTokenRepositoryImpl {
+    pool: PgPool,
+}
+
+impl EmailTokenRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EmailTokenRepository for EmailTokenRepositoryImpl {
+    /// Store a new token, discarding the user's unused tokens for the same purpose
+    async fn create_email_token(&self, token: &EmailTokenDb) -> AppResult<()> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query("DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL")
+            .bind(token.user_id)
+            .bind(&token.purpose)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_tokens (id, user_id, purpose, token_hash, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(token.id)
+        .bind(token.user_id)
+        .bind(&token.purpose)
+        .bind(&token.token_hash)
+        .bind(token.created_at)
+        .bind(token.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Mark an unexpired, unused token as used. Returns None if it cannot be used.
+    async fn get_email_token(&self, token_hash: &str, purpose: &str) -> AppResult<Option<EmailTokenDb>> {
+        let result = sqlx::query_as::<_, EmailTokenDb>(
+            "SELECT * FROM email_tokens WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()"
+        )
+        .bind(token_hash)
+        .bind(purpose)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn consume_email_token(&self, token_hash: &str, purpose: &str) -> AppResult<Option<EmailTokenDb>> {
+        let result = sqlx::query_as::<_, EmailTokenDb>(
+            r#"
+            UPDATE email_tokens SET used_at = NOW()
+            WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING *
+            "#
+        )
+        .bind(token_hash)
+        .bind(purpose)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+pub struct LoginAuditRepositoryImpl {
+    pool: PgPool,
+}
+
+impl LoginAuditRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LoginAuditRepository for LoginAuditRepositoryImpl {
+    async fn record_failed_login(&self, attempt: &FailedLoginDb) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO failed_logins (id, email, user_id, ip_address, reason, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(attempt.id)
+        .bind(&attempt.email)
+        .bind(attempt.user_id)
+        .bind(&attempt.ip_address)
+        .bind(&attempt.reason)
+        .bind(attempt.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Most recent first, optionally for one email address
+    async fn list_failed_logins(&self, email: Option<&str>, limit: i64) -> AppResult<Vec<FailedLoginDb>> {
+        let result = sqlx::query_as::<_, FailedLoginDb>(
+            r#"
+            SELECT * FROM failed_logins
+            WHERE $1::TEXT IS NULL OR email = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(email)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+pub struct WalletLoginRepositoryImpl {
+    pool: PgPool,
+}
+
+impl WalletLoginRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WalletLoginRepository for WalletLoginRepositoryImpl {
+    async fn create_wallet_challenge(&self, challenge: &WalletLoginChallengeDb) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO wallet_login_challenges (id, wallet_address, nonce, message, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(challenge.id)
+        .bind(&challenge.wallet_address)
+        .bind(&challenge.nonce)
+        .bind(&challenge.message)
+        .bind(challenge.created_at)
+        .bind(challenge.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_wallet_challenge(&self, nonce: &str) -> AppResult<Option<WalletLoginChallengeDb>> {
+        let result = sqlx::query_as::<_, WalletLoginChallengeDb>(
+            "SELECT * FROM wallet_login_challenges WHERE nonce = $1"
+        )
+        .bind(nonce)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Mark an unexpired, unused challenge as used. Returns false if it cannot be used.
+    async fn consume_wallet_challenge(&self, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE wallet_login_challenges SET used_at = NOW() WHERE id = $1 AND used_at IS NULL AND expires_at > NOW()"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Delete challenges that have been used or have expired
+    async fn delete_stale_wallet_challenges(&self, now: DateTime<Utc>) -> AppResult<u64> {
+        let result = sqlx::query(
+            "DELETE FROM wallet_login_challenges WHERE expires_at <= $1 OR used_at IS NOT NULL"
+        )
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub struct ApiKeyRepositoryImpl {
+    pool: PgPool,
+}
+
+impl ApiKeyRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    async fn create_api_key(&self, key: &ApiKeyDb) -> AppResult<ApiKeyDb> {
+        let result = sqlx::query_as::<_, ApiKeyDb>(
+            r#"
+            INSERT INTO api_keys (id, institution_id, name, prefix, key_hash, scopes, created_by, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(key.id)
+        .bind(key.institution_id)
+        .bind(&key.name)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .bind(&key.scopes)
+        .bind(key.created_by)
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_api_key(&self, institution_id: Uuid, id: Uuid) -> AppResult<Option<ApiKeyDb>> {
+        let result = sqlx::query_as::<_, ApiKeyDb>("SELECT * FROM api_keys WHERE id = $1 AND institution_id = $2")
+            .bind(id)
+            .bind(institution_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_api_key_by_prefix(&self, prefix: &str) -> AppResult<Option<ApiKeyDb>> {
+        let result = sqlx::query_as::<_, ApiKeyDb>("SELECT * FROM api_keys WHERE prefix = $1")
+            .bind(prefix)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_api_keys(&self, institution_id: Uuid) -> AppResult<Vec<ApiKeyDb>> {
+        let result = sqlx::query_as::<_, ApiKeyDb>(
+            "SELECT * FROM api_keys WHERE institution_id = $1 ORDER BY created_at DESC"
+        )
+        .bind(institution_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Record that the key was used. Writes at most once a minute per key.
+    async fn touch_api_key(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys SET last_used_at = NOW()
+            WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Revoke an active key of the institution. Returns false if there was none.
+    async fn revoke_api_key(&self, institution_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND institution_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(id)
+        .bind(institution_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Revoke an active key and insert its replacement in one transaction.
+    /// Returns None if the old key is not an active key of the institution.
+    async fn rotate_api_key(&self, institution_id: Uuid, old_id: Uuid, new_key: &ApiKeyDb) -> AppResult<Option<ApiKeyDb>> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let created = sqlx::query_as::<_, ApiKeyDb>(
+            r#"
+            INSERT INTO api_keys (id, institution_id, name, prefix, key_hash, scopes, created_by, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(new_key.id)
+        .bind(new_key.institution_id)
+        .bind(&new_key.name)
+        .bind(&new_key.prefix)
+        .bind(&new_key.key_hash)
+        .bind(&new_key.scopes)
+        .bind(new_key.created_by)
+        .bind(new_key.created_at)
+        .bind(new_key.expires_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let revoked = sqlx::query(
+            r#"
+            UPDATE api_keys SET revoked_at = NOW(), replaced_by = $1
+            WHERE id = $2 AND institution_id = $3 AND revoked_at IS NULL
+            "#
+        )
+        .bind(created.id)
+        .bind(old_id)
+        .bind(institution_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // Dropping the transaction rolls back the new key
+        if revoked.rows_affected() != 1 {
+            return Ok(None);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(Some(created))
+    }
+}
+
+pub struct OAuthRepositoryImpl {
+    pool: PgPool,
+}
+
+impl OAuthRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OAuthRepository for OAuthRepositoryImpl {
+    async fn create_client(&self, client: &OAuthClientDb) -> AppResult<OAuthClientDb> {
+        let result = sqlx::query_as::<_, OAuthClientDb>(
+            r#"
+            INSERT INTO oauth_clients (id, client_id, client_secret_hash, name, redirect_uris, owner_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(client.id)
+        .bind(&client.client_id)
+        .bind(&client.client_secret_hash)
+        .bind(&client.name)
+        .bind(&client.redirect_uris)
+        .bind(client.owner_id)
+        .bind(client.created_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_client(&self, client_id: &str) -> AppResult<Option<OAuthClientDb>> {
+        let result = sqlx::query_as::<_, OAuthClientDb>("SELECT * FROM oauth_clients WHERE client_id = $1")
+            .bind(client_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_client_by_id(&self, id: Uuid) -> AppResult<Option<OAuthClientDb>> {
+        let result = sqlx::query_as::<_, OAuthClientDb>("SELECT * FROM oauth_clients WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_clients(&self, owner_id: Uuid) -> AppResult<Vec<OAuthClientDb>> {
+        let result = sqlx::query_as::<_, OAuthClientDb>(
+            "SELECT * FROM oauth_clients WHERE owner_id = $1 ORDER BY created_at DESC"
+        )
+        .bind(owner_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Revoke an active client of the owner. Returns false if there was none.
+    async fn revoke_client(&self, owner_id: Uuid, client_id: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_clients SET revoked_at = NOW() WHERE client_id = $1 AND owner_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(client_id)
+        .bind(owner_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Record a user's consent together with the authorization code that redeems it
+    async fn create_grant(&self, grant: &OAuthGrantDb, code: &OAuthAuthorizationCodeDb) -> AppResult<OAuthGrantDb> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let created = sqlx::query_as::<_, OAuthGrantDb>(
+            r#"
+            INSERT INTO oauth_grants (id, client_id, user_id, scopes, credential_ids, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(grant.id)
+        .bind(grant.client_id)
+        .bind(grant.user_id)
+        .bind(&grant.scopes)
+        .bind(&grant.credential_ids)
+        .bind(grant.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_authorization_codes (id, grant_id, code_hash, redirect_uri, code_challenge, nonce, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#
+        )
+        .bind(code.id)
+        .bind(created.id)
+        .bind(&code.code_hash)
+        .bind(&code.redirect_uri)
+        .bind(&code.code_challenge)
+        .bind(&code.nonce)
+        .bind(code.created_at)
+        .bind(code.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(created)
+    }
+
+    async fn get_grant(&self, id: Uuid) -> AppResult<Option<OAuthGrantDb>> {
+        let result = sqlx::query_as::<_, OAuthGrantDb>("SELECT * FROM oauth_grants WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_active_grants(&self, user_id: Uuid) -> AppResult<Vec<OAuthGrantDb>> {
+        let result = sqlx::query_as::<_, OAuthGrantDb>(
+            "SELECT * FROM oauth_grants WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Revoke an active grant of the user. Returns false if there was none.
+    async fn revoke_grant(&self, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_grants SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn get_authorization_code(&self, code_hash: &str) -> AppResult<Option<OAuthAuthorizationCodeDb>> {
+        let result = sqlx::query_as::<_, OAuthAuthorizationCodeDb>(
+            "SELECT * FROM oauth_authorization_codes WHERE code_hash = $1"
+        )
+        .bind(code_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Mark an unused code as used. Returns false if it was already used.
+    async fn consume_authorization_code(&self, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_authorization_codes SET used_at = NOW() WHERE id = $1 AND used_at IS NULL"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn create_access_token(&self, token: &OAuthAccessTokenDb) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_access_tokens (id, grant_id, token_hash, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#
+        )
+        .bind(token.id)
+        .bind(token.grant_id)
+        .bind(&token.token_hash)
+        .bind(token.created_at)
+        .bind(token.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_access_token(&self, token_hash: &str) -> AppResult<Option<OAuthAccessTokenDb>> {
+        let result = sqlx::query_as::<_, OAuthAccessTokenDb>(
+            "SELECT * FROM oauth_access_tokens WHERE token_hash = $1"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+pub struct JwtKeyRepositoryImpl {
+    pool: PgPool,
+}
+
+impl JwtKeyRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JwtKeyRepository for JwtKeyRepositoryImpl {
+    /// Insert the initial signing key, unless another instance already created one
+    async fn create_first_key(&self, key: &JwtSigningKeyDb) -> AppResult<Option<JwtSigningKeyDb>> {
+        let result = sqlx::query_as::<_, JwtSigningKeyDb>(
+            r#"
+            INSERT INTO jwt_signing_keys (id, algorithm, public_jwk, encrypted_private_key, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(key.id)
+        .bind(&key.algorithm)
+        .bind(&key.public_jwk)
+        .bind(&key.encrypted_private_key)
+        .bind(key.created_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// The current key plus keys rotated out recently enough to still have live tokens
+    async fn get_verification_keys(&self, rotated_out_after: DateTime<Utc>) -> AppResult<Vec<JwtSigningKeyDb>> {
+        let result = sqlx::query_as::<_, JwtSigningKeyDb>(
+            r#"
+            SELECT * FROM jwt_signing_keys
+            WHERE rotated_out_at IS NULL OR rotated_out_at > $1
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(rotated_out_after)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Make `key` the signing key, retiring the current one from signing
+    async fn rotate_key(&self, key: &JwtSigningKeyDb) -> AppResult<JwtSigningKeyDb> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query("UPDATE jwt_signing_keys SET rotated_out_at = $1 WHERE rotated_out_at IS NULL")
+            .bind(key.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let result = sqlx::query_as::<_, JwtSigningKeyDb>(
+            r#"
+            INSERT INTO jwt_signing_keys (id, algorithm, public_jwk, encrypted_private_key, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(key.id)
+        .bind(&key.algorithm)
+        .bind(&key.public_jwk)
+        .bind(&key.encrypted_private_key)
+        .bind(key.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+pub struct IssuerKeyRepositoryImpl {
+    pool: PgPool,
+}
+
+impl IssuerKeyRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IssuerKeyRepository for IssuerKeyRepositoryImpl {
+    /// None if the institution already has an active key
+    async fn create_issuer_key(&self, key: &IssuerKeyDb) -> AppResult<Option<IssuerKeyDb>> {
+        let result = sqlx::query_as::<_, IssuerKeyDb>(
+            r#"
+            INSERT INTO issuer_keys (id, institution_id, public_key, encrypted_seed, is_active, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(key.id)
+        .bind(key.institution_id)
+        .bind(&key.public_key)
+        .bind(&key.encrypted_seed)
+        .bind(key.is_active)
+        .bind(key.created_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_active_issuer_key(&self, institution_id: Uuid) -> AppResult<Option<IssuerKeyDb>> {
+        let result = sqlx::query_as::<_, IssuerKeyDb>(
+            "SELECT * FROM issuer_keys WHERE institution_id = $1 AND is_active = TRUE"
+        )
+        .bind(institution_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_issuer_key_by_public_key(&self, public_key: &str) -> AppResult<Option<IssuerKeyDb>> {
+        let result = sqlx::query_as::<_, IssuerKeyDb>(
+            "SELECT * FROM issuer_keys WHERE public_key = $1"
+        )
+        .bind(public_key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+}
+
+async fn insert_wallet_key(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, key: &WalletKeyDb) -> AppResult<WalletKeyDb> {
+    sqlx::query_as::<_, WalletKeyDb>(
+        r#"
+        INSERT INTO wallet_keys (user_id, wallet_address, encrypted_phrase, mnemonic_exported_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#
+    )
+    .bind(key.user_id)
+    .bind(&key.wallet_address)
+    .bind(&key.encrypted_phrase)
+    .bind(key.mnemonic_exported_at)
+    .bind(key.created_at)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))
+}
+
+pub struct WalletKeyRepositoryImpl {
+    pool: PgPool,
+}
+
+impl WalletKeyRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WalletKeyRepository for WalletKeyRepositoryImpl {
+    /// Store a custodial wallet key and point the user's account at its address
+    async fn set_custodial_wallet(&self, key: &WalletKeyDb) -> AppResult<WalletKeyDb> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query("UPDATE users SET wallet_address = $1, wallet_custody = 'custodial', updated_at = $2 WHERE id = $3")
+            .bind(&key.wallet_address)
+            .bind(chrono::Utc::now())
+            .bind(key.user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let result = insert_wallet_key(&mut tx, key).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_wallet_key(&self, user_id: Uuid) -> AppResult<Option<WalletKeyDb>> {
+        let result = sqlx::query_as::<_, WalletKeyDb>(
+            "SELECT * FROM wallet_keys WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn mark_mnemonic_exported(&self, user_id: Uuid) -> AppResult<bool> {
+        // Only the first export succeeds, even under concurrent requests
+        let result = sqlx::query(
+            "UPDATE wallet_keys SET mnemonic_exported_at = $1 WHERE user_id = $2 AND mnemonic_exported_at IS NULL"
+        )
+        .bind(chrono::Utc::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_wallet_key(&self, user_id: Uuid) -> AppResult<()> {
+        sqlx::query("DELETE FROM wallet_keys WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+pub struct DocumentKeyRepositoryImpl {
+    pool: PgPool,
+}
+
+impl DocumentKeyRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DocumentKeyRepository for DocumentKeyRepositoryImpl {
+    /// Insert an owner's first wrapping key, unless a concurrent request already created one
+    async fn create_wrapping_key(&self, key: &DocumentWrappingKeyDb) -> AppResult<Option<DocumentWrappingKeyDb>> {
+        let result = sqlx::query_as::<_, DocumentWrappingKeyDb>(
+            r#"
+            INSERT INTO document_wrapping_keys (id, owner_type, owner_id, encrypted_key, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(key.id)
+        .bind(&key.owner_type)
+        .bind(key.owner_id)
+        .bind(&key.encrypted_key)
+        .bind(key.created_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_current_wrapping_key(&self, owner_type: &str, owner_id: Uuid) -> AppResult<Option<DocumentWrappingKeyDb>> {
+        let result = sqlx::query_as::<_, DocumentWrappingKeyDb>(
+            "SELECT * FROM document_wrapping_keys WHERE owner_type = $1 AND owner_id = $2 AND rotated_out_at IS NULL"
+        )
+        .bind(owner_type)
+        .bind(owner_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_current_wrapping_keys(&self) -> AppResult<Vec<DocumentWrappingKeyDb>> {
+        let result = sqlx::query_as::<_, DocumentWrappingKeyDb>(
+            "SELECT * FROM document_wrapping_keys WHERE rotated_out_at IS NULL ORDER BY created_at"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_wrapping_keys(&self, ids: &[Uuid]) -> AppResult<Vec<DocumentWrappingKeyDb>> {
+        let result = sqlx::query_as::<_, DocumentWrappingKeyDb>(
+            "SELECT * FROM document_wrapping_keys WHERE id = ANY($1)"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    async fn get_document_keys(&self, credential_id: Uuid) -> AppResult<Vec<CredentialDocumentKeyDb>> {
+        let result = sqlx::query_as::<_, CredentialDocumentKeyDb>(
+            "SELECT * FROM credential_document_keys WHERE credential_id = $1"
+        )
+        .bind(credential_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Document keys wrapped by any of the owner's keys, current or rotated out
+    async fn get_document_keys_for_owner(&self, owner_type: &str, owner_id: Uuid) -> AppResult<Vec<CredentialDocumentKeyDb>> {
+        let result = sqlx::query_as::<_, CredentialDocumentKeyDb>(
+            r#"
+            SELECT k.* FROM credential_document_keys k
+            JOIN document_wrapping_keys w ON w.id = k.wrapping_key_id
+            WHERE w.owner_type = $1 AND w.owner_id = $2
+            "#
+        )
+        .bind(owner_type)
+        .bind(owner_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Replace an owner's current wrapping key, moving the given document keys,
+    /// rewrapped under the new key, over to it. `rewrapped` rows carry the
+    /// wrapping key they are replacing. None if the old key was already rotated out.
+    async fn rotate_wrapping_key(
+        &self,
+        old_key_id: Uuid,
+        new_key: &DocumentWrappingKeyDb,
+        rewrapped: &[CredentialDocumentKeyDb],
+    ) -> AppResult<Option<DocumentWrappingKeyDb>> {
+        let mut tx = self.pool
+            .begin()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rotated_out = sqlx::query(
+            "UPDATE document_wrapping_keys SET rotated_out_at = $1 WHERE id = $2 AND rotated_out_at IS NULL"
+        )
+        .bind(new_key.created_at)
+        .bind(old_key_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if rotated_out.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let result = sqlx::query_as::<_, DocumentWrappingKeyDb>(
+            r#"
+            INSERT INTO document_wrapping_keys (id, owner_type, owner_id, encrypted_key, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(new_key.id)
+        .bind(&new_key.owner_type)
+        .bind(new_key.owner_id)
+        .bind(&new_key.encrypted_key)
+        .bind(new_key.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        for key in rewrapped {
+            sqlx::query(
+                r#"
+                UPDATE credential_document_keys SET wrapping_key_id = $1, wrapped_key = $2
+                WHERE credential_id = $3 AND wrapping_key_id = $4
+                "#
+            )
+            .bind(new_key.id)
+            .bind(&key.wrapped_key)
+            .bind(key.credential_id)
+            .bind(key.wrapping_key_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(Some(result))
+    }
+}
+
+pub struct SealedSecretRepositoryImpl {
+    pool: PgPool,
+}
+
+impl SealedSecretRepositoryImpl {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// The table, primary key, owner id and sealed value columns for each purpose
+fn sealed_column(purpose: SealPurpose) -> (&'static str, &'static str, &'static str, &'static str) {
+    match purpose {
+        SealPurpose::WalletPhrase => ("wallet_keys", "user_id", "user_id", "encrypted_phrase"),
+        SealPurpose::TotpSecret => ("user_totp", "user_id", "user_id", "encrypted_secret"),
+        SealPurpose::IssuerSeed => ("issuer_keys", "id", "institution_id", "encrypted_seed"),
+        SealPurpose::DocumentWrappingKey => ("document_wrapping_keys", "id", "id", "encrypted_key"),
+        SealPurpose::TokenSigningKey => ("jwt_signing_keys", "id", "id", "encrypted_private_key"),
+    }
+}
+
+#[async_trait]
+impl SealedSecretRepository for SealedSecretRepositoryImpl {
+    /// Sealed values of a purpose not written in the format `prefix` marks
+    async fn get_sealed_secrets_without_prefix(&self, purpose: SealPurpose, prefix: &str) -> AppResult<Vec<SealedSecretDb>> {
+        let (table, id, owner_id, sealed) = sealed_column(purpose);
+        let query = format!(
+            "SELECT {id} AS id, {owner_id} AS owner_id, {sealed} AS sealed FROM {table} \
+             WHERE {sealed} <> '' AND left({sealed}, length($1)) <> $1"
+        );
+
+        let result = sqlx::query_as::<_, SealedSecretDb>(&query)
+            .bind(prefix)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Replace a sealed value unless it changed since it was read
+    async fn replace_sealed_secret(&self, purpose: SealPurpose, secret: &SealedSecretDb, sealed: &str) -> AppResult<bool> {
+        let (table, id, _, column) = sealed_column(purpose);
+        let query = format!("UPDATE {table} SET {column} = $1 WHERE {id} = $2 AND {column} = $3");
+
+        let result = sqlx::query(&query)
+            .bind(sealed)
+            .bind(secret.id)
+            .bind(&secret.sealed)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
     }
 }
@@ -11,6 +11,7 @@ const CONFIG = {
     // Use 127.0.0.1 to avoid IPv6 localhost resolution issues in some browsers
     API_BASE_URL: 'http://127.0.0.1:8080/api',
     STORAGE_KEY: 'ssp_auth_token',
+    REFRESH_KEY: 'ssp_refresh_token',
     USER_KEY: 'ssp_user_data',
     TOAST_DURATION: 5000,
     ANIMATION_DURATION: 300
@@ -19,6 +20,7 @@ const CONFIG = {
 // State Management
 const state = {
     token: null,
+    refreshToken: null,
     user: null,
     credentials: [],
     qrScanner: null
@@ -75,7 +77,7 @@ function setLoading(loading, message = 'Loading...') {
 /**
  * Make API request
  */
-async function apiRequest(endpoint, options = {}) {
+async function apiRequest(endpoint, options = {}, retried = false) {
     const url = `${CONFIG.API_BASE_URL}${endpoint}`;
     const headers = {
         'Content-Type': 'application/json',
@@ -91,6 +93,11 @@ async function apiRequest(endpoint, options = {}) {
             ...options,
             headers
         });
+
+        // Access tokens are short-lived; renew once and retry
+        if (response.status === 401 && state.refreshToken && !retried && await refreshSession()) {
+            return apiRequest(endpoint, options, true);
+        }
         
         let data;
         const contentType = response.headers.get('content-type');
@@ -114,6 +121,32 @@ async function apiRequest(endpoint, options = {}) {
     }
 }
 
+/**
+ * Exchange the refresh token for new tokens; clears auth data if the session has ended
+ */
+async function refreshSession() {
+    try {
+        const response = await fetch(`${CONFIG.API_BASE_URL}/auth/refresh`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ refresh_token: state.refreshToken })
+        });
+
+        if (!response.ok) {
+            clearAuthData();
+            updateNavigation();
+            return false;
+        }
+
+        const data = await response.json();
+        saveAuthData(data.token, state.user, data.refresh_token);
+        return true;
+    } catch (error) {
+        console.error('Session refresh failed:', error);
+        return false;
+    }
+}
+
 /**
  * Format date
  */
@@ -131,8 +164,10 @@ function formatDate(dateString) {
  */
 function clearAuthData() {
     localStorage.removeItem(CONFIG.STORAGE_KEY);
+    localStorage.removeItem(CONFIG.REFRESH_KEY);
     localStorage.removeItem(CONFIG.USER_KEY);
     state.token = null;
+    state.refreshToken = null;
     state.user = null;
     state.credentials = [];
 }
@@ -140,10 +175,12 @@ function clearAuthData() {
 /**
  * Save auth data to localStorage
  */
-function saveAuthData(token, user) {
+function saveAuthData(token, user, refreshToken) {
     localStorage.setItem(CONFIG.STORAGE_KEY, token);
+    localStorage.setItem(CONFIG.REFRESH_KEY, refreshToken);
     localStorage.setItem(CONFIG.USER_KEY, JSON.stringify(user));
     state.token = token;
+    state.refreshToken = refreshToken;
     state.user = user;
 }
 
@@ -156,6 +193,7 @@ function loadAuthData() {
     
     if (token && userData) {
         state.token = token;
+        state.refreshToken = localStorage.getItem(CONFIG.REFRESH_KEY);
         state.user = JSON.parse(userData);
         return true;
     }
@@ -179,6 +217,8 @@ function updateNavigation() {
         } else if (userNameElement && state.user.email) {
             userNameElement.textContent = state.user.email.split('@')[0]; // Show username part of email
         }
+        const verifyEmailBtn = document.getElementById('verify-email-btn');
+        if (verifyEmailBtn) verifyEmailBtn.style.display = state.user.is_verified === false ? 'inline-flex' : 'none';
     } else {
         // User is not logged in - show auth buttons, hide user info
         if (navAuthButtons) navAuthButtons.style.display = 'flex';
@@ -290,7 +330,16 @@ function animateCounters() {
 /**
  * Handle logout
  */
-function handleLogout() {
+async function handleLogout() {
+    // End the session server-side; local data is cleared even if this fails
+    if (state.token) {
+        try {
+            await apiRequest('/auth/logout', { method: 'POST' });
+        } catch (error) {
+            console.error('Logout request failed:', error);
+        }
+    }
+
     clearAuthData();
     closeModal(); // Close modal if open
     updateNavigation();
@@ -300,6 +349,424 @@ function handleLogout() {
     showSection('hero');
 }
 
+/**
+ * Handle logout from all devices
+ */
+async function handleLogoutAll() {
+    try {
+        await apiRequest('/auth/logout-all', { method: 'POST' });
+    } catch (error) {
+        showToast(`Failed to log out other devices: ${error.message}`, 'error');
+        return;
+    }
+
+    clearAuthData();
+    closeModal();
+    updateNavigation();
+    showToast('Logged out from all devices', 'info');
+    showSection('hero');
+}
+
+/**
+ * Mail a password reset link to the address in the login form
+ */
+async function handleForgotPassword(e) {
+    if (e) e.preventDefault();
+
+    const loginEmail = document.getElementById('login-email');
+    const email = (loginEmail && loginEmail.value) || prompt('Enter your account email address:');
+    if (!email) return;
+
+    try {
+        const response = await apiRequest('/auth/forgot-password', {
+            method: 'POST',
+            body: JSON.stringify({ email })
+        });
+        showToast(response.message, 'info');
+    } catch (error) {
+        showToast(error.message || 'Failed to request a password reset', 'error');
+    }
+}
+
+/**
+ * Send a new email verification link to the logged-in user
+ */
+async function resendVerificationEmail() {
+    try {
+        await apiRequest('/auth/verify-email/send', { method: 'POST' });
+        showToast('Verification email sent. Check your inbox.', 'success');
+    } catch (error) {
+        showToast(error.message || 'Failed to send verification email', 'error');
+    }
+}
+
+/**
+ * Handle verification and password reset links opened from an email
+ */
+async function handleEmailLinks() {
+    const params = new URLSearchParams(window.location.search);
+    const verifyToken = params.get('verify_email_token');
+    const resetToken = params.get('reset_password_token');
+    if (!verifyToken && !resetToken) return;
+
+    // Keep tokens out of the address bar and browser history
+    window.history.replaceState({}, document.title, window.location.pathname);
+
+    if (verifyToken) {
+        try {
+            await apiRequest('/auth/verify-email', {
+                method: 'POST',
+                body: JSON.stringify({ token: verifyToken })
+            });
+            if (state.user) {
+                state.user.is_verified = true;
+                localStorage.setItem(CONFIG.USER_KEY, JSON.stringify(state.user));
+            }
+            updateNavigation();
+            showToast('Email address verified', 'success');
+        } catch (error) {
+            showToast(error.message || 'Email verification failed', 'error');
+        }
+        return;
+    }
+
+    const newPassword = prompt('Choose a new password:');
+    if (!newPassword) return;
+
+    try {
+        const response = await apiRequest('/auth/reset-password', {
+            method: 'POST',
+            body: JSON.stringify({ token: resetToken, new_password: newPassword })
+        });
+        clearAuthData();
+        updateNavigation();
+        showToast(response.message, 'success');
+        showLoginModal();
+    } catch (error) {
+        showToast(error.message || 'Password reset failed', 'error');
+    }
+}
+
+const OAUTH_REQUEST_KEY = 'ssp_oauth_request';
+
+/**
+ * Pick up a "Sign in with Safari Skills Passport" request that a partner
+ * portal started at /oauth/authorize
+ */
+function handleOAuthAuthorizeLink() {
+    const params = new URLSearchParams(window.location.search);
+    if (!params.has('oauth_authorize')) return;
+
+    params.delete('oauth_authorize');
+    window.history.replaceState({}, document.title, window.location.pathname);
+    sessionStorage.setItem(OAUTH_REQUEST_KEY, params.toString());
+    resumeOAuthAuthorization();
+}
+
+/**
+ * Show the consent screen for a pending partner sign-in, logging in first if needed
+ */
+async function resumeOAuthAuthorization() {
+    const query = sessionStorage.getItem(OAUTH_REQUEST_KEY);
+    if (!query) return;
+
+    if (!state.token) {
+        showToast('Log in to continue to the partner site', 'info');
+        showLoginModal();
+        return;
+    }
+
+    let consent;
+    try {
+        consent = await apiRequest(`/oauth/authorize?${query}`);
+    } catch (error) {
+        sessionStorage.removeItem(OAUTH_REQUEST_KEY);
+        showToast(error.message || 'Invalid sign-in request', 'error');
+        return;
+    }
+
+    showOAuthConsentModal(consent, Object.fromEntries(new URLSearchParams(query)));
+}
+
+/**
+ * Ask the user what to share with a partner portal. Client-supplied text is
+ * inserted as text, never as HTML.
+ */
+function showOAuthConsentModal(consent, request) {
+    closeModal();
+    const modal = document.createElement('div');
+    modal.id = 'oauth-consent-modal';
+    modal.className = 'modal-overlay';
+    modal.innerHTML = `
+        <div class="modal-content">
+            <div class="modal-header">
+                <h2><i class="fas fa-sign-in-alt"></i> <span id="oauth-client-name"></span></h2>
+                <button class="modal-close">&times;</button>
+            </div>
+            <div class="modal-body">
+                <p class="qr-description">wants to use your Safari Skills Passport to:</p>
+                <ul id="oauth-scope-list"></ul>
+                <div id="oauth-credential-list"></div>
+                <p class="qr-description">You will be sent to <strong id="oauth-redirect-uri"></strong></p>
+                <div class="modal-actions">
+                    <button type="button" class="btn btn-outline" id="oauth-deny-btn">Cancel</button>
+                    <button type="button" class="btn btn-primary" id="oauth-approve-btn">Allow</button>
+                </div>
+            </div>
+        </div>
+    `;
+    document.body.appendChild(modal);
+
+    modal.querySelector('#oauth-client-name').textContent = consent.client_name;
+    modal.querySelector('#oauth-redirect-uri').textContent = new URL(consent.redirect_uri).host;
+
+    const scopeList = modal.querySelector('#oauth-scope-list');
+    consent.scopes.forEach(scope => {
+        const item = document.createElement('li');
+        item.textContent = scope.description;
+        scopeList.appendChild(item);
+    });
+
+    const credentialList = modal.querySelector('#oauth-credential-list');
+    consent.credentials.forEach(credential => {
+        const group = document.createElement('div');
+        group.className = 'checkbox-group';
+        const checkbox = document.createElement('input');
+        checkbox.type = 'checkbox';
+        checkbox.id = `oauth-credential-${credential.credential_id}`;
+        checkbox.value = credential.credential_id;
+        const label = document.createElement('label');
+        label.htmlFor = checkbox.id;
+        label.textContent = `${credential.title} (${credential.status})`;
+        group.appendChild(checkbox);
+        group.appendChild(label);
+        credentialList.appendChild(group);
+    });
+
+    modal.querySelector('.modal-close').addEventListener('click', () => submitOAuthDecision(request, false));
+    modal.querySelector('#oauth-deny-btn').addEventListener('click', () => submitOAuthDecision(request, false));
+    modal.querySelector('#oauth-approve-btn').addEventListener('click', () => submitOAuthDecision(request, true));
+
+    modal.style.display = 'flex';
+    modal.style.visibility = 'visible';
+    modal.style.opacity = '1';
+    document.body.style.overflow = 'hidden';
+}
+
+/**
+ * Approve or deny, then send the browser back to the partner portal
+ */
+async function submitOAuthDecision(request, approve) {
+    const credentialIds = Array.from(document.querySelectorAll('#oauth-credential-list input:checked'))
+        .map(input => input.value);
+
+    try {
+        const response = await apiRequest('/oauth/authorize', {
+            method: 'POST',
+            body: JSON.stringify({ ...request, approve, credential_ids: approve ? credentialIds : [] })
+        });
+        sessionStorage.removeItem(OAUTH_REQUEST_KEY);
+        window.location.href = response.redirect_to;
+    } catch (error) {
+        showToast(error.message || 'Failed to complete sign-in', 'error');
+    }
+}
+
+/**
+ * Show two-factor authentication status, with enrollment or management actions
+ */
+async function showTwoFactorModal() {
+    let status;
+    try {
+        status = await apiRequest('/auth/2fa');
+    } catch (error) {
+        showToast(error.message || 'Failed to load two-factor status', 'error');
+        return;
+    }
+
+    closeModal();
+    const modal = document.createElement('div');
+    modal.id = 'two-factor-modal';
+    modal.className = 'modal-overlay';
+    modal.innerHTML = `
+        <div class="modal-content">
+            <div class="modal-header">
+                <h2><i class="fas fa-shield-alt"></i> Two-Factor Authentication</h2>
+                <button class="modal-close">&times;</button>
+            </div>
+            <div class="modal-body">
+                ${status.enabled ? `
+                    <p>Two-factor authentication is <strong>enabled</strong>. ${status.recovery_codes_remaining} recovery codes remaining.</p>
+                    <div class="modal-actions">
+                        <button onclick="regenerateRecoveryCodes()" class="btn btn-primary">New Recovery Codes</button>
+                        ${status.required ? '' : '<button onclick="disableTwoFactor()" class="btn btn-secondary">Disable</button>'}
+                    </div>
+                ` : `
+                    <p>${status.required ? 'Your role requires two-factor authentication. ' : ''}Protect your account with a code from an authenticator app.</p>
+                    <div id="two-factor-setup"></div>
+                    <div class="modal-actions">
+                        <button onclick="beginTwoFactorSetup()" class="btn btn-primary" id="two-factor-setup-btn">Set Up</button>
+                    </div>
+                `}
+            </div>
+        </div>
+    `;
+    document.body.appendChild(modal);
+
+    const closeBtn = modal.querySelector('.modal-close');
+    if (closeBtn) {
+        closeBtn.addEventListener('click', () => closeModal());
+    }
+
+    modal.style.display = 'flex';
+    modal.style.visibility = 'visible';
+    modal.style.opacity = '1';
+    document.body.style.overflow = 'hidden';
+}
+
+/**
+ * Show the QR code for a new TOTP secret and ask for the first code
+ */
+async function beginTwoFactorSetup() {
+    try {
+        const setup = await apiRequest('/auth/2fa/setup', { method: 'POST' });
+        document.getElementById('two-factor-setup').innerHTML = `
+            <div class="qr-preview">
+                <img src="data:image/png;base64,${setup.qr_code}" alt="Authenticator QR Code" style="max-width: 200px; max-height: 200px;">
+            </div>
+            <p class="qr-description">Scan with your authenticator app, or enter the key <code>${setup.secret}</code></p>
+            <div class="form-group">
+                <input type="text" id="two-factor-code" inputmode="numeric" autocomplete="one-time-code" placeholder="6-digit code">
+            </div>
+        `;
+        const button = document.getElementById('two-factor-setup-btn');
+        button.textContent = 'Enable';
+        button.onclick = enableTwoFactor;
+    } catch (error) {
+        showToast(error.message || 'Failed to start two-factor setup', 'error');
+    }
+}
+
+/**
+ * Confirm enrollment and show the recovery codes once
+ */
+async function enableTwoFactor() {
+    const code = document.getElementById('two-factor-code').value.trim();
+    try {
+        const response = await apiRequest('/auth/2fa/enable', {
+            method: 'POST',
+            body: JSON.stringify({ code })
+        });
+        showRecoveryCodes(response.recovery_codes);
+        showToast('Two-factor authentication enabled', 'success');
+    } catch (error) {
+        showToast(error.message || 'Invalid code', 'error');
+    }
+}
+
+async function disableTwoFactor() {
+    const code = prompt('Enter a code from your authenticator app to disable two-factor authentication:');
+    if (!code) return;
+
+    try {
+        await apiRequest('/auth/2fa/disable', {
+            method: 'POST',
+            body: JSON.stringify({ code: code.trim() })
+        });
+        closeModal();
+        showToast('Two-factor authentication disabled', 'info');
+    } catch (error) {
+        showToast(error.message || 'Failed to disable two-factor authentication', 'error');
+    }
+}
+
+async function regenerateRecoveryCodes() {
+    const code = prompt('Enter a code from your authenticator app:');
+    if (!code) return;
+
+    try {
+        const response = await apiRequest('/auth/2fa/recovery-codes', {
+            method: 'POST',
+            body: JSON.stringify({ code: code.trim() })
+        });
+        showRecoveryCodes(response.recovery_codes);
+    } catch (error) {
+        showToast(error.message || 'Failed to create recovery codes', 'error');
+    }
+}
+
+function showRecoveryCodes(codes) {
+    const body = document.querySelector('#two-factor-modal .modal-body');
+    if (!body) return;
+    body.innerHTML = `
+        <p>Store these recovery codes somewhere safe. Each can be used once if you lose your authenticator. They will not be shown again.</p>
+        <pre>${codes.join('\n')}</pre>
+        <div class="modal-actions">
+            <button onclick="closeModal()" class="btn btn-primary">Done</button>
+        </div>
+    `;
+}
+
+/**
+ * Ask for the current and a new password. Other devices are signed out on success.
+ */
+function showChangePasswordModal() {
+    closeModal();
+    const modal = document.createElement('div');
+    modal.id = 'change-password-modal';
+    modal.className = 'modal-overlay';
+    modal.innerHTML = `
+        <div class="modal-content">
+            <div class="modal-header">
+                <h2><i class="fas fa-key"></i> Change Password</h2>
+                <button class="modal-close">&times;</button>
+            </div>
+            <div class="modal-body">
+                <form id="change-password-form">
+                    <div class="form-group">
+                        <label for="current-password">Current Password</label>
+                        <input type="password" id="current-password" class="form-input" required autocomplete="current-password">
+                    </div>
+                    <div class="form-group">
+                        <label for="new-password">New Password</label>
+                        <input type="password" id="new-password" class="form-input" required minlength="8" autocomplete="new-password">
+                    </div>
+                    <p class="qr-description">Use at least 8 characters. Common passwords and passwords containing your name or email are not accepted.</p>
+                    <div class="modal-actions">
+                        <button type="submit" class="btn btn-primary">Change Password</button>
+                    </div>
+                </form>
+            </div>
+        </div>
+    `;
+    document.body.appendChild(modal);
+
+    modal.querySelector('.modal-close').addEventListener('click', () => closeModal());
+    modal.querySelector('#change-password-form').addEventListener('submit', handleChangePassword);
+
+    modal.style.display = 'flex';
+    modal.style.visibility = 'visible';
+    modal.style.opacity = '1';
+    document.body.style.overflow = 'hidden';
+}
+
+async function handleChangePassword(e) {
+    e.preventDefault();
+    const currentPassword = document.getElementById('current-password').value;
+    const newPassword = document.getElementById('new-password').value;
+
+    try {
+        const response = await apiRequest('/auth/change-password', {
+            method: 'POST',
+            body: JSON.stringify({ current_password: currentPassword, new_password: newPassword })
+        });
+        closeModal();
+        showToast(response.message, 'success');
+    } catch (error) {
+        showToast(error.message || 'Failed to change password', 'error');
+    }
+}
+
 // ==================== Dashboard ====================
 
 /**
@@ -922,12 +1389,21 @@ async function revokeCredential(credentialId) {
     if (!confirm('Are you sure you want to revoke this credential? This action cannot be undone.')) {
         return;
     }
+
+    const reasons = ['misconduct', 'issued_in_error', 'superseded', 'holder_request'];
+    const reason = prompt(`Reason for revocation (${reasons.join(', ')}):`, 'issued_in_error');
+    if (!reason || !reasons.includes(reason.trim())) {
+        showToast('A valid revocation reason is required', 'error');
+        return;
+    }
+    const note = prompt('Note for the credential history (optional):') || null;
     
     setLoading(true, 'Revoking credential...');
     
     try {
         await apiRequest(`/credentials/${credentialId}/revoke`, {
-            method: 'POST'
+            method: 'POST',
+            body: JSON.stringify({ reason: reason.trim(), note })
         });
         
         showToast('Credential revoked successfully', 'success');
@@ -1783,6 +2259,8 @@ function init() {
     initVerifyTabs();
     initPasswordToggles();
     initFormEnhancements();
+    handleEmailLinks();
+    handleOAuthAuthorizeLink();
     
     // Event listeners
     const loginForm = document.getElementById('login-form');
@@ -1804,6 +2282,11 @@ function init() {
     if (logoutBtn) {
         logoutBtn.addEventListener('click', handleLogout);
     }
+
+    const logoutAllBtn = document.getElementById('logout-all-btn');
+    if (logoutAllBtn) {
+        logoutAllBtn.addEventListener('click', handleLogoutAll);
+    }
     
     const getStartedBtn = document.querySelector('.nav-auth-buttons .btn:last-child');
     console.log('Looking for sign up button:', getStartedBtn);
@@ -1828,6 +2311,10 @@ window.showSection = showSection;
 window.viewCredential = viewCredential;
 window.downloadQR = downloadQR;
 window.handleLogout = handleLogout;
+window.handleLogoutAll = handleLogoutAll;
+window.handleForgotPassword = handleForgotPassword;
+window.handleWalletLogin = handleWalletLogin;
+window.resendVerificationEmail = resendVerificationEmail;
 window.handleGetStarted = function() {
     console.log('handleGetStarted called');
 
@@ -2070,22 +2557,7 @@ async function handleLogin(e) {
             body: JSON.stringify({ email, password })
         });
 
-        saveAuthData(response.token, {
-            id: response.user.id,
-            email: response.user.email,
-            role: response.user.role,
-            name: response.user.name
-        });
-
-        showToast('Login successful!', 'success');
-        
-        // Close modal immediately and update navigation
-        closeModal();
-        updateNavigation();
-        
-        // Show dashboard
-        showDashboard();
-        
+        await completeLogin(response);
     } catch (error) {
         showToast(error.message || 'Login failed', 'error');
 
@@ -2105,6 +2577,93 @@ async function handleLogin(e) {
     }
 }
 
+/**
+ * Finish a login response: ask for the second factor if required, then store the session
+ */
+async function completeLogin(response) {
+    // Accounts with two-factor authentication need a second step
+    if (response.mfa_required) {
+        const code = prompt('Enter the code from your authenticator app, or a recovery code:');
+        if (!code) {
+            showToast('Login cancelled', 'info');
+            return;
+        }
+        response = await apiRequest('/auth/login/mfa', {
+            method: 'POST',
+            body: JSON.stringify({ challenge_token: response.challenge_token, code: code.trim() })
+        });
+    }
+
+    saveAuthData(response.token, {
+        id: response.user.id,
+        email: response.user.email,
+        role: response.user.role,
+        name: response.user.name,
+        is_verified: response.user.is_verified
+    }, response.refresh_token);
+
+    showToast('Login successful!', 'success');
+
+    // Close modal immediately and update navigation
+    closeModal();
+    updateNavigation();
+
+    // Show dashboard
+    showDashboard();
+
+    // Continue a partner sign-in that was waiting for this login
+    resumeOAuthAuthorization();
+}
+
+/**
+ * Log in by signing a challenge with a Substrate wallet extension (e.g. polkadot{.js})
+ */
+async function handleWalletLogin() {
+    const extensions = window.injectedWeb3 || {};
+    const extension = extensions['polkadot-js'] || Object.values(extensions)[0];
+    if (!extension) {
+        showToast('Install a Substrate wallet extension such as polkadot{.js} to sign in with your wallet', 'warning');
+        return;
+    }
+
+    setLoading(true, 'Waiting for wallet...');
+
+    try {
+        const injected = await extension.enable('Safari Skills Passport');
+        const accounts = await injected.accounts.get();
+        if (!accounts.length) {
+            showToast('Your wallet extension has no accounts', 'warning');
+            return;
+        }
+
+        const address = accounts.length === 1
+            ? accounts[0].address
+            : prompt(`Wallet address to sign in with:\n${accounts.map(a => `${a.name || 'Account'}: ${a.address}`).join('\n')}`, accounts[0].address);
+        if (!address) return;
+
+        const challenge = await apiRequest('/auth/wallet/challenge', {
+            method: 'POST',
+            body: JSON.stringify({ wallet_address: address.trim() })
+        });
+
+        const data = '0x' + Array.from(new TextEncoder().encode(challenge.message))
+            .map(byte => byte.toString(16).padStart(2, '0'))
+            .join('');
+        const { signature } = await injected.signer.signRaw({ address: address.trim(), data, type: 'bytes' });
+
+        const response = await apiRequest('/auth/wallet/login', {
+            method: 'POST',
+            body: JSON.stringify({ nonce: challenge.nonce, signature })
+        });
+
+        await completeLogin(response);
+    } catch (error) {
+        showToast(error.message || 'Wallet login failed', 'error');
+    } finally {
+        setLoading(false);
+    }
+}
+
 // Enhanced register handler with validation
 async function handleRegister(e) {
     e.preventDefault();
@@ -2152,10 +2711,11 @@ async function handleRegister(e) {
             id: response.user.id,
             email: response.user.email,
             role: response.user.role,
-            name: response.user.name
-        });
+            name: response.user.name,
+            is_verified: response.user.is_verified
+        }, response.refresh_token);
 
-        showToast('Account created successfully!', 'success');
+        showToast('Account created! Check your email to verify your address.', 'success');
         
         // Close modal immediately and update navigation
         closeModal();
@@ -79,7 +79,7 @@ echo ""
 echo "🎉 Setup complete!"
 echo ""
 echo "Next steps:"
-echo "1. Update .env with your configuration (JWT_SECRET, etc.)"
+echo "1. Update .env with your configuration (KEYSTORE_MASTER_KEY, etc.)"
 echo "2. Start the server: cargo run --bin api-server"
 echo "3. Access API at: http://localhost:8080"
 echo "4. Check health: curl http://localhost:8080/health"